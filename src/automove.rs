@@ -0,0 +1,399 @@
+use std::collections::HashSet;
+
+use rltk::{DijkstraMap, Rltk};
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::{
+    components::{
+        CombatStats, Confusion, Door, EntryTrigger, Hidden, Item, Monster, Name, Position,
+    },
+    gamelog::{GameLog, LogCategory},
+    map::map::{AvoidingMap, Map, TileType},
+    player::{self, PlayerAction},
+    state::RunState,
+};
+
+/// Ways the player can move for several turns on their own
+#[derive(Clone)]
+pub enum AutoMode {
+    /// Head for the closest unexplored tile
+    Explore,
+    /// Walk in a straight line until the surroundings change
+    Run {
+        delta_x: i32,
+        delta_y: i32,
+        open_neighbours: Option<usize>,
+    },
+    /// Follow a path over explored tiles
+    Travel { path: Vec<usize> },
+}
+
+/// Set while the player moves on their own, with everything seen whenever moving started
+#[derive(Default)]
+pub struct AutoMove {
+    pub mode: Option<AutoMode>,
+    known: HashSet<Entity>,
+    last_hp: u32,
+}
+
+pub fn start_explore(ecs: &mut World) -> RunState {
+    start(ecs, AutoMode::Explore)
+}
+
+pub fn start_run(ecs: &mut World, delta_x: i32, delta_y: i32) -> RunState {
+    start(
+        ecs,
+        AutoMode::Run {
+            delta_x,
+            delta_y,
+            open_neighbours: None,
+        },
+    )
+}
+
+/// Travels to the tile along explored tiles only
+pub fn start_travel(ecs: &mut World, target: Position) -> RunState {
+    let path = {
+        let player_pos = *ecs.fetch::<Position>();
+        let map = ecs.fetch::<Map>();
+        travel_path(ecs, &map, player_pos, target)
+    };
+    match path {
+        None => {
+            ecs.fetch_mut::<GameLog>()
+                .log(LogCategory::System, "You don't know a way there.");
+            RunState::AwaitingInput
+        }
+        Some(path) => start(ecs, AutoMode::Travel { path }),
+    }
+}
+
+pub fn travel_to_stairs(ecs: &mut World) -> RunState {
+    let stairs = {
+        let map = ecs.fetch::<Map>();
+        map.tiles
+            .iter()
+            .enumerate()
+            .find(|(idx, tile)| **tile == TileType::DownStairs && map.revealed_tiles[*idx])
+            .map(|(idx, _)| map.xy_from_index(&idx))
+    };
+    match stairs {
+        None => {
+            ecs.fetch_mut::<GameLog>()
+                .log(LogCategory::System, "You haven't found the stairs yet.");
+            RunState::AwaitingInput
+        }
+        Some((x, y)) => start_travel(ecs, Position { x, y }),
+    }
+}
+
+// Moving on your own is refused while there is a monster in view
+fn start(ecs: &mut World, mode: AutoMode) -> RunState {
+    let visible = visible_entities(ecs);
+    if visible
+        .iter()
+        .any(|(_, interest)| *interest == Interest::Monster)
+    {
+        ecs.fetch_mut::<GameLog>()
+            .log(LogCategory::System, "Not with enemies in view!");
+        return RunState::AwaitingInput;
+    }
+
+    let hp = player_hp(ecs);
+    let mut auto_move = ecs.fetch_mut::<AutoMove>();
+    auto_move.mode = Some(mode);
+    auto_move.last_hp = hp;
+    auto_move
+        .known
+        .extend(visible.into_iter().map(|(entity, _)| entity));
+    RunState::AutoMove
+}
+
+/// Takes one more step, or stops if something needs the player's attention
+pub fn step(ecs: &mut World, ctx: &Rltk) -> RunState {
+    if ctx.key.is_some() || ctx.left_click {
+        return stop(ecs, Some("You stop.".to_string()));
+    }
+    if let Some(message) = danger(ecs) {
+        return stop(ecs, Some(message));
+    }
+
+    let mode = match ecs.fetch::<AutoMove>().mode.clone() {
+        None => return RunState::AwaitingInput,
+        Some(mode) => mode,
+    };
+    let player_pos = *ecs.fetch::<Position>();
+    let next_step = match mode {
+        AutoMode::Explore => {
+            let next_step = {
+                let map = ecs.fetch::<Map>();
+                explore_step(ecs, &map, player_pos)
+            };
+            match next_step {
+                None => {
+                    return stop(
+                        ecs,
+                        Some("There is nothing left to explore here.".to_string()),
+                    )
+                }
+                Some(next_step) => next_step,
+            }
+        }
+        AutoMode::Run {
+            delta_x,
+            delta_y,
+            open_neighbours,
+        } => {
+            let (surroundings, on_stairs) = {
+                let map = ecs.fetch::<Map>();
+                let player_idx = map.index_from_xy(player_pos.x, player_pos.y);
+                (
+                    run_surroundings(ecs, &map, player_pos, delta_x, delta_y),
+                    map.tiles[player_idx] == TileType::DownStairs,
+                )
+            };
+            match surroundings {
+                None => return stop(ecs, None),
+                // Running stops on the stairs, but can start from them
+                Some(_) if on_stairs && open_neighbours.is_some() => return stop(ecs, None),
+                Some(open) if open_neighbours.is_some_and(|before| before != open) => {
+                    return stop(ecs, None)
+                }
+                Some(open) => {
+                    ecs.fetch_mut::<AutoMove>().mode = Some(AutoMode::Run {
+                        delta_x,
+                        delta_y,
+                        open_neighbours: Some(open),
+                    });
+                    (delta_x, delta_y)
+                }
+            }
+        }
+        AutoMode::Travel { mut path } => {
+            let next_step = {
+                let map = ecs.fetch::<Map>();
+                let player_idx = map.index_from_xy(player_pos.x, player_pos.y);
+                while path.first() == Some(&player_idx) {
+                    path.remove(0);
+                }
+                path.first()
+                    .filter(|next| !map.blocked[**next])
+                    .map(|next| {
+                        let (x, y) = map.xy_from_index(next);
+                        (
+                            x as i32 - player_pos.x as i32,
+                            y as i32 - player_pos.y as i32,
+                        )
+                    })
+            };
+            let arrived = path.is_empty();
+            ecs.fetch_mut::<AutoMove>().mode = Some(AutoMode::Travel { path });
+            match next_step {
+                None if arrived => return stop(ecs, None),
+                None => return stop(ecs, Some("Something is in the way.".to_string())),
+                Some(next_step) => next_step,
+            }
+        }
+    };
+
+    let (delta_x, delta_y) = next_step;
+    match player::perform(ecs, PlayerAction::Move { delta_x, delta_y }) {
+        RunState::PlayerTurn => RunState::PlayerTurn,
+        other => {
+            ecs.fetch_mut::<AutoMove>().mode = None;
+            other
+        }
+    }
+}
+
+fn stop(ecs: &mut World, message: Option<String>) -> RunState {
+    ecs.fetch_mut::<AutoMove>().mode = None;
+    if let Some(message) = message {
+        ecs.fetch_mut::<GameLog>().log(LogCategory::System, message);
+    }
+    RunState::AwaitingInput
+}
+
+fn player_hp(ecs: &World) -> u32 {
+    let player = ecs.fetch::<Entity>();
+    ecs.read_storage::<CombatStats>()
+        .get(*player)
+        .map_or(0, |stats| stats.hp)
+}
+
+// Anything that should interrupt moving on your own, with the reason
+fn danger(ecs: &mut World) -> Option<String> {
+    let hp = player_hp(ecs);
+    let player = *ecs.fetch::<Entity>();
+    {
+        let mut auto_move = ecs.fetch_mut::<AutoMove>();
+        let hurt = hp < auto_move.last_hp;
+        auto_move.last_hp = hp;
+        if hurt {
+            return Some("You are hurt!".to_string());
+        }
+    }
+    if ecs.read_storage::<Confusion>().contains(player) {
+        return Some("You are too confused to go on.".to_string());
+    }
+
+    let auto_move = ecs.fetch::<AutoMove>();
+    let (entity, interest) = visible_entities(ecs)
+        .into_iter()
+        .find(|(entity, interest)| {
+            *interest == Interest::Monster || !auto_move.known.contains(entity)
+        })?;
+    let name = ecs
+        .read_storage::<Name>()
+        .get(entity)
+        .map_or("something".to_string(), |name| name.name.clone());
+    Some(match interest {
+        Interest::Monster => format!("You see a {}.", name),
+        Interest::Item => format!("You find a {}.", name),
+        Interest::Trap => format!("You spot a {}.", name),
+    })
+}
+
+#[derive(PartialEq)]
+enum Interest {
+    Monster,
+    Item,
+    Trap,
+}
+
+// Monsters, items and spotted traps the player can see right now
+fn visible_entities(ecs: &World) -> Vec<(Entity, Interest)> {
+    let map = ecs.fetch::<Map>();
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let monsters = ecs.read_storage::<Monster>();
+    let items = ecs.read_storage::<Item>();
+    let traps = ecs.read_storage::<EntryTrigger>();
+
+    (&entities, &positions, !&hidden)
+        .join()
+        .filter(|(_, pos, _)| map.visible_tiles[map.index_from_xy(pos.x, pos.y)])
+        .filter_map(|(entity, _, _)| {
+            if monsters.contains(entity) {
+                Some((entity, Interest::Monster))
+            } else if items.contains(entity) {
+                Some((entity, Interest::Item))
+            } else if traps.contains(entity) {
+                Some((entity, Interest::Trap))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// Spotted traps are never stepped on deliberately
+fn known_traps(ecs: &World, map: &Map) -> HashSet<usize> {
+    (
+        &ecs.read_storage::<EntryTrigger>(),
+        &ecs.read_storage::<Position>(),
+        !&ecs.read_storage::<Hidden>(),
+    )
+        .join()
+        .map(|(_, pos, _)| map.index_from_xy(pos.x, pos.y))
+        .collect()
+}
+
+// Walks down a Dijkstra map flowing out of every unrevealed tile that can be walked on
+fn explore_step(ecs: &World, map: &Map, player_pos: Position) -> Option<(i32, i32)> {
+    let targets: Vec<usize> = (0..map.tiles.len())
+        .filter(|idx| !map.revealed_tiles[*idx] && map.tiles[*idx] != TileType::Wall)
+        .collect();
+    if targets.is_empty() {
+        return None;
+    }
+
+    let avoid = known_traps(ecs, map);
+    let pathing_map = AvoidingMap { map, avoid: &avoid };
+    let dijkstra = DijkstraMap::new(
+        map.width as usize,
+        map.height as usize,
+        &targets,
+        &pathing_map,
+        map.tiles.len() as f32,
+    );
+    let player_idx = map.index_from_xy(player_pos.x, player_pos.y);
+    let next = DijkstraMap::find_lowest_exit(&dijkstra, player_idx, &pathing_map)?;
+    if dijkstra.map[next] == f32::MAX {
+        return None;
+    }
+
+    let (next_x, next_y) = map.xy_from_index(&next);
+    Some((
+        next_x as i32 - player_pos.x as i32,
+        next_y as i32 - player_pos.y as i32,
+    ))
+}
+
+// A* restricted to explored tiles, so travelling never reveals anything new
+fn travel_path(ecs: &World, map: &Map, from: Position, to: Position) -> Option<Vec<usize>> {
+    if to.x >= map.width || to.y >= map.height {
+        return None;
+    }
+    let target_idx = map.index_from_xy(to.x, to.y);
+    if !map.revealed_tiles[target_idx] || map.tiles[target_idx] == TileType::Wall {
+        return None;
+    }
+
+    let mut avoid = known_traps(ecs, map);
+    avoid.extend((0..map.tiles.len()).filter(|idx| !map.revealed_tiles[*idx]));
+    let path = rltk::a_star_search(
+        map.index_from_xy(from.x, from.y),
+        target_idx,
+        &AvoidingMap { map, avoid: &avoid },
+    );
+    if !path.success || path.steps.len() < 2 {
+        return None;
+    }
+    Some(path.steps)
+}
+
+/// How many walkable tiles surround the player, or None if running has to stop right here
+fn run_surroundings(
+    ecs: &World,
+    map: &Map,
+    player_pos: Position,
+    delta_x: i32,
+    delta_y: i32,
+) -> Option<usize> {
+    let next_x = player_pos.x as i32 + delta_x;
+    let next_y = player_pos.y as i32 + delta_y;
+    if next_x < 0 || next_y < 0 || next_x >= map.width as i32 || next_y >= map.height as i32 {
+        return None;
+    }
+    let next_idx = map.index_from_xy(next_x as u16, next_y as u16);
+    if map.blocked[next_idx] || known_traps(ecs, map).contains(&next_idx) {
+        return None;
+    }
+    // Doors are opened on purpose, not by running into them
+    let doors = ecs.read_storage::<Door>();
+    if map.tile_content[next_idx]
+        .iter()
+        .any(|entity| doors.contains(*entity))
+    {
+        return None;
+    }
+
+    let mut open = 0;
+    for y in player_pos.y as i32 - 1..=player_pos.y as i32 + 1 {
+        for x in player_pos.x as i32 - 1..=player_pos.x as i32 + 1 {
+            if (x, y) == (player_pos.x as i32, player_pos.y as i32) {
+                continue;
+            }
+            if x < 0 || y < 0 || x >= map.width as i32 || y >= map.height as i32 {
+                continue;
+            }
+            let idx = map.index_from_xy(x as u16, y as u16);
+            if map.revealed_tiles[idx] && map.tiles[idx] != TileType::Wall {
+                open += 1;
+            }
+        }
+    }
+    Some(open)
+}
@@ -0,0 +1,75 @@
+use specs::World;
+
+use crate::{components::Position, map::map::Map};
+
+// Part of the console the map is drawn on, the rest is taken by the UI
+pub const VIEW_WIDTH: u16 = 80;
+pub const VIEW_HEIGHT: u16 = 43;
+
+/// Translates between world coordinates and the part of the console the map is drawn on
+#[derive(Clone, Copy)]
+pub struct Camera {
+    min_x: i32,
+    min_y: i32,
+    map_width: u16,
+    map_height: u16,
+}
+
+impl Camera {
+    /// Centers the view on the target, but never scrolls past the edges of the map
+    pub fn new(map: &Map, target: Position) -> Self {
+        Self {
+            min_x: Camera::offset(target.x, map.width, VIEW_WIDTH),
+            min_y: Camera::offset(target.y, map.height, VIEW_HEIGHT),
+            map_width: map.width,
+            map_height: map.height,
+        }
+    }
+
+    /// The camera that follows the player
+    pub fn from_ecs(ecs: &World) -> Self {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Position>();
+        Camera::new(&map, *player_pos)
+    }
+
+    fn offset(target: u16, map_size: u16, view_size: u16) -> i32 {
+        if map_size <= view_size {
+            return 0;
+        }
+        let offset = target as i32 - view_size as i32 / 2;
+        offset.clamp(0, (map_size - view_size) as i32)
+    }
+
+    pub fn to_screen(self, x: u16, y: u16) -> Option<(i32, i32)> {
+        let screen_x = x as i32 - self.min_x;
+        let screen_y = y as i32 - self.min_y;
+        if Camera::is_on_screen(screen_x, screen_y) {
+            Some((screen_x, screen_y))
+        } else {
+            None
+        }
+    }
+
+    pub fn to_world(self, screen_x: i32, screen_y: i32) -> Option<Position> {
+        if !Camera::is_on_screen(screen_x, screen_y) {
+            return None;
+        }
+        let x = screen_x + self.min_x;
+        let y = screen_y + self.min_y;
+        if x >= self.map_width as i32 || y >= self.map_height as i32 {
+            return None;
+        }
+        Some(Position {
+            x: x as u16,
+            y: y as u16,
+        })
+    }
+
+    fn is_on_screen(screen_x: i32, screen_y: i32) -> bool {
+        screen_x >= 0
+            && screen_y >= 0
+            && screen_x < VIEW_WIDTH as i32
+            && screen_y < VIEW_HEIGHT as i32
+    }
+}
@@ -1,14 +1,16 @@
+use std::collections::BTreeMap;
+
 use rltk::{Point, RGB};
 use serde::{Deserialize, Serialize};
 use specs::error::NoError;
 use specs::prelude::*;
-use specs::saveload::{ConvertSaveload, Marker, SimpleMarker};
+use specs::saveload::{ConvertSaveload, Marker, SimpleMarker, SimpleMarkerAllocator};
 use specs_derive::*;
 
 use crate::gamelog::GameLog;
 use crate::map::map::Map;
 
-#[derive(Component, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Component, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
 pub struct Position {
     pub x: u16,
     pub y: u16,
@@ -67,6 +69,11 @@ pub struct Name {
     pub name: String,
 }
 
+#[derive(Component, ConvertSaveload, Clone)]
+pub struct Description {
+    pub text: String,
+}
+
 #[derive(Component, Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct BlocksTile {}
 
@@ -102,21 +109,68 @@ pub struct WantsToMelee {
 #[derive(Component, ConvertSaveload, Clone)]
 pub struct SufferDamage {
     pub amount: Vec<u32>,
+    /// Whatever dealt each amount, so a death can be put down to someone
+    pub causes: Vec<String>,
+    pub by_player: bool,
 }
 
 impl SufferDamage {
-    pub fn new_damage(store: &mut WriteStorage<SufferDamage>, victim: Entity, amount: u32) {
+    pub fn new_damage(
+        store: &mut WriteStorage<SufferDamage>,
+        victim: Entity,
+        amount: u32,
+        cause: &str,
+        by_player: bool,
+    ) {
         if let Some(suffering) = store.get_mut(victim) {
             suffering.amount.push(amount);
+            suffering.causes.push(cause.to_string());
+            suffering.by_player |= by_player;
         } else {
             let dmg = SufferDamage {
                 amount: vec![amount],
+                causes: vec![cause.to_string()],
+                by_player,
             };
             store.insert(victim, dmg).expect("Unable to insert damage");
         }
     }
 }
 
+/// Things worth remembering about the player's run
+#[derive(Component, Serialize, Deserialize, Clone, Default)]
+pub struct RunStats {
+    pub kills: BTreeMap<String, u32>,
+    pub deepest_depth: u32,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub mode: GameMode,
+    /// What dealt the killing blow, set when the player dies
+    #[serde(default)]
+    pub killed_by: Option<String>,
+}
+
+/// How forgiving a run is about its saves, chosen in the settings when the run starts
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    /// Saves can be loaded any number of times, as in any other game
+    #[default]
+    Casual,
+    /// Loading uses the save up, dying deletes it and the pause menu can't reload
+    Strict,
+}
+
+impl GameMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Casual => "Casual",
+            GameMode::Strict => "Strict",
+        }
+    }
+}
+
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Item {}
 
@@ -253,6 +307,130 @@ pub struct EntityMoved {}
 #[derive(Component, Serialize, Deserialize, Clone, Copy)]
 pub struct SingleActivation {}
 
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct Wallet {
+    pub gold: u32,
+}
+
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct Gold {
+    pub amount: u32,
+}
+
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct BaseValue {
+    pub value: u32,
+}
+
+impl BaseValue {
+    pub fn buy_price(&self) -> u32 {
+        self.value
+    }
+
+    // Vendors only pay half of the base value
+    pub fn sell_price(&self) -> u32 {
+        u32::max(1, self.value / 2)
+    }
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy)]
+pub struct Vendor {}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum RarityTier {
+    Common,
+    Magic,
+    Rare,
+}
+
+impl RarityTier {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RarityTier::Common => "Common",
+            RarityTier::Magic => "Magic",
+            RarityTier::Rare => "Rare",
+        }
+    }
+
+    pub fn color(&self) -> RGB {
+        match self {
+            RarityTier::Common => RGB::named(rltk::WHITE),
+            RarityTier::Magic => RGB::named(rltk::DODGER_BLUE),
+            RarityTier::Rare => RGB::named(rltk::ORANGE),
+        }
+    }
+}
+
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct Rarity {
+    pub tier: RarityTier,
+}
+
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct Resistance {
+    pub percent: u32,
+}
+
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct LifeRegen {
+    pub interval: u32,
+    pub countdown: u32,
+}
+
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct VisionBonus {
+    pub range: u16,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy)]
+pub struct LeavesCorpse {}
+
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct Freshness {
+    pub turns: u32,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy)]
+pub struct Teleports {}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy)]
+pub struct Alarm {}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy)]
+pub struct Pit {}
+
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct Alerted {
+    pub turns: u32,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy)]
+pub struct WantsToDescend {}
+
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct Door {
+    pub open: bool,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy)]
+pub struct Locked {}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy)]
+pub struct BlocksVisibility {}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy)]
+pub struct Key {}
+
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct WantsToOpenDoor {
+    pub door: Entity,
+}
+
+#[derive(Component, ConvertSaveload, Clone, Copy)]
+pub struct WantsToCloseDoor {
+    pub door: Entity,
+}
+
 pub fn register_components(ecs: &mut World) {
     ecs.register::<Position>();
     ecs.register::<Renderable>();
@@ -260,10 +438,12 @@ pub fn register_components(ecs: &mut World) {
     ecs.register::<Viewshed>();
     ecs.register::<Monster>();
     ecs.register::<Name>();
+    ecs.register::<Description>();
     ecs.register::<BlocksTile>();
     ecs.register::<CombatStats>();
     ecs.register::<WantsToMelee>();
     ecs.register::<SufferDamage>();
+    ecs.register::<RunStats>();
     ecs.register::<Item>();
     ecs.register::<ProvidesHealing>();
     ecs.register::<InBackpack>();
@@ -291,4 +471,33 @@ pub fn register_components(ecs: &mut World) {
     ecs.register::<EntryTrigger>();
     ecs.register::<EntityMoved>();
     ecs.register::<SingleActivation>();
+    ecs.register::<Wallet>();
+    ecs.register::<Gold>();
+    ecs.register::<BaseValue>();
+    ecs.register::<Vendor>();
+    ecs.register::<Rarity>();
+    ecs.register::<Resistance>();
+    ecs.register::<LifeRegen>();
+    ecs.register::<VisionBonus>();
+    ecs.register::<LeavesCorpse>();
+    ecs.register::<Freshness>();
+    ecs.register::<Teleports>();
+    ecs.register::<Alarm>();
+    ecs.register::<Pit>();
+    ecs.register::<Alerted>();
+    ecs.register::<WantsToDescend>();
+    ecs.register::<Door>();
+    ecs.register::<Locked>();
+    ecs.register::<BlocksVisibility>();
+    ecs.register::<Key>();
+    ecs.register::<WantsToOpenDoor>();
+    ecs.register::<WantsToCloseDoor>();
+}
+
+/// A world with every component registered and nothing in it yet
+pub fn new_world() -> World {
+    let mut ecs = World::new();
+    register_components(&mut ecs);
+    ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    ecs
 }
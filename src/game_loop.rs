@@ -1,12 +1,21 @@
 use specs::{Entity, World, WorldExt};
 
 use crate::{
-    components::CombatStats,
-    gui, level,
-    menu::{game_over, inventory_menu, main_menu::MainMenuSelection, pause_menu, target_menu},
+    automove::{self, AutoMove},
+    components::{CombatStats, WantsToDescend},
+    gamelog::GameLog,
+    gui,
+    history::{self, RunRecord},
+    level,
+    menu::{
+        character_menu, game_over, help_menu, inventory_menu, log_menu, look_menu,
+        main_menu::MainMenuSelection, overview_menu, pause_menu, target_menu, trade_menu,
+    },
+    morgue::Morgue,
     player,
+    replay::{self, Event, Recorder},
     state::{GlobalState, RunState},
-    systems::Systems,
+    systems::{autosave, lifetime_system::LifetimeClock, saveload_system, Systems},
 };
 
 pub fn next_iteration(
@@ -15,6 +24,8 @@ pub fn next_iteration(
     phase: RunState,
     mut systems: Systems,
 ) -> GlobalState {
+    autosave::poll(ecs);
+
     match phase {
         RunState::NewGame => {}
         _ => {
@@ -23,34 +34,40 @@ pub fn next_iteration(
     }
 
     let next_phase = match phase {
-        RunState::PreRun => {
-            systems.run(ecs, &ctx);
-            RunState::AwaitingInput
+        RunState::PreRun | RunState::PlayerTurn | RunState::MonsterTurn => {
+            let elapsed_ms = ecs.fetch_mut::<LifetimeClock>().elapsed(ctx.frame_time_ms);
+            let next_phase = run_turn_phase(ecs, &mut systems, phase, elapsed_ms);
+            if let RunState::Dead { .. } = next_phase {
+                record_death(ecs);
+            }
+            next_phase
         }
         RunState::AwaitingInput => {
-            systems.run_during_pause(ecs, &ctx);
+            ecs.fetch_mut::<LifetimeClock>().wait(ctx.frame_time_ms);
+            systems.run_during_pause(ecs);
             player::player_input(ecs, ctx)
         }
-        RunState::PlayerTurn => {
-            systems.run(ecs, &ctx);
-            RunState::MonsterTurn
-        }
-        RunState::MonsterTurn => {
-            systems.run(ecs, &ctx);
-            if player_is_dead(ecs) {
-                RunState::Dead
-            } else {
-                RunState::AwaitingInput
-            }
-        }
+        RunState::AutoMove => automove::step(ecs, ctx),
         RunState::ShowInventory => inventory_menu::inventory(ecs, ctx),
         RunState::ShowDropItem => inventory_menu::drop_item_menu(ecs, ctx),
         RunState::ShowUnequipItem => inventory_menu::unequip_menu(ecs, ctx),
-        RunState::ShowTargeting { range, item } => target_menu::target_menu(ecs, ctx, range, item),
-        RunState::Dead => {
-            let result = game_over::game_over(ctx);
+        RunState::ShowTrade { vendor, mode } => trade_menu::trade_menu(ecs, ctx, vendor, mode),
+        RunState::Look { x, y, index } => look_menu::look_menu(ecs, ctx, x, y, index),
+        RunState::ShowCharacter => character_menu::character_menu(ecs, ctx),
+        RunState::ShowOverview { cursor, editing } => {
+            overview_menu::overview_menu(ecs, ctx, cursor, editing)
+        }
+        RunState::ShowHelp { page } => help_menu::help_menu(ecs, ctx, page),
+        RunState::ShowLog { scroll, filter } => log_menu::log_menu(ecs, ctx, scroll, filter),
+        RunState::ShowTargeting {
+            range,
+            item,
+            cursor,
+        } => target_menu::target_menu(ecs, ctx, range, item, cursor),
+        RunState::Dead { scroll } => {
+            let result = game_over::game_over(ecs, ctx, scroll);
             match result {
-                game_over::GameOverResult::NoSelection => RunState::Dead,
+                game_over::GameOverResult::NoSelection { scroll } => RunState::Dead { scroll },
                 game_over::GameOverResult::QuitToMenu => {
                     game_over_cleanup(ecs);
                     return GlobalState::MainMenu {
@@ -68,6 +85,11 @@ pub fn next_iteration(
             RunState::PreRun
         }
         RunState::PauseMenu { selected_menu } => pause_menu::pause_menu(ecs, ctx, selected_menu),
+        RunState::ShowSaveSlots {
+            mode,
+            selected,
+            prompt,
+        } => pause_menu::save_slots_menu(ecs, ctx, mode, selected, prompt),
     };
 
     ecs.insert(next_phase);
@@ -78,6 +100,56 @@ pub fn next_iteration(
     }
 }
 
+/// Runs the systems for a phase of the turn, the same way for the game and for replays
+///
+/// Phases that don't run the systems are handed back as they are
+pub fn run_turn_phase(
+    ecs: &mut World,
+    systems: &mut Systems,
+    phase: RunState,
+    elapsed_ms: u32,
+) -> RunState {
+    if !matches!(
+        phase,
+        RunState::PreRun | RunState::PlayerTurn | RunState::MonsterTurn
+    ) {
+        return phase;
+    }
+    replay::record(ecs, Event::Step { elapsed_ms });
+    ecs.insert(phase);
+    systems.run(ecs, elapsed_ms);
+
+    match phase {
+        RunState::PreRun => RunState::AwaitingInput,
+        RunState::PlayerTurn => {
+            ecs.fetch_mut::<GameLog>().next_turn();
+            if player_fell_through(ecs) {
+                RunState::NextLevel
+            } else {
+                RunState::MonsterTurn
+            }
+        }
+        _ => {
+            // Intents the turn left behind are settled first, so a snapshot never holds them half done
+            systems.run_during_pause(ecs);
+            if player_is_dead(ecs) {
+                replay::checkpoint(ecs);
+                RunState::Dead { scroll: 0 }
+            } else if player_fell_through(ecs) {
+                RunState::NextLevel
+            } else {
+                autosave::autosave_if_due(ecs);
+                replay::checkpoint_if_due(ecs);
+                if ecs.fetch::<AutoMove>().mode.is_some() {
+                    RunState::AutoMove
+                } else {
+                    RunState::AwaitingInput
+                }
+            }
+        }
+    }
+}
+
 fn player_is_dead(ecs: &mut World) -> bool {
     let player_entity = ecs.fetch::<Entity>();
     let combat_stats_storage = ecs.read_component::<CombatStats>();
@@ -89,6 +161,28 @@ fn player_is_dead(ecs: &mut World) -> bool {
     }
 }
 
+// The run is over, so its saves go if it was strict, and it's written up in the morgue and the history
+fn record_death(ecs: &mut World) {
+    saveload_system::forfeit_run(ecs);
+    let morgue = Morgue::record(ecs);
+    ecs.insert(morgue);
+
+    let killer = saveload_system::run_stats(ecs).killed_by;
+    let run = RunRecord::from_ecs(ecs, Some(killer.unwrap_or_else(|| "unknown".to_string())));
+    if let Err(error) = history::record_run(&run) {
+        rltk::console::log(format!("Couldn't add the run to the history: {}", error));
+    }
+}
+
+fn player_fell_through(ecs: &mut World) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    let mut wants_to_descend = ecs.write_storage::<WantsToDescend>();
+    wants_to_descend.remove(*player_entity).is_some()
+}
+
 fn game_over_cleanup(ecs: &mut World) {
+    autosave::end_session(ecs);
+    ecs.remove::<Morgue>();
+    ecs.remove::<Recorder>();
     ecs.delete_all();
 }
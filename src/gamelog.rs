@@ -1,6 +1,122 @@
+use std::collections::VecDeque;
+
+use rltk::RGB;
 use serde::{Deserialize, Serialize};
 
+// How many entries the log keeps before dropping the oldest ones
+const MAX_ENTRIES: usize = 500;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum LogCategory {
+    Combat,
+    Loot,
+    Status,
+    System,
+}
+
+impl LogCategory {
+    pub const ALL: [LogCategory; 4] = [
+        LogCategory::Combat,
+        LogCategory::Loot,
+        LogCategory::Status,
+        LogCategory::System,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogCategory::Combat => "Combat",
+            LogCategory::Loot => "Loot",
+            LogCategory::Status => "Status",
+            LogCategory::System => "System",
+        }
+    }
+
+    pub fn color(&self) -> RGB {
+        match self {
+            LogCategory::Combat => RGB::named(rltk::ORANGE),
+            LogCategory::Loot => RGB::named(rltk::GOLD),
+            LogCategory::Status => RGB::named(rltk::CYAN),
+            LogCategory::System => RGB::named(rltk::GREY),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LogFragment {
+    pub text: String,
+    pub color: RGB,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LogEntry {
+    pub turn: u32,
+    pub category: LogCategory,
+    pub fragments: Vec<LogFragment>,
+}
+
+impl LogEntry {
+    pub fn new(category: LogCategory) -> Self {
+        Self {
+            turn: 0,
+            category,
+            fragments: Vec::new(),
+        }
+    }
+
+    pub fn text<S: ToString>(self, text: S) -> Self {
+        self.colored(text, RGB::named(rltk::WHITE))
+    }
+
+    pub fn colored<S: ToString>(mut self, text: S, color: RGB) -> Self {
+        self.fragments.push(LogFragment {
+            text: text.to_string(),
+            color,
+        });
+        self
+    }
+
+    pub fn damage(self, amount: u32) -> Self {
+        self.colored(format!("{} hp", amount), RGB::named(rltk::RED))
+    }
+
+    pub fn healing(self, amount: u32) -> Self {
+        self.colored(format!("{} hp", amount), RGB::named(rltk::GREEN))
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct GameLog {
-    pub entries: Vec<String>,
+    entries: VecDeque<LogEntry>,
+    pub turn: u32,
+}
+
+impl GameLog {
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::with_capacity(MAX_ENTRIES),
+            turn: 0,
+        }
+    }
+
+    /// Stamps the entry with the current turn and adds it to the log
+    pub fn push(&mut self, mut entry: LogEntry) {
+        entry.turn = self.turn;
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Adds a single line of plain text
+    pub fn log<S: ToString>(&mut self, category: LogCategory, text: S) {
+        self.push(LogEntry::new(category).text(text));
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &LogEntry> + ExactSizeIterator {
+        self.entries.iter()
+    }
+
+    pub fn next_turn(&mut self) {
+        self.turn += 1;
+    }
 }
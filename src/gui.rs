@@ -2,28 +2,35 @@ use rltk::{Point, Rltk, RGB};
 use specs::{prelude::*, shred::Fetch};
 
 use crate::{
+    camera::{Camera, VIEW_HEIGHT, VIEW_WIDTH},
     components::{
-        CombatStats, Hidden, HungerClock, HungerState, Name, Player, Position, Renderable,
+        CombatStats, Hidden, HungerClock, HungerState, Lifetime, Name, Player, Position,
+        Renderable, Wallet,
     },
-    gamelog::GameLog,
+    gamelog::{GameLog, LogEntry},
     map::map::{Map, TileType},
+    systems::lifetime_system::LifetimeClock,
 };
 
 pub fn draw(ecs: &World, ctx: &mut Rltk) {
+    let camera = Camera::from_ecs(ecs);
     let map = ecs.fetch::<Map>();
-    draw_map(ctx, &map);
-    draw_entities(ecs, ctx, &map);
+    draw_map(ctx, &map, &camera);
+    draw_entities(ecs, ctx, &map, &camera);
     draw_ui(ecs, ctx, &map);
-    draw_tooltip(ecs, ctx, &map);
+    draw_tooltip(ecs, ctx, &map, &camera);
 }
 
-fn draw_map(ctx: &mut Rltk, map: &Fetch<Map>) {
-    let floor_fg = RGB::from_f32(0.5, 0.5, 0.5);
-    let wall_fg = RGB::from_f32(0.0, 1.0, 0.0);
-    let downstairs_fg = RGB::from_f32(0.0, 1.0, 1.0);
-    let floor = rltk::to_cp437('.');
-    let downstairs = rltk::to_cp437('>');
+/// Glyph and color of a tile type, walls on the map use a glyph that connects to their neighbours
+pub fn tile_glyph(tile: TileType) -> (rltk::FontCharType, RGB) {
+    match tile {
+        TileType::Floor => (rltk::to_cp437('.'), RGB::from_f32(0.5, 0.5, 0.5)),
+        TileType::Wall => (rltk::to_cp437('#'), RGB::from_f32(0.0, 1.0, 0.0)),
+        TileType::DownStairs => (rltk::to_cp437('>'), RGB::from_f32(0.0, 1.0, 1.0)),
+    }
+}
 
+fn draw_map(ctx: &mut Rltk, map: &Fetch<Map>, camera: &Camera) {
     let default_bg = RGB::from_f32(0., 0., 0.);
     let bloodstain_bg = RGB::from_f32(0.75, 0., 0.);
 
@@ -32,21 +39,13 @@ fn draw_map(ctx: &mut Rltk, map: &Fetch<Map>) {
         // Render a title depending upon the tile type
         if map.revealed_tiles[idx] {
             let (x, y) = map.xy_from_index(&idx);
-            let glyph;
-            let mut fg;
-            match tile {
-                TileType::Floor => {
-                    glyph = floor;
-                    fg = floor_fg;
-                }
-                TileType::Wall => {
-                    glyph = map.wall_glyph(x, y);
-                    fg = wall_fg;
-                }
-                TileType::DownStairs => {
-                    glyph = downstairs;
-                    fg = downstairs_fg;
-                }
+            let (screen_x, screen_y) = match camera.to_screen(x, y) {
+                None => continue,
+                Some(screen) => screen,
+            };
+            let (mut glyph, mut fg) = tile_glyph(*tile);
+            if *tile == TileType::Wall {
+                glyph = map.wall_glyph(x, y);
             }
             if map.bloodstains.contains(&idx) {
                 bg = bloodstain_bg;
@@ -54,26 +53,33 @@ fn draw_map(ctx: &mut Rltk, map: &Fetch<Map>) {
             if !map.visible_tiles[idx] {
                 fg = fg.to_greyscale();
             }
-            ctx.set(x, y, fg, bg, glyph);
+            ctx.set(screen_x, screen_y, fg, bg, glyph);
         }
     }
 }
 
-fn draw_entities(ecs: &World, ctx: &mut Rltk, map: &Fetch<Map>) {
+fn draw_entities(ecs: &World, ctx: &mut Rltk, map: &Fetch<Map>, camera: &Camera) {
     let positions = ecs.read_storage::<Position>();
     let renderables = ecs.read_storage::<Renderable>();
     let hidden = ecs.read_storage::<Hidden>();
+    let lifetimes = ecs.read_storage::<Lifetime>();
+    let clock = ecs.fetch::<LifetimeClock>();
 
-    let mut data = (&positions, &renderables, !&hidden)
+    // Particles that ran out while waiting for input stay in the world until the next turn
+    let mut data = (&positions, &renderables, !&hidden, lifetimes.maybe())
         .join()
+        .filter(|(_, _, _, lifetime)| !lifetime.is_some_and(|lifetime| clock.has_expired(lifetime)))
         .collect::<Vec<_>>();
-    data.sort_by(|(_, render1, _), (_, render2, _)| {
+    data.sort_by(|(_, render1, _, _), (_, render2, _, _)| {
         render2.render_order.cmp(&render1.render_order)
     });
-    for (pos, render, _hidden) in data.iter() {
+    for (pos, render, _hidden, _lifetime) in data.iter() {
         let idx = map.index_from_xy(pos.x, pos.y);
-        if map.visible_tiles[idx] {
-            ctx.set(pos.x, pos.y, render.fg, render.bg, render.glyph);
+        if !map.visible_tiles[idx] {
+            continue;
+        }
+        if let Some((screen_x, screen_y)) = camera.to_screen(pos.x, pos.y) {
+            ctx.set(screen_x, screen_y, render.fg, render.bg, render.glyph);
         }
     }
 }
@@ -81,16 +87,16 @@ fn draw_entities(ecs: &World, ctx: &mut Rltk, map: &Fetch<Map>) {
 fn draw_ui(ecs: &World, ctx: &mut Rltk, map: &Fetch<Map>) {
     ctx.draw_box(
         0,
-        map.height,
-        map.width - 1,
-        map.window_height - map.height - 1,
+        VIEW_HEIGHT,
+        VIEW_WIDTH - 1,
+        map.window_height - VIEW_HEIGHT - 1,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::BLACK),
     );
     let depth = format!("Depth: {}", map.depth);
     ctx.print_color(
         2,
-        map.height,
+        VIEW_HEIGHT,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
         &depth,
@@ -99,11 +105,23 @@ fn draw_ui(ecs: &World, ctx: &mut Rltk, map: &Fetch<Map>) {
     let combat_stats = ecs.read_storage::<CombatStats>();
     let players = ecs.read_storage::<Player>();
     let hunger_clock = ecs.read_storage::<HungerClock>();
+    let wallets = ecs.read_storage::<Wallet>();
+    for (_player, wallet) in (&players, &wallets).join() {
+        let gold = format!("Gold: {}", wallet.gold);
+        ctx.print_color(
+            2,
+            VIEW_HEIGHT - 1,
+            RGB::named(rltk::GOLD),
+            RGB::named(rltk::BLACK),
+            &gold,
+        );
+    }
+
     for (_player, stats, hunger) in (&players, &combat_stats, &hunger_clock).join() {
         let health = format!(" HP: {} / {} ", stats.hp, stats.max_hp);
         ctx.print_color(
             12,
-            map.height,
+            VIEW_HEIGHT,
             RGB::named(rltk::YELLOW),
             RGB::named(rltk::BLACK),
             &health,
@@ -111,7 +129,7 @@ fn draw_ui(ecs: &World, ctx: &mut Rltk, map: &Fetch<Map>) {
 
         ctx.draw_bar_horizontal(
             28,
-            map.height,
+            VIEW_HEIGHT,
             51,
             stats.hp,
             stats.max_hp,
@@ -122,7 +140,7 @@ fn draw_ui(ecs: &World, ctx: &mut Rltk, map: &Fetch<Map>) {
         match hunger.state {
             HungerState::WellFed => ctx.print_color(
                 71,
-                map.height - 1,
+                VIEW_HEIGHT - 1,
                 RGB::named(rltk::GREEN),
                 RGB::named(rltk::BLACK),
                 "Well fed",
@@ -130,14 +148,14 @@ fn draw_ui(ecs: &World, ctx: &mut Rltk, map: &Fetch<Map>) {
             HungerState::Normal => {}
             HungerState::Hungry => ctx.print_color(
                 71,
-                map.height - 1,
+                VIEW_HEIGHT - 1,
                 RGB::named(rltk::ORANGE),
                 RGB::named(rltk::BLACK),
                 "Hungry",
             ),
             HungerState::Starving => ctx.print_color(
                 71,
-                map.height - 1,
+                VIEW_HEIGHT - 1,
                 RGB::named(rltk::RED),
                 RGB::named(rltk::BLACK),
                 "Starving",
@@ -147,32 +165,48 @@ fn draw_ui(ecs: &World, ctx: &mut Rltk, map: &Fetch<Map>) {
 
     let log = ecs.fetch::<GameLog>();
     let mut y = map.window_height - 2;
-    for s in log.entries.iter().rev() {
-        if y <= map.height {
+    for entry in log.entries().rev() {
+        if y <= VIEW_HEIGHT {
             break;
         } else {
-            ctx.print(2, y, s);
+            draw_log_entry(ctx, 2, y, entry);
         }
         y -= 1;
     }
 }
 
-fn draw_tooltip(ecs: &World, ctx: &mut Rltk, map: &Fetch<Map>) {
+/// Prints every fragment of the entry in its own color, one after another
+pub fn draw_log_entry(ctx: &mut Rltk, x: i32, y: u16, entry: &LogEntry) {
+    let mut x = x;
+    for fragment in entry.fragments.iter() {
+        ctx.print_color(
+            x,
+            y,
+            fragment.color,
+            RGB::named(rltk::BLACK),
+            &fragment.text,
+        );
+        x += fragment.text.chars().count() as i32;
+    }
+}
+
+fn draw_tooltip(ecs: &World, ctx: &mut Rltk, map: &Fetch<Map>, camera: &Camera) {
     let names = ecs.read_storage::<Name>();
     let positions = ecs.read_storage::<Position>();
     let hidden = ecs.read_storage::<Hidden>();
 
     let mouse_pos = ctx.mouse_pos();
     ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::MAGENTA));
-    if mouse_pos.0 >= map.width as i32 || mouse_pos.1 >= map.height as i32 {
-        return;
-    }
+    let mouse_world_pos = match camera.to_world(mouse_pos.0, mouse_pos.1) {
+        None => return,
+        Some(pos) => pos,
+    };
 
     let mut tooltip: Vec<String> = Vec::new();
     for (name, position, _hidden) in (&names, &positions, !&hidden).join() {
         let idx = map.index_from_xy(position.x, position.y);
-        if position.x as i32 == mouse_pos.0
-            && position.y as i32 == mouse_pos.1
+        if position.x == mouse_world_pos.x
+            && position.y == mouse_world_pos.y
             && map.visible_tiles[idx]
         {
             tooltip.push(name.name.to_string());
@@ -191,7 +225,7 @@ fn draw_tooltip(ecs: &World, ctx: &mut Rltk, map: &Fetch<Map>) {
     }
     width += 3;
 
-    if mouse_pos.0 > (map.width / 2) as i32 {
+    if mouse_pos.0 > (VIEW_WIDTH / 2) as i32 {
         let arrow_pos = Point::new(mouse_pos.0 - 2, mouse_pos.1);
         let left_x = mouse_pos.0 - width;
         let mut y = mouse_pos.1;
@@ -0,0 +1,215 @@
+use std::cmp::Reverse;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use specs::{Entity, World, WorldExt};
+
+use crate::{
+    components::{GameMode, Name, Wallet},
+    gamelog::GameLog,
+    systems::{save_slots, saveload_system},
+};
+
+// Points for every level reached and every monster killed, gold counts one point a coin
+const POINTS_PER_DEPTH: u32 = 100;
+const POINTS_PER_KILL: u32 = 10;
+
+/// One finished run, a line of its own in the history file
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct RunRecord {
+    pub seed: u64,
+    pub character: String,
+    pub score: u32,
+    pub depth: u32,
+    pub kills: u32,
+    pub gold: u32,
+    pub turns: u32,
+    /// What ended the run, None for a run that was won
+    pub cause_of_death: Option<String>,
+    pub mode: GameMode,
+    pub timestamp: u64,
+}
+
+impl RunRecord {
+    /// The run the world holds, ended by the given cause
+    pub fn from_ecs(ecs: &World, cause_of_death: Option<String>) -> Self {
+        let player = *ecs.fetch::<Entity>();
+        let run = saveload_system::run_stats(ecs);
+        let character = ecs
+            .read_storage::<Name>()
+            .get(player)
+            .map_or_else(String::new, |name| name.name.clone());
+        let gold = ecs
+            .read_storage::<Wallet>()
+            .get(player)
+            .map_or(0, |wallet| wallet.gold);
+        let kills = run.kills.values().sum();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self {
+            seed: run.seed,
+            character,
+            score: score(run.deepest_depth, kills, gold),
+            depth: run.deepest_depth,
+            kills,
+            gold,
+            turns: ecs.fetch::<GameLog>().turn,
+            cause_of_death,
+            mode: run.mode,
+            timestamp,
+        }
+    }
+
+    pub fn fate(&self) -> &str {
+        self.cause_of_death.as_deref().unwrap_or("Victory")
+    }
+}
+
+/// A run's score, the same numbers always give the same score
+pub fn score(deepest_depth: u32, kills: u32, gold: u32) -> u32 {
+    deepest_depth
+        .saturating_mul(POINTS_PER_DEPTH)
+        .saturating_add(kills.saturating_mul(POINTS_PER_KILL))
+        .saturating_add(gold)
+}
+
+/// What the hall of fame ranks runs by
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum RunOrder {
+    Score,
+    Depth,
+    Kills,
+    Recent,
+}
+
+impl RunOrder {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RunOrder::Score => "score",
+            RunOrder::Depth => "depth",
+            RunOrder::Kills => "kills",
+            RunOrder::Recent => "date",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            RunOrder::Score => RunOrder::Depth,
+            RunOrder::Depth => RunOrder::Kills,
+            RunOrder::Kills => RunOrder::Recent,
+            RunOrder::Recent => RunOrder::Score,
+        }
+    }
+}
+
+/// Best first, ties go to the higher score and then to the earlier run
+pub fn sort_runs(runs: &mut [RunRecord], order: RunOrder) {
+    runs.sort_by_key(|run| {
+        let key = match order {
+            RunOrder::Score => u64::from(run.score),
+            RunOrder::Depth => u64::from(run.depth),
+            RunOrder::Kills => u64::from(run.kills),
+            RunOrder::Recent => run.timestamp,
+        };
+        (Reverse(key), Reverse(run.score), run.timestamp)
+    });
+}
+
+fn history_path() -> PathBuf {
+    save_slots::game_dir().join("history.jsonl")
+}
+
+/// Adds the run to the end of the history file
+pub fn record_run(run: &RunRecord) -> Result<(), io::Error> {
+    fs::create_dir_all(save_slots::game_dir())?;
+    let line = serde_json::to_string(run)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path())?;
+    writeln!(file, "{}", line)
+}
+
+/// Every run in the history file, a line that can't be read is left out
+pub fn load_history() -> Vec<RunRecord> {
+    fs::read_to_string(history_path())
+        .map(|data| {
+            data.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(score: u32, depth: u32, kills: u32, timestamp: u64) -> RunRecord {
+        RunRecord {
+            seed: timestamp,
+            character: "Player".to_string(),
+            score,
+            depth,
+            kills,
+            gold: 0,
+            turns: 0,
+            cause_of_death: Some("Orc".to_string()),
+            mode: GameMode::Casual,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn score_adds_depth_kills_and_gold() {
+        assert_eq!(score(0, 0, 0), 0);
+        assert_eq!(score(1, 0, 0), 100);
+        assert_eq!(score(0, 1, 0), 10);
+        assert_eq!(score(0, 0, 1), 1);
+        assert_eq!(score(5, 12, 37), 500 + 120 + 37);
+    }
+
+    #[test]
+    fn score_never_overflows() {
+        assert_eq!(score(u32::MAX, 0, 0), u32::MAX);
+        assert_eq!(score(1, u32::MAX, u32::MAX), u32::MAX);
+    }
+
+    #[test]
+    fn a_level_is_worth_more_than_a_kill() {
+        assert!(score(2, 0, 0) > score(1, 9, 0));
+    }
+
+    #[test]
+    fn runs_sort_best_first() {
+        let mut runs = vec![run(300, 3, 0, 1), run(500, 2, 30, 2), run(300, 3, 0, 3)];
+
+        sort_runs(&mut runs, RunOrder::Score);
+        let order: Vec<u64> = runs.iter().map(|run| run.timestamp).collect();
+        assert_eq!(order, vec![2, 1, 3]);
+
+        sort_runs(&mut runs, RunOrder::Depth);
+        let order: Vec<u64> = runs.iter().map(|run| run.timestamp).collect();
+        assert_eq!(order, vec![1, 3, 2]);
+
+        sort_runs(&mut runs, RunOrder::Kills);
+        assert_eq!(runs[0].timestamp, 2);
+
+        sort_runs(&mut runs, RunOrder::Recent);
+        let order: Vec<u64> = runs.iter().map(|run| run.timestamp).collect();
+        assert_eq!(order, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn records_round_trip_through_the_file_format() {
+        let mut won = run(1_234, 9, 40, 7);
+        won.cause_of_death = None;
+        let line = serde_json::to_string(&won).unwrap();
+        assert_eq!(serde_json::from_str::<RunRecord>(&line).unwrap(), won);
+        assert_eq!(won.fate(), "Victory");
+    }
+}
@@ -0,0 +1,530 @@
+use std::{collections::BTreeMap, fmt, fs, io};
+
+use rltk::VirtualKeyCode;
+use serde::{Deserialize, Serialize};
+
+// User config with the key bindings, read from the working directory
+const CONFIG_FILE: &str = "./keybindings.json";
+
+/// Everything the player can do from the map screen
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Action {
+    MoveNorth,
+    MoveSouth,
+    MoveWest,
+    MoveEast,
+    MoveNorthWest,
+    MoveNorthEast,
+    MoveSouthWest,
+    MoveSouthEast,
+    Pickup,
+    Inventory,
+    DropItem,
+    UnequipItem,
+    Look,
+    CharacterSheet,
+    MessageLog,
+    Descend,
+    DisarmTrap,
+    CloseDoor,
+    SkipTurn,
+    AutoExplore,
+    Overview,
+    Help,
+    Menu,
+}
+
+impl Action {
+    pub const ALL: [Action; 23] = [
+        Action::MoveNorth,
+        Action::MoveSouth,
+        Action::MoveWest,
+        Action::MoveEast,
+        Action::MoveNorthWest,
+        Action::MoveNorthEast,
+        Action::MoveSouthWest,
+        Action::MoveSouthEast,
+        Action::Pickup,
+        Action::Inventory,
+        Action::DropItem,
+        Action::UnequipItem,
+        Action::Look,
+        Action::CharacterSheet,
+        Action::MessageLog,
+        Action::Descend,
+        Action::DisarmTrap,
+        Action::CloseDoor,
+        Action::SkipTurn,
+        Action::AutoExplore,
+        Action::Overview,
+        Action::Help,
+        Action::Menu,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::MoveNorth => "Move north",
+            Action::MoveSouth => "Move south",
+            Action::MoveWest => "Move west",
+            Action::MoveEast => "Move east",
+            Action::MoveNorthWest => "Move north-west",
+            Action::MoveNorthEast => "Move north-east",
+            Action::MoveSouthWest => "Move south-west",
+            Action::MoveSouthEast => "Move south-east",
+            Action::Pickup => "Pick up an item",
+            Action::Inventory => "Use an item",
+            Action::DropItem => "Drop an item",
+            Action::UnequipItem => "Unequip an item",
+            Action::Look => "Look around",
+            Action::CharacterSheet => "Character sheet",
+            Action::MessageLog => "Message log",
+            Action::Descend => "Go down the stairs",
+            Action::DisarmTrap => "Disarm an adjacent trap",
+            Action::CloseDoor => "Close an adjacent door",
+            Action::SkipTurn => "Rest / skip turn",
+            Action::AutoExplore => "Explore automatically",
+            Action::Overview => "Map overview and notes",
+            Action::Help => "Help",
+            Action::Menu => "Save and quit menu",
+        }
+    }
+
+    pub fn direction(&self) -> Option<(i32, i32)> {
+        match self {
+            Action::MoveNorth => Some((0, -1)),
+            Action::MoveSouth => Some((0, 1)),
+            Action::MoveWest => Some((-1, 0)),
+            Action::MoveEast => Some((1, 0)),
+            Action::MoveNorthWest => Some((-1, -1)),
+            Action::MoveNorthEast => Some((1, -1)),
+            Action::MoveSouthWest => Some((-1, 1)),
+            Action::MoveSouthEast => Some((1, 1)),
+            _ => None,
+        }
+    }
+}
+
+/// Preset the config file starts from before applying its own bindings
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    Numpad,
+    Vi,
+    #[default]
+    Wasd,
+}
+
+#[derive(Deserialize, Default)]
+struct KeyBindingsConfig {
+    #[serde(default)]
+    layout: Layout,
+    #[serde(default)]
+    bindings: BTreeMap<Action, Vec<String>>,
+}
+
+pub enum KeyBindingsError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    UnknownKey {
+        action: Action,
+        key: String,
+    },
+    Conflict {
+        key: VirtualKeyCode,
+        first: Action,
+        second: Action,
+    },
+}
+
+impl fmt::Display for KeyBindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyBindingsError::Io(err) => write!(f, "Unable to read {}: {}", CONFIG_FILE, err),
+            KeyBindingsError::Parse(err) => write!(f, "Invalid {}: {}", CONFIG_FILE, err),
+            KeyBindingsError::UnknownKey { action, key } => {
+                write!(f, "Unknown key '{}' bound to {:?}", key, action)
+            }
+            KeyBindingsError::Conflict { key, first, second } => write!(
+                f,
+                "Key {} is bound to both {:?} and {:?}",
+                key_name(*key),
+                first,
+                second
+            ),
+        }
+    }
+}
+
+/// Which keys trigger which action, every key triggers at most one action
+#[derive(Clone)]
+pub struct KeyBindings {
+    bindings: BTreeMap<Action, Vec<VirtualKeyCode>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::preset(Layout::default())
+    }
+}
+
+impl KeyBindings {
+    /// The numpad and the arrow keys move in every layout, the letters depend on the layout
+    pub fn preset(layout: Layout) -> Self {
+        use VirtualKeyCode::*;
+
+        let mut bindings = BTreeMap::new();
+        let mut bind = |action: Action, keys: &[VirtualKeyCode]| {
+            bindings.insert(action, keys.to_vec());
+        };
+        let (north, south, west, east, north_west, north_east, south_west, south_east) =
+            match layout {
+                Layout::Numpad => (None, None, None, None, None, None, None, None),
+                Layout::Vi => (
+                    Some(K),
+                    Some(J),
+                    Some(H),
+                    Some(L),
+                    Some(Y),
+                    Some(U),
+                    Some(B),
+                    Some(N),
+                ),
+                Layout::Wasd => (
+                    Some(W),
+                    Some(X),
+                    Some(A),
+                    Some(D),
+                    Some(Q),
+                    Some(E),
+                    Some(Z),
+                    Some(C),
+                ),
+            };
+        let with_letter = |keys: &[VirtualKeyCode], letter: Option<VirtualKeyCode>| {
+            let mut keys = keys.to_vec();
+            keys.extend(letter);
+            keys
+        };
+        bind(Action::MoveNorth, &with_letter(&[Numpad8, Up], north));
+        bind(Action::MoveSouth, &with_letter(&[Numpad2, Down], south));
+        bind(Action::MoveWest, &with_letter(&[Numpad4, Left], west));
+        bind(Action::MoveEast, &with_letter(&[Numpad6, Right], east));
+        bind(Action::MoveNorthWest, &with_letter(&[Numpad7], north_west));
+        bind(Action::MoveNorthEast, &with_letter(&[Numpad9], north_east));
+        bind(Action::MoveSouthWest, &with_letter(&[Numpad1], south_west));
+        bind(Action::MoveSouthEast, &with_letter(&[Numpad3], south_east));
+
+        match layout {
+            Layout::Numpad => {
+                bind(Action::Pickup, &[Numpad5, G]);
+                bind(Action::UnequipItem, &[U]);
+                bind(Action::Look, &[L]);
+            }
+            Layout::Vi => {
+                bind(Action::Pickup, &[Numpad5, G, Comma]);
+                bind(Action::UnequipItem, &[V]);
+                bind(Action::Look, &[Semicolon]);
+            }
+            Layout::Wasd => {
+                bind(Action::Pickup, &[Numpad5, S]);
+                bind(Action::UnequipItem, &[U]);
+                bind(Action::Look, &[L]);
+            }
+        }
+        bind(Action::Inventory, &[I]);
+        bind(Action::DropItem, &[R]);
+        bind(Action::CharacterSheet, &[P]);
+        bind(Action::MessageLog, &[M]);
+        bind(Action::Descend, &[Period]);
+        bind(Action::DisarmTrap, &[T]);
+        bind(Action::CloseDoor, &[O]);
+        bind(Action::SkipTurn, &[Space]);
+        bind(Action::AutoExplore, &[F, Numpad0]);
+        bind(Action::Overview, &[Tab]);
+        bind(Action::Help, &[Slash, F1]);
+        bind(Action::Menu, &[Escape]);
+
+        Self { bindings }
+    }
+
+    /// Reads the config file, a missing file means the default bindings
+    pub fn load() -> Result<Self, KeyBindingsError> {
+        let data = match fs::read_to_string(CONFIG_FILE) {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(KeyBindingsError::Io(err)),
+        };
+        Self::parse(&data)
+    }
+
+    // The contents of a config file, keys it leaves out keep the layout's bindings
+    fn parse(data: &str) -> Result<Self, KeyBindingsError> {
+        let config: KeyBindingsConfig =
+            serde_json::from_str(data).map_err(KeyBindingsError::Parse)?;
+        Self::from_config(config)
+    }
+
+    // Bindings listed in the config replace the preset's keys for that action
+    fn from_config(config: KeyBindingsConfig) -> Result<Self, KeyBindingsError> {
+        let mut key_bindings = Self::preset(config.layout);
+        for (action, names) in config.bindings {
+            let keys = names
+                .into_iter()
+                .map(|name| {
+                    parse_key(&name).ok_or(KeyBindingsError::UnknownKey { action, key: name })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            key_bindings.bindings.insert(action, keys);
+        }
+        key_bindings.validate()?;
+        Ok(key_bindings)
+    }
+
+    fn validate(&self) -> Result<(), KeyBindingsError> {
+        let mut seen: Vec<(VirtualKeyCode, Action)> = Vec::new();
+        for (action, keys) in self.bindings.iter() {
+            for key in keys {
+                if let Some((_, first)) = seen.iter().find(|(seen_key, _)| seen_key == key) {
+                    return Err(KeyBindingsError::Conflict {
+                        key: *key,
+                        first: *first,
+                        second: *action,
+                    });
+                }
+                seen.push((*key, *action));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn action(&self, key: VirtualKeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    /// The movement delta of the key, if it is bound to a move
+    pub fn direction(&self, key: VirtualKeyCode) -> Option<(i32, i32)> {
+        self.action(key).and_then(|action| action.direction())
+    }
+
+    pub fn is(&self, key: VirtualKeyCode, action: Action) -> bool {
+        self.action(key) == Some(action)
+    }
+
+    pub fn keys(&self, action: Action) -> &[VirtualKeyCode] {
+        self.bindings.get(&action).map_or(&[], |keys| keys)
+    }
+
+    /// The keys of the action as shown to the player, e.g. "W, Numpad8, Up"
+    pub fn describe(&self, action: Action) -> String {
+        let keys = self.keys(action);
+        if keys.is_empty() {
+            return "(unbound)".to_string();
+        }
+        keys.iter()
+            .map(|key| key_name(*key))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+// Names used for keys in the config file and on the help screen
+const KEY_NAMES: &[(VirtualKeyCode, &str)] = &[
+    (VirtualKeyCode::A, "A"),
+    (VirtualKeyCode::B, "B"),
+    (VirtualKeyCode::C, "C"),
+    (VirtualKeyCode::D, "D"),
+    (VirtualKeyCode::E, "E"),
+    (VirtualKeyCode::F, "F"),
+    (VirtualKeyCode::G, "G"),
+    (VirtualKeyCode::H, "H"),
+    (VirtualKeyCode::I, "I"),
+    (VirtualKeyCode::J, "J"),
+    (VirtualKeyCode::K, "K"),
+    (VirtualKeyCode::L, "L"),
+    (VirtualKeyCode::M, "M"),
+    (VirtualKeyCode::N, "N"),
+    (VirtualKeyCode::O, "O"),
+    (VirtualKeyCode::P, "P"),
+    (VirtualKeyCode::Q, "Q"),
+    (VirtualKeyCode::R, "R"),
+    (VirtualKeyCode::S, "S"),
+    (VirtualKeyCode::T, "T"),
+    (VirtualKeyCode::U, "U"),
+    (VirtualKeyCode::V, "V"),
+    (VirtualKeyCode::W, "W"),
+    (VirtualKeyCode::X, "X"),
+    (VirtualKeyCode::Y, "Y"),
+    (VirtualKeyCode::Z, "Z"),
+    (VirtualKeyCode::Key0, "0"),
+    (VirtualKeyCode::Key1, "1"),
+    (VirtualKeyCode::Key2, "2"),
+    (VirtualKeyCode::Key3, "3"),
+    (VirtualKeyCode::Key4, "4"),
+    (VirtualKeyCode::Key5, "5"),
+    (VirtualKeyCode::Key6, "6"),
+    (VirtualKeyCode::Key7, "7"),
+    (VirtualKeyCode::Key8, "8"),
+    (VirtualKeyCode::Key9, "9"),
+    (VirtualKeyCode::Numpad0, "Numpad0"),
+    (VirtualKeyCode::Numpad1, "Numpad1"),
+    (VirtualKeyCode::Numpad2, "Numpad2"),
+    (VirtualKeyCode::Numpad3, "Numpad3"),
+    (VirtualKeyCode::Numpad4, "Numpad4"),
+    (VirtualKeyCode::Numpad5, "Numpad5"),
+    (VirtualKeyCode::Numpad6, "Numpad6"),
+    (VirtualKeyCode::Numpad7, "Numpad7"),
+    (VirtualKeyCode::Numpad8, "Numpad8"),
+    (VirtualKeyCode::Numpad9, "Numpad9"),
+    (VirtualKeyCode::Up, "Up"),
+    (VirtualKeyCode::Down, "Down"),
+    (VirtualKeyCode::Left, "Left"),
+    (VirtualKeyCode::Right, "Right"),
+    (VirtualKeyCode::Home, "Home"),
+    (VirtualKeyCode::End, "End"),
+    (VirtualKeyCode::PageUp, "PageUp"),
+    (VirtualKeyCode::PageDown, "PageDown"),
+    (VirtualKeyCode::Insert, "Insert"),
+    (VirtualKeyCode::Delete, "Delete"),
+    (VirtualKeyCode::Space, "Space"),
+    (VirtualKeyCode::Escape, "Escape"),
+    (VirtualKeyCode::Return, "Enter"),
+    (VirtualKeyCode::Tab, "Tab"),
+    (VirtualKeyCode::Back, "Backspace"),
+    (VirtualKeyCode::Period, "."),
+    (VirtualKeyCode::Comma, ","),
+    (VirtualKeyCode::Semicolon, ";"),
+    (VirtualKeyCode::Slash, "/"),
+    (VirtualKeyCode::Minus, "-"),
+    (VirtualKeyCode::Equals, "="),
+    (VirtualKeyCode::F1, "F1"),
+    (VirtualKeyCode::F2, "F2"),
+    (VirtualKeyCode::F3, "F3"),
+    (VirtualKeyCode::F4, "F4"),
+    (VirtualKeyCode::F5, "F5"),
+    (VirtualKeyCode::F6, "F6"),
+    (VirtualKeyCode::F7, "F7"),
+];
+
+pub fn key_name(key: VirtualKeyCode) -> &'static str {
+    KEY_NAMES
+        .iter()
+        .find(|(known, _)| *known == key)
+        .map_or("?", |(_, name)| name)
+}
+
+/// The character a key types in a text field, letters are lowercase unless shift is held
+pub fn typed_char(key: VirtualKeyCode, shift: bool) -> Option<char> {
+    if key == VirtualKeyCode::Space {
+        return Some(' ');
+    }
+    let name = key_name(key);
+    if name.chars().count() != 1 || name == "?" {
+        return None;
+    }
+    let typed = name.chars().next().unwrap();
+    if shift {
+        Some(typed)
+    } else {
+        Some(typed.to_ascii_lowercase())
+    }
+}
+
+fn parse_key(name: &str) -> Option<VirtualKeyCode> {
+    KEY_NAMES
+        .iter()
+        .find(|(_, known)| known.eq_ignore_ascii_case(name))
+        .map(|(key, _)| *key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUTS: [Layout; 3] = [Layout::Numpad, Layout::Vi, Layout::Wasd];
+
+    #[test]
+    fn presets_have_no_conflicts() {
+        for layout in LAYOUTS {
+            let bindings = KeyBindings::preset(layout);
+            assert!(bindings.validate().is_ok());
+            for action in Action::ALL {
+                assert!(!bindings.keys(action).is_empty(), "{:?} is unbound", action);
+            }
+        }
+    }
+
+    #[test]
+    fn duplicated_key_is_a_conflict() {
+        let error = KeyBindings::parse(r#"{"bindings": {"Pickup": ["I"]}}"#)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            KeyBindingsError::Conflict {
+                key: VirtualKeyCode::I,
+                first: Action::Pickup,
+                second: Action::Inventory,
+            }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Key I is bound to both Pickup and Inventory"
+        );
+
+        // Clashing with a key the layout moves with counts too
+        let error = KeyBindings::parse(r#"{"layout": "vi", "bindings": {"Look": ["k"]}}"#)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            KeyBindingsError::Conflict {
+                key: VirtualKeyCode::K,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn partial_config_keeps_the_layout() {
+        let bindings = KeyBindings::parse("{}").ok().unwrap();
+        assert!(bindings.is(VirtualKeyCode::W, Action::MoveNorth));
+
+        let bindings = KeyBindings::parse(r#"{"layout": "vi"}"#).ok().unwrap();
+        assert!(bindings.is(VirtualKeyCode::K, Action::MoveNorth));
+        assert!(bindings.is(VirtualKeyCode::Up, Action::MoveNorth));
+
+        let bindings = KeyBindings::parse(r#"{"bindings": {"Descend": ["Enter", "pagedown"]}}"#)
+            .ok()
+            .unwrap();
+        assert_eq!(
+            bindings.keys(Action::Descend),
+            &[VirtualKeyCode::Return, VirtualKeyCode::PageDown]
+        );
+        assert_eq!(bindings.action(VirtualKeyCode::Period), None);
+        assert!(bindings.is(VirtualKeyCode::I, Action::Inventory));
+    }
+
+    #[test]
+    fn unknown_names_are_readable_errors() {
+        let error = KeyBindings::parse(r#"{"bindings": {"Descend": ["Hyper"]}}"#)
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "Unknown key 'Hyper' bound to Descend");
+
+        for data in [
+            r#"{"bindings": {"Fly": ["F"]}}"#,
+            r#"{"layout": "dvorak"}"#,
+            r#"{"bindings": {"Descend": "Enter"}}"#,
+            "{",
+        ] {
+            let error = KeyBindings::parse(data).err().unwrap();
+            assert!(matches!(error, KeyBindingsError::Parse(_)));
+            assert!(error
+                .to_string()
+                .starts_with("Invalid ./keybindings.json: "));
+        }
+    }
+}
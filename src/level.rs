@@ -1,40 +1,101 @@
 use rltk::RandomNumberGenerator;
-use specs::{Entity, Join, World, WorldExt};
+use specs::{shred::Resource, Entity, Join, World, WorldExt};
 
 use crate::{
-    components::{CombatStats, Equipped, InBackpack, Player, Position, Viewshed},
-    gamelog::GameLog,
+    automove::AutoMove,
+    components::{
+        self, CombatStats, Equipped, GameMode, InBackpack, Player, Position, RunStats, Viewshed,
+    },
+    gamelog::{GameLog, LogCategory},
+    keybindings::KeyBindings,
     map::{map::Map, random_builder},
+    menu::{
+        hall_of_fame::HallOfFame, help_menu::HelpContent, slot_menu::SlotBrowser,
+        target_menu::LastTarget,
+    },
+    replay,
+    settings::Settings,
     spawn::spawner,
-    systems::particle_system::ParticleBuilder,
+    state::GlobalState,
+    systems::{
+        autosave::{self, Autosave},
+        lifetime_system::LifetimeClock,
+        particle_system::ParticleBuilder,
+        trigger_system::SprungTraps,
+    },
 };
 
+// One in VENDOR_CHANCE levels has a vendor
+const VENDOR_CHANCE: i32 = 3;
+
 pub fn new_game(ecs: &mut World) {
-    // Remove all existing entities
-    ecs.delete_all();
+    // Every run gets a seed of its own, so it can be told apart and replayed
+    let seed = ecs.fetch_mut::<RandomNumberGenerator>().next_u64();
+    let mode = ecs.fetch::<Settings>().game_mode;
+
+    replace_world(ecs);
+    start_run(ecs, seed, mode);
+    ecs.fetch_mut::<SlotBrowser>().active = None;
+    if ecs.fetch::<Settings>().record_runs {
+        replay::start_recording(ecs);
+    }
+}
+
+/// Generates the first level from the seed, in a world that has no run in it yet
+pub fn start_run(ecs: &mut World, seed: u64, mode: GameMode) {
+    ecs.insert(RandomNumberGenerator::seeded(seed));
 
     let player_pos = generate_map(1, ecs);
     let player_entity = spawner::player(ecs, player_pos);
+    if let Some(run_stats) = ecs.write_storage::<RunStats>().get_mut(player_entity) {
+        run_stats.seed = seed;
+        run_stats.mode = mode;
+    }
     ecs.insert(player_entity);
     ecs.insert(ParticleBuilder::new());
+    ecs.insert(SprungTraps::default());
+    ecs.insert(LifetimeClock::default());
+    ecs.insert(LastTarget::default());
+    ecs.insert(AutoMove::default());
 
     {
-        let mut gamelog = GameLog { entries: vec![] };
-        gamelog
-            .entries
-            .push("While roaming in the wilds, you stumbled upon a mysterious cave.".to_string());
-        gamelog.entries.push(
-            "Having no shelter from the harsh weather outside, you decide to explore it."
-                .to_string(),
+        let mut gamelog = GameLog::new();
+        gamelog.log(
+            LogCategory::System,
+            "While roaming in the wilds, you stumbled upon a mysterious cave.",
+        );
+        gamelog.log(
+            LogCategory::System,
+            "Having no shelter from the harsh weather outside, you decide to explore it.",
         );
-        gamelog.entries.push(
-            "But as soon as you enter the cave, the entrance colapses trapping you inside."
-                .to_string(),
+        gamelog.log(
+            LogCategory::System,
+            "But as soon as you enter the cave, the entrance colapses trapping you inside.",
         );
         ecs.insert(gamelog);
     }
 }
 
+// A run starts in a world of its own, entity ids handed out by an old world would change the
+// order systems see entities in, and the same seed would play out differently
+fn replace_world(ecs: &mut World) {
+    let mut world = components::new_world();
+    carry::<KeyBindings>(ecs, &mut world);
+    carry::<Settings>(ecs, &mut world);
+    carry::<HelpContent>(ecs, &mut world);
+    carry::<SlotBrowser>(ecs, &mut world);
+    carry::<HallOfFame>(ecs, &mut world);
+    carry::<Autosave>(ecs, &mut world);
+    carry::<GlobalState>(ecs, &mut world);
+    *ecs = world;
+}
+
+fn carry<T: Resource>(from: &mut World, to: &mut World) {
+    if let Some(resource) = from.remove::<T>() {
+        to.insert(resource);
+    }
+}
+
 pub fn next_level(ecs: &mut World) {
     // Delete entities that aren't the player or player's equipment
     remove_entities_on_level_change(ecs);
@@ -51,9 +112,10 @@ pub fn next_level(ecs: &mut World) {
     {
         // Notify the player and give them some health
         let mut gamelog = ecs.fetch_mut::<GameLog>();
-        gamelog
-            .entries
-            .push("You descend to the next level, and take a moment to heal.".to_string());
+        gamelog.log(
+            LogCategory::Status,
+            "You descend to the next level, and take a moment to heal.",
+        );
         let mut health_storage = ecs.write_storage::<CombatStats>();
         let player_health = health_storage.get_mut(player_entity);
         if let Some(player_health) = player_health {
@@ -71,7 +133,13 @@ pub fn next_level(ecs: &mut World) {
             player_position.x = player_pos.x;
             player_position.y = player_pos.y;
         }
+        let mut run_stats = ecs.write_storage::<RunStats>();
+        if let Some(run_stats) = run_stats.get_mut(player_entity) {
+            run_stats.deepest_depth = u32::max(run_stats.deepest_depth, new_depth);
+        }
     }
+
+    autosave::autosave(ecs);
 }
 
 fn generate_map(new_depth: u32, ecs: &mut World) -> Position {
@@ -85,8 +153,16 @@ fn generate_map(new_depth: u32, ecs: &mut World) -> Position {
     }
     map_builder.spawn_entities(ecs);
     let player_pos = map_builder.get_starting_position();
+    let map = map_builder.get_map();
+    let has_vendor = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        rng.roll_dice(1, VENDOR_CHANCE) == 1
+    };
+    if has_vendor {
+        spawner::spawn_vendor(ecs, &map, player_pos);
+    }
     ecs.insert(player_pos);
-    ecs.insert(map_builder.get_map());
+    ecs.insert(map);
     player_pos
 }
 
@@ -1,40 +1,170 @@
-use specs::{prelude::*, saveload::SimpleMarkerAllocator};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process,
+};
 
 use crate::{
-    components::SerializeMe,
-    menu::main_menu,
+    keybindings::KeyBindings,
+    menu::{
+        hall_of_fame::HallOfFame,
+        help_menu::HelpContent,
+        main_menu,
+        replay_viewer::{MAX_SPEED, MIN_SPEED},
+        slot_menu::SlotBrowser,
+    },
+    replay::{Playback, Recording},
+    settings::Settings,
     state::{GlobalState, State},
+    systems::{
+        autosave::{self, Autosave},
+        saveload_system,
+    },
 };
 
+mod automove;
+mod camera;
 mod components;
 mod game_loop;
 mod gamelog;
 mod gui;
+mod history;
+mod keybindings;
 mod level;
 mod map;
 mod menu;
+mod morgue;
 mod player;
 mod rect;
+mod replay;
+mod settings;
+#[cfg(test)]
+mod simulation;
 mod spawn;
 mod state;
 mod systems;
 
+// Turns a second a replay is watched at when the command line doesn't say
+const DEFAULT_REPLAY_SPEED: u16 = 8;
+
+/// A recording to watch instead of playing, given on the command line
+struct ReplayArgs {
+    path: PathBuf,
+    speed: u16,
+    headless: bool,
+}
+
+// `--replay FILE` watches a recording, `--speed TURNS` sets how fast and `--headless` only checks it
+fn replay_args() -> Result<Option<ReplayArgs>, String> {
+    let mut path = None;
+    let mut speed = DEFAULT_REPLAY_SPEED;
+    let mut headless = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--replay" => path = Some(args.next().ok_or("--replay needs a recording file")?),
+            "--speed" => {
+                speed = args
+                    .next()
+                    .and_then(|speed| speed.parse().ok())
+                    .ok_or("--speed needs a number of turns a second")?;
+            }
+            "--headless" => headless = true,
+            other => return Err(format!("Unknown argument {}", other)),
+        }
+    }
+    Ok(path.map(|path| ReplayArgs {
+        path: PathBuf::from(path),
+        speed: speed.clamp(MIN_SPEED, MAX_SPEED),
+        headless,
+    }))
+}
+
 fn main() -> rltk::BError {
+    let replay_args = replay_args().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(2);
+    });
+
+    // Checking a recording needs no window, the exit code says whether it matched
+    if let Some(ReplayArgs {
+        path,
+        headless: true,
+        ..
+    }) = &replay_args
+    {
+        match replay::replay_headless(path) {
+            Ok(end) => {
+                for line in end.report() {
+                    println!("{}", line);
+                }
+                process::exit(if end.matched() { 0 } else { 1 });
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(2);
+            }
+        }
+    }
+
     use rltk::RltkBuilder;
     let mut context = RltkBuilder::simple80x50()
         .with_title("Roguelike tutorial")
         .build()?;
     context.with_post_scanlines(true);
 
-    let mut gs = State { ecs: World::new() };
-    components::register_components(&mut gs.ecs);
-    gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    if let Some(ReplayArgs { path, speed, .. }) = replay_args {
+        let recording = Recording::load(&path).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(2);
+        });
+        let mut ecs = replay::replay_world();
+        let playback = Playback::start(&mut ecs, recording);
+        ecs.insert(playback);
+        ecs.insert(GlobalState::Replay {
+            speed,
+            paused: false,
+            waited_ms: 0.0,
+        });
+        return rltk::main_loop(context, State { ecs });
+    }
+
+    let mut gs = State {
+        ecs: components::new_world(),
+    };
+
+    let bindings = KeyBindings::load().unwrap_or_else(|err| {
+        rltk::console::log(format!("{}, using the default key bindings", err));
+        KeyBindings::default()
+    });
+    gs.ecs.insert(bindings);
+    let settings = Settings::load().unwrap_or_else(|err| {
+        rltk::console::log(format!("{}, using the default settings", err));
+        Settings::default()
+    });
+    // A save left by the original game gets a slot of its own
+    if let Err(err) = saveload_system::import_original_save(
+        Path::new(saveload_system::ORIGINAL_SAVE_FILE),
+        settings.save_format(),
+    ) {
+        rltk::console::log(format!("The old save couldn't be imported: {}", err));
+    }
+    gs.ecs.insert(settings);
+    gs.ecs.insert(HelpContent::build());
+    gs.ecs.insert(SlotBrowser::default());
+    gs.ecs.insert(HallOfFame::default());
 
     let rng = rltk::RandomNumberGenerator::new();
     gs.ecs.insert(rng);
 
-    gs.ecs.insert(GlobalState::MainMenu {
-        selected_menu: main_menu::MainMenuSelection::NewGame,
-    });
+    gs.ecs.insert(Autosave::default());
+
+    // After a crash the menu opens on resuming the run that was lost
+    let selected_menu = if autosave::interrupted_session() {
+        main_menu::MainMenuSelection::ResumeSession
+    } else {
+        main_menu::MainMenuSelection::NewGame
+    };
+    gs.ecs.insert(GlobalState::MainMenu { selected_menu });
     rltk::main_loop(context, gs)
 }
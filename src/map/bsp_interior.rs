@@ -3,7 +3,7 @@ use rltk::RandomNumberGenerator;
 use crate::{components::Position, rect::Rect, spawn::spawner};
 
 use super::{
-    apply_horizontal_tunnel, apply_room_to_map, apply_vertical_tunnel,
+    apply_horizontal_tunnel, apply_room_to_map, apply_vertical_tunnel, find_room_entrances,
     map::{Map, TileType},
     MapBuilder,
 };
@@ -16,6 +16,7 @@ pub struct BspInteriorBuilder {
     map: Map,
     rooms: Vec<Rect>,
     leafs: Vec<Rect>,
+    doors: Vec<usize>,
 }
 
 impl MapBuilder for BspInteriorBuilder {
@@ -43,9 +44,12 @@ impl MapBuilder for BspInteriorBuilder {
         let (stairs_x, stairs_y) = self.rooms[self.rooms.len() - 1].center();
         let stairs_idx = self.map.index_from_xy(stairs_x, stairs_y);
         self.map.tiles[stairs_idx] = TileType::DownStairs;
+
+        self.doors = find_room_entrances(&self.map, &self.rooms);
     }
 
     fn spawn_entities(&mut self, ecs: &mut specs::World) {
+        spawner::spawn_doors(ecs, &self.map, &self.doors, &self.rooms[0]);
         for room in self.rooms.iter().skip(1) {
             spawner::spawn_room(ecs, &self.map, room, MAX_ENTITIES);
         }
@@ -53,11 +57,12 @@ impl MapBuilder for BspInteriorBuilder {
 }
 
 impl BspInteriorBuilder {
-    pub fn new(new_depth: u32) -> Self {
+    pub fn new(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             rooms: Vec::new(),
             leafs: Vec::new(),
+            doors: Vec::new(),
         }
     }
 
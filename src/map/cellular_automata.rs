@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use rltk::RandomNumberGenerator;
 
@@ -16,7 +16,7 @@ const MAX_ENTITIES: u16 = 6;
 pub struct CellularAutomataBuilder {
     map: Map,
     starting_position: Position,
-    noise_areas: HashMap<i32, Vec<usize>>,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
 }
 
 impl MapBuilder for CellularAutomataBuilder {
@@ -46,11 +46,11 @@ impl MapBuilder for CellularAutomataBuilder {
 }
 
 impl CellularAutomataBuilder {
-    pub fn new(new_depth: u32) -> Self {
+    pub fn new(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 1, y: 1 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
         }
     }
 
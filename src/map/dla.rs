@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use rltk::{Point, RandomNumberGenerator};
 
@@ -21,7 +21,7 @@ pub enum DLAAlgorithm {
 pub struct DLABuilder {
     map: Map,
     starting_position: Position,
-    noise_areas: HashMap<i32, Vec<usize>>,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
     algorithm: DLAAlgorithm,
     brush_size: u16,
     symmetry: Symmetry,
@@ -90,11 +90,11 @@ impl MapBuilder for DLABuilder {
 }
 
 impl DLABuilder {
-    pub fn new_walk_inwards(new_depth: u32) -> Self {
+    pub fn new_walk_inwards(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
             algorithm: DLAAlgorithm::WalkInwards,
             brush_size: 1,
             symmetry: Symmetry::None,
@@ -102,11 +102,11 @@ impl DLABuilder {
         }
     }
 
-    pub fn new_walk_outwards(new_depth: u32) -> Self {
+    pub fn new_walk_outwards(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
             algorithm: DLAAlgorithm::WalkOutwards,
             brush_size: 2,
             symmetry: Symmetry::None,
@@ -114,11 +114,11 @@ impl DLABuilder {
         }
     }
 
-    pub fn new_central_attractor(new_depth: u32) -> Self {
+    pub fn new_central_attractor(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
             algorithm: DLAAlgorithm::CentralAttractor,
             brush_size: 2,
             symmetry: Symmetry::None,
@@ -126,11 +126,11 @@ impl DLABuilder {
         }
     }
 
-    pub fn insectoid(new_depth: u32) -> Self {
+    pub fn insectoid(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
             algorithm: DLAAlgorithm::CentralAttractor,
             brush_size: 2,
             symmetry: Symmetry::Horizontal,
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use rltk::console;
 
@@ -29,7 +29,7 @@ pub struct DrunkardSettings {
 pub struct DrunkardsWalkBuilder {
     map: Map,
     starting_position: Position,
-    noise_areas: HashMap<i32, Vec<usize>>,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
     settings: DrunkardSettings,
 }
 
@@ -154,11 +154,11 @@ impl MapBuilder for DrunkardsWalkBuilder {
 }
 
 impl DrunkardsWalkBuilder {
-    pub fn open_area(new_depth: u32) -> Self {
+    pub fn open_area(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
             settings: DrunkardSettings {
                 spawn_mode: DrunkSpawnMode::StartingPoint,
                 drunken_lifetime: 400,
@@ -169,11 +169,11 @@ impl DrunkardsWalkBuilder {
         }
     }
 
-    pub fn open_halls(new_depth: u32) -> Self {
+    pub fn open_halls(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
             settings: DrunkardSettings {
                 spawn_mode: DrunkSpawnMode::Random,
                 drunken_lifetime: 400,
@@ -184,11 +184,11 @@ impl DrunkardsWalkBuilder {
         }
     }
 
-    pub fn winding_passages(new_depth: u32) -> Self {
+    pub fn winding_passages(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
             settings: DrunkardSettings {
                 spawn_mode: DrunkSpawnMode::Random,
                 drunken_lifetime: 100,
@@ -199,11 +199,11 @@ impl DrunkardsWalkBuilder {
         }
     }
 
-    pub fn fat_passages(new_depth: u32) -> Self {
+    pub fn fat_passages(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
             settings: DrunkardSettings {
                 spawn_mode: DrunkSpawnMode::Random,
                 drunken_lifetime: 100,
@@ -214,11 +214,11 @@ impl DrunkardsWalkBuilder {
         }
     }
 
-    pub fn fearful_symmetry(new_depth: u32) -> Self {
+    pub fn fearful_symmetry(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
             settings: DrunkardSettings {
                 spawn_mode: DrunkSpawnMode::Random,
                 drunken_lifetime: 100,
@@ -1,4 +1,8 @@
-use std::{collections::HashSet, fs::File, io::Write};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs::File,
+    io::Write,
+};
 
 use rltk::{Algorithm2D, BaseMap, FontCharType, Point};
 use serde::{Deserialize, Serialize};
@@ -6,6 +10,7 @@ use specs::Entity;
 
 pub const WINDOW_WIDTH: u16 = 80;
 pub const WINDOW_HEIGHT: u16 = 50;
+// Default level size, levels can be bigger than the screen
 pub const MAP_WIDTH: u16 = 80;
 pub const MAP_HEIGHT: u16 = 43;
 
@@ -27,17 +32,24 @@ pub struct Map {
     pub visible_tiles: Vec<bool>,
     pub blocked: Vec<bool>,
     pub depth: u32,
-    pub bloodstains: HashSet<usize>,
+    pub bloodstains: BTreeSet<usize>,
+
+    // Notes the player left on tiles, shown on the overview screen
+    #[serde(default)]
+    pub notes: BTreeMap<usize, String>,
 
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
     pub tile_content: Vec<Vec<Entity>>,
+
+    // Tiles where an entity (like a closed door) blocks the line of sight
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    pub view_blocked: HashSet<usize>,
 }
 
 impl Map {
-    pub fn empty_map(new_depth: u32) -> Self {
-        let width = MAP_WIDTH;
-        let height = MAP_HEIGHT;
+    pub fn empty_map(new_depth: u32, width: u16, height: u16) -> Self {
         let map_dimensions = width as usize * height as usize;
         Self {
             window_width: WINDOW_WIDTH,
@@ -49,13 +61,15 @@ impl Map {
             visible_tiles: vec![false; map_dimensions],
             blocked: vec![false; map_dimensions],
             depth: new_depth,
-            bloodstains: HashSet::new(),
+            bloodstains: BTreeSet::new(),
+            notes: BTreeMap::new(),
             tile_content: vec![Vec::new(); map_dimensions],
+            view_blocked: HashSet::new(),
         }
     }
 
     pub fn index_from_xy(&self, x: u16, y: u16) -> usize {
-        ((y * self.width) + x) as usize
+        y as usize * self.width as usize + x as usize
     }
 
     pub fn xy_from_index(&self, idx: &usize) -> (u16, u16) {
@@ -131,19 +145,25 @@ impl Map {
             }
         };
 
-        for y in 0..self.height {
-            let mut line = String::new();
-            for x in 0..self.width {
-                let tile_idx = self.index_from_xy(x, y);
-                line += match self.tiles[tile_idx] {
-                    TileType::DownStairs => ">",
-                    TileType::Floor => ".",
-                    TileType::Wall => "#",
-                };
-            }
+        for line in self.ascii_rows() {
             let _ = writeln!(output, "{}", line);
         }
     }
+
+    /// Every tile as a character, one string per row
+    pub fn ascii_rows(&self) -> Vec<String> {
+        (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| match self.tiles[self.index_from_xy(x, y)] {
+                        TileType::DownStairs => '>',
+                        TileType::Floor => '.',
+                        TileType::Wall => '#',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
 }
 
 impl Algorithm2D for Map {
@@ -154,7 +174,7 @@ impl Algorithm2D for Map {
 
 impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx] == TileType::Wall
+        self.tiles[idx] == TileType::Wall || self.view_blocked.contains(&idx)
     }
 
     fn get_available_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
@@ -200,3 +220,25 @@ impl BaseMap for Map {
         rltk::DistanceAlg::Pythagoras.distance2d(p1, p2)
     }
 }
+
+/// A view of the map for pathfinding that treats some extra tiles as impassable
+pub struct AvoidingMap<'a> {
+    pub map: &'a Map,
+    pub avoid: &'a HashSet<usize>,
+}
+
+impl<'a> BaseMap for AvoidingMap<'a> {
+    fn is_opaque(&self, idx: usize) -> bool {
+        self.map.is_opaque(idx)
+    }
+
+    fn get_available_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
+        let mut exits = self.map.get_available_exits(idx);
+        exits.retain(|(exit, _)| !self.avoid.contains(exit));
+        exits
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.map.get_pathing_distance(idx1, idx2)
+    }
+}
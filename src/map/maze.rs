@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use rltk::RandomNumberGenerator;
 
@@ -203,7 +203,7 @@ impl<'a> Grid<'a> {
 pub struct MazeBuilder {
     map: Map,
     starting_position: Position,
-    noise_areas: HashMap<i32, Vec<usize>>,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
 }
 
 impl MapBuilder for MazeBuilder {
@@ -246,11 +246,11 @@ impl MapBuilder for MazeBuilder {
 }
 
 impl MazeBuilder {
-    pub fn new(new_depth: u32) -> Self {
+    pub fn new(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
         }
     }
 }
@@ -2,7 +2,7 @@ use rltk::{FastNoise, RandomNumberGenerator};
 use specs::World;
 use std::{
     cmp::{max, min},
-    collections::HashMap,
+    collections::{BTreeMap, HashSet},
 };
 
 use crate::{components::Position, rect::Rect};
@@ -13,7 +13,7 @@ use self::{
     cellular_automata::CellularAutomataBuilder,
     dla::DLABuilder,
     drunkard::DrunkardsWalkBuilder,
-    map::{Map, TileType},
+    map::{Map, TileType, MAP_HEIGHT, MAP_WIDTH},
     maze::MazeBuilder,
     voronoi::VoronoiBuilder,
     wfc::WaveformCollapseBuilder,
@@ -29,6 +29,10 @@ mod maze;
 mod voronoi;
 mod wfc;
 
+// Caves sprawl far beyond the edges of the screen
+const CAVE_WIDTH: u16 = 200;
+const CAVE_HEIGHT: u16 = 120;
+
 pub enum Symmetry {
     None,
     Horizontal,
@@ -46,23 +50,45 @@ pub trait MapBuilder {
 pub fn random_builder(new_depth: u32, rng: &mut RandomNumberGenerator) -> Box<dyn MapBuilder> {
     let builder_idx = rng.roll_dice(1, 17);
     match builder_idx {
-        1 => Box::new(BspDungeonBuilder::new(new_depth)),
-        2 => Box::new(BspInteriorBuilder::new(new_depth)),
-        3 => Box::new(CellularAutomataBuilder::new(new_depth)),
-        4 => Box::new(DrunkardsWalkBuilder::open_area(new_depth)),
-        5 => Box::new(DrunkardsWalkBuilder::open_halls(new_depth)),
-        6 => Box::new(DrunkardsWalkBuilder::winding_passages(new_depth)),
-        7 => Box::new(DrunkardsWalkBuilder::fat_passages(new_depth)),
-        8 => Box::new(DrunkardsWalkBuilder::fearful_symmetry(new_depth)),
-        9 => Box::new(MazeBuilder::new(new_depth)),
-        10 => Box::new(DLABuilder::new_walk_inwards(new_depth)),
-        11 => Box::new(DLABuilder::new_walk_outwards(new_depth)),
-        12 => Box::new(DLABuilder::new_central_attractor(new_depth)),
-        13 => Box::new(DLABuilder::insectoid(new_depth)),
-        14 => Box::new(VoronoiBuilder::pythagoras(new_depth)),
-        15 => Box::new(VoronoiBuilder::manhattan(new_depth)),
-        16 => Box::new(VoronoiBuilder::chebyshev(new_depth)),
-        _ => Box::new(WaveformCollapseBuilder::from_manual_tiles(new_depth)),
+        1 => Box::new(BspDungeonBuilder::new(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+        2 => Box::new(BspInteriorBuilder::new(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+        3 => Box::new(CellularAutomataBuilder::new(
+            new_depth,
+            CAVE_WIDTH,
+            CAVE_HEIGHT,
+        )),
+        4 => Box::new(DrunkardsWalkBuilder::open_area(
+            new_depth, MAP_WIDTH, MAP_HEIGHT,
+        )),
+        5 => Box::new(DrunkardsWalkBuilder::open_halls(
+            new_depth, MAP_WIDTH, MAP_HEIGHT,
+        )),
+        6 => Box::new(DrunkardsWalkBuilder::winding_passages(
+            new_depth, MAP_WIDTH, MAP_HEIGHT,
+        )),
+        7 => Box::new(DrunkardsWalkBuilder::fat_passages(
+            new_depth, MAP_WIDTH, MAP_HEIGHT,
+        )),
+        8 => Box::new(DrunkardsWalkBuilder::fearful_symmetry(
+            new_depth, MAP_WIDTH, MAP_HEIGHT,
+        )),
+        9 => Box::new(MazeBuilder::new(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+        10 => Box::new(DLABuilder::new_walk_inwards(
+            new_depth, MAP_WIDTH, MAP_HEIGHT,
+        )),
+        11 => Box::new(DLABuilder::new_walk_outwards(
+            new_depth, MAP_WIDTH, MAP_HEIGHT,
+        )),
+        12 => Box::new(DLABuilder::new_central_attractor(
+            new_depth, MAP_WIDTH, MAP_HEIGHT,
+        )),
+        13 => Box::new(DLABuilder::insectoid(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+        14 => Box::new(VoronoiBuilder::pythagoras(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+        15 => Box::new(VoronoiBuilder::manhattan(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+        16 => Box::new(VoronoiBuilder::chebyshev(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+        _ => Box::new(WaveformCollapseBuilder::from_manual_tiles(
+            new_depth, MAP_WIDTH, MAP_HEIGHT,
+        )),
     }
 }
 
@@ -97,10 +123,50 @@ pub fn apply_vertical_tunnel(map: &mut Map, y1: u16, y2: u16, x: u16) {
     }
 }
 
+/// Finds one tile wide openings in the walls around the rooms, where doors can be placed
+pub fn find_room_entrances(map: &Map, rooms: &[Rect]) -> Vec<usize> {
+    let mut entrances = HashSet::new();
+    for room in rooms.iter() {
+        let mut boundary = Vec::new();
+        for x in room.x1 + 1..=room.x2 {
+            boundary.push((x, room.y1, true));
+            boundary.push((x, room.y2 + 1, true));
+        }
+        for y in room.y1 + 1..=room.y2 {
+            boundary.push((room.x1, y, false));
+            boundary.push((room.x2 + 1, y, false));
+        }
+
+        for (x, y, horizontal_wall) in boundary {
+            if x < 1 || y < 1 || x >= map.width - 1 || y >= map.height - 1 {
+                continue;
+            }
+            let idx = map.index_from_xy(x, y);
+            if map.tiles[idx] != TileType::Floor {
+                continue;
+            }
+            // The opening must be flanked by walls, otherwise it's not a doorway
+            let (side1, side2) = if horizontal_wall {
+                (map.index_from_xy(x - 1, y), map.index_from_xy(x + 1, y))
+            } else {
+                (map.index_from_xy(x, y - 1), map.index_from_xy(x, y + 1))
+            };
+            if map.tiles[side1] == TileType::Wall && map.tiles[side2] == TileType::Wall {
+                entrances.insert(idx);
+            }
+        }
+    }
+    let mut entrances: Vec<usize> = entrances.into_iter().collect();
+    entrances.sort_unstable();
+    entrances
+}
+
 pub fn remove_unreachable_areas(map: &mut Map, start_idx: usize) -> usize {
     map.populate_blocked();
     let poi: Vec<usize> = vec![start_idx];
-    let dijkstra_map = rltk::DijkstraMap::new(map.width, map.height, &poi, map, 300.0);
+    // No path can be longer than the whole map, however big it is
+    let max_depth = map.tiles.len() as f32 * 1.5;
+    let dijkstra_map = rltk::DijkstraMap::new(map.width, map.height, &poi, map, max_depth);
     let (mut exit_idx, mut exit_distance) = (start_idx, 0.0f32);
     for (i, tile) in map.tiles.iter_mut().enumerate() {
         if *tile != TileType::Floor {
@@ -123,8 +189,8 @@ pub fn remove_unreachable_areas(map: &mut Map, start_idx: usize) -> usize {
 pub fn generate_voronoi_spawn_regions(
     map: &Map,
     rng: &mut RandomNumberGenerator,
-) -> HashMap<i32, Vec<usize>> {
-    let mut noise_areas: HashMap<i32, Vec<usize>> = HashMap::new();
+) -> BTreeMap<i32, Vec<usize>> {
+    let mut noise_areas: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
     let mut noise = FastNoise::seeded(rng.roll_dice(1, u16::MAX as i32) as u64);
     noise.set_noise_type(rltk::NoiseType::Cellular);
     noise.set_frequency(0.08);
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use rltk::{Point, RandomNumberGenerator};
 
@@ -21,7 +21,7 @@ pub enum DistanceAlgorithm {
 pub struct VoronoiBuilder {
     map: Map,
     starting_position: Position,
-    noise_areas: HashMap<i32, Vec<usize>>,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
     n_seeds: usize,
     distance_algorith: DistanceAlgorithm,
 }
@@ -102,31 +102,31 @@ impl MapBuilder for VoronoiBuilder {
 }
 
 impl VoronoiBuilder {
-    pub fn pythagoras(new_depth: u32) -> Self {
+    pub fn pythagoras(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
             n_seeds: 64,
             distance_algorith: DistanceAlgorithm::Pythagoras,
         }
     }
 
-    pub fn manhattan(new_depth: u32) -> Self {
+    pub fn manhattan(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
             n_seeds: 64,
             distance_algorith: DistanceAlgorithm::Manhattan,
         }
     }
 
-    pub fn chebyshev(new_depth: u32) -> Self {
+    pub fn chebyshev(new_depth: u32, width: u16, height: u16) -> Self {
         Self {
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
             n_seeds: 64,
             distance_algorith: DistanceAlgorithm::Chebyshev,
         }
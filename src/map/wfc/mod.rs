@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use self::{
     constraints::CompatibilityMatrix,
@@ -26,7 +26,7 @@ pub struct WaveformCollapseBuilder {
     modules: Vec<Module>,
     map: Map,
     starting_position: Position,
-    noise_areas: HashMap<i32, Vec<usize>>,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
 }
 
 impl MapBuilder for WaveformCollapseBuilder {
@@ -94,13 +94,13 @@ impl MapBuilder for WaveformCollapseBuilder {
 }
 
 impl WaveformCollapseBuilder {
-    pub fn from_manual_tiles(new_depth: u32) -> Self {
+    pub fn from_manual_tiles(new_depth: u32, width: u16, height: u16) -> Self {
         let modules = load_from_byte_slice(WFC_MAP_1, CHUNK_SIZE);
         Self {
             modules,
-            map: Map::empty_map(new_depth),
+            map: Map::empty_map(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
-            noise_areas: HashMap::new(),
+            noise_areas: BTreeMap::new(),
         }
     }
 
@@ -139,7 +139,14 @@ fn convert_tiles_to_modules(tiles: &[TileType], chunk_size: u8) -> Vec<Module> {
         modules.insert(module);
     }
 
-    let mut result = Vec::new();
-    result.extend(modules.into_iter());
+    // Sorted by pattern, so a module gets the same index whatever order the set hands them out in
+    let mut result: Vec<Module> = modules.into_iter().collect();
+    result.sort_by_key(|module| {
+        module
+            .pattern
+            .iter()
+            .map(|tile| *tile as u8)
+            .collect::<Vec<u8>>()
+    });
     result
 }
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap};
 
 use super::constraints::CompatibilityMatrix;
 
@@ -146,7 +146,7 @@ impl Solver {
 
         // Current block is not yet collapsed, so we need to validate all neighbor's potential states
         // against all current block's potential states.
-        let mut new_neighbors_possible_states: HashSet<usize> = HashSet::new();
+        let mut new_neighbors_possible_states: BTreeSet<usize> = BTreeSet::new();
         for possible_current_module in self.possible_states[current_idx].iter() {
             let new_possible_states = self.filter_compatible(
                 possible_current_module,
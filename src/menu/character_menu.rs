@@ -0,0 +1,233 @@
+use rltk::{Rltk, VirtualKeyCode, RGB};
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::{
+    components::{
+        CombatStats, Confusion, DefenseBonus, EquipmentSlot, Equipped, HungerClock, HungerState,
+        LifeRegen, MeleePowerBonus, Name, Resistance, RunStats, Viewshed, VisionBonus, Wallet,
+    },
+    gamelog::GameLog,
+    keybindings::{Action, KeyBindings},
+    map::map::Map,
+    state::RunState,
+    systems::{
+        damage_system::total_resistance,
+        melee_combat_system::{effective_stats, Bonuses, EffectiveStats},
+    },
+};
+
+// Where the second column of the sheet starts
+const RIGHT_COLUMN: i32 = 42;
+
+pub fn character_menu(ecs: &mut World, ctx: &mut Rltk) -> RunState {
+    draw_sheet(ecs, ctx);
+
+    match ctx.key {
+        Some(VirtualKeyCode::Escape) => RunState::AwaitingInput,
+        Some(key) if ecs.fetch::<KeyBindings>().is(key, Action::CharacterSheet) => {
+            RunState::AwaitingInput
+        }
+        _ => RunState::ShowCharacter,
+    }
+}
+
+fn draw_sheet(ecs: &World, ctx: &mut Rltk) {
+    let map = ecs.fetch::<Map>();
+    ctx.cls();
+    ctx.draw_box(
+        0,
+        0,
+        map.window_width - 1,
+        map.window_height - 1,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        3,
+        0,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Character",
+    );
+    ctx.print_color(
+        3,
+        map.window_height - 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESC close",
+    );
+
+    let player = *ecs.fetch::<Entity>();
+    draw_lines(ctx, 3, &stat_lines(ecs, player, map.depth));
+    draw_lines(ctx, RIGHT_COLUMN, &gear_lines(ecs, player));
+}
+
+fn draw_lines(ctx: &mut Rltk, x: i32, lines: &[(String, RGB)]) {
+    for (i, (text, color)) in lines.iter().enumerate() {
+        ctx.print_color(x, 2 + i as i32, *color, RGB::named(rltk::BLACK), text);
+    }
+}
+
+fn heading(text: &str) -> (String, RGB) {
+    (text.to_string(), RGB::named(rltk::YELLOW))
+}
+
+fn white<S: ToString>(text: S) -> (String, RGB) {
+    (text.to_string(), RGB::named(rltk::WHITE))
+}
+
+fn blank() -> (String, RGB) {
+    (String::new(), RGB::named(rltk::BLACK))
+}
+
+fn player_stats(ecs: &World, player: Entity) -> Option<(CombatStats, EffectiveStats)> {
+    let combat_stats = ecs.read_storage::<CombatStats>();
+    let stats = combat_stats.get(player)?;
+    let bonuses = Bonuses {
+        names: &ecs.read_storage::<Name>(),
+        equipped: &ecs.read_storage::<Equipped>(),
+        melee_bonus: &ecs.read_storage::<MeleePowerBonus>(),
+        defense_bonus: &ecs.read_storage::<DefenseBonus>(),
+        hunger_clocks: &ecs.read_storage::<HungerClock>(),
+    };
+    Some((stats.clone(), effective_stats(player, stats, &bonuses)))
+}
+
+// A stat broken down into its base value, every bonus and the total used in combat
+fn breakdown(lines: &mut Vec<(String, RGB)>, title: &str, base: i32, bonuses: &[(String, i32)]) {
+    let total = base + bonuses.iter().map(|(_, bonus)| bonus).sum::<i32>();
+    lines.push(heading(&format!("{}: {}", title, total)));
+    lines.push(white(format!("  {:<20}{:>+5}", "Base", base)));
+    for (source, bonus) in bonuses {
+        lines.push((
+            format!("  {:<20}{:>+5}", source, bonus),
+            RGB::named(rltk::CYAN),
+        ));
+    }
+}
+
+fn stat_lines(ecs: &World, player: Entity, depth: u32) -> Vec<(String, RGB)> {
+    let mut lines = Vec::new();
+
+    if let Some((stats, effective)) = player_stats(ecs, player) {
+        lines.push(heading(&format!("HP: {} / {}", stats.hp, stats.max_hp)));
+        lines.push(blank());
+        breakdown(
+            &mut lines,
+            "Power",
+            effective.base_power,
+            &effective.power_bonuses,
+        );
+        lines.push(blank());
+        breakdown(
+            &mut lines,
+            "Defense",
+            effective.base_defense,
+            &effective.defense_bonuses,
+        );
+        lines.push(blank());
+    }
+
+    let resistance = total_resistance(
+        player,
+        &ecs.read_storage::<Equipped>(),
+        &ecs.read_storage::<Resistance>(),
+    );
+    lines.push(white(format!("Resistance: {}%", resistance)));
+    if let Some(viewshed) = ecs.read_storage::<Viewshed>().get(player) {
+        let bonus: u16 = (
+            &ecs.read_storage::<Equipped>(),
+            &ecs.read_storage::<VisionBonus>(),
+        )
+            .join()
+            .filter(|(equipped_by, _)| equipped_by.owner == player)
+            .map(|(_, bonus)| bonus.range)
+            .sum();
+        lines.push(white(format!("Sight: {}", viewshed.range + bonus)));
+    }
+    for (equipped_by, regen) in (
+        &ecs.read_storage::<Equipped>(),
+        &ecs.read_storage::<LifeRegen>(),
+    )
+        .join()
+    {
+        if equipped_by.owner == player {
+            lines.push(white(format!(
+                "Regenerates 1 hp every {} turns",
+                regen.interval
+            )));
+        }
+    }
+    if let Some(wallet) = ecs.read_storage::<Wallet>().get(player) {
+        lines.push((format!("Gold: {}", wallet.gold), RGB::named(rltk::GOLD)));
+    }
+    lines.push(blank());
+
+    lines.push(heading("Progress"));
+    lines.push(white(format!(
+        "  Turns played: {}",
+        ecs.fetch::<GameLog>().turn
+    )));
+    lines.push(white(format!("  Current depth: {}", depth)));
+    if let Some(run_stats) = ecs.read_storage::<RunStats>().get(player) {
+        lines.push(white(format!(
+            "  Deepest depth: {}",
+            run_stats.deepest_depth
+        )));
+    }
+
+    lines
+}
+
+fn gear_lines(ecs: &World, player: Entity) -> Vec<(String, RGB)> {
+    let mut lines = vec![heading("Equipment")];
+    let names = ecs.read_storage::<Name>();
+    let equipped = ecs.read_storage::<Equipped>();
+    for (slot, slot_name) in [
+        (EquipmentSlot::Melee, "Melee"),
+        (EquipmentSlot::Shield, "Shield"),
+    ] {
+        let item = (&equipped, &names)
+            .join()
+            .find(|(equipped_by, _)| equipped_by.owner == player && equipped_by.slot == slot)
+            .map_or("(empty)".to_string(), |(_, name)| name.name.clone());
+        lines.push(white(format!("  {:<8}{}", slot_name, item)));
+    }
+    lines.push(blank());
+
+    lines.push(heading("Status"));
+    let mut effects = Vec::new();
+    if let Some(confusion) = ecs.read_storage::<Confusion>().get(player) {
+        effects.push((
+            format!("  Confused for {} turns", confusion.turns),
+            RGB::named(rltk::MAGENTA),
+        ));
+    }
+    if let Some(hunger) = ecs.read_storage::<HungerClock>().get(player) {
+        match hunger.state {
+            HungerState::WellFed => {
+                effects.push(("  Well fed".to_string(), RGB::named(rltk::GREEN)))
+            }
+            HungerState::Normal => {}
+            HungerState::Hungry => effects.push(("  Hungry".to_string(), RGB::named(rltk::ORANGE))),
+            HungerState::Starving => {
+                effects.push(("  Starving".to_string(), RGB::named(rltk::RED)))
+            }
+        }
+    }
+    if effects.is_empty() {
+        effects.push(white("  None"));
+    }
+    lines.append(&mut effects);
+    lines.push(blank());
+
+    if let Some(run_stats) = ecs.read_storage::<RunStats>().get(player) {
+        let total: u32 = run_stats.kills.values().sum();
+        lines.push(heading(&format!("Kills: {}", total)));
+        for (name, count) in run_stats.kills.iter() {
+            lines.push(white(format!("  {:<24}{:>4}", name, count)));
+        }
+    }
+
+    lines
+}
@@ -1,39 +1,93 @@
-use rltk::RGB;
+use rltk::{Rltk, VirtualKeyCode, RGB};
+use specs::World;
+
+use crate::{
+    keybindings::{Action, KeyBindings},
+    map::map::Map,
+    morgue::Morgue,
+};
 
 #[derive(PartialEq)]
 pub enum GameOverResult {
-    NoSelection,
+    NoSelection { scroll: u16 },
     QuitToMenu,
 }
 
-pub fn game_over(ctx: &mut rltk::Rltk) -> GameOverResult {
+/// The death recap, scrolled from the top with the movement keys
+pub fn game_over(ecs: &mut World, ctx: &mut Rltk, scroll: u16) -> GameOverResult {
+    let morgue = ecs.fetch::<Morgue>();
+    let map = ecs.fetch::<Map>();
+
+    let page = map.window_height - 4;
+    let max_scroll = morgue.lines.len().saturating_sub(page as usize) as u16;
+    let scroll = u16::min(scroll, max_scroll);
+
+    draw_recap(ctx, &map, &morgue, scroll);
+
+    let bindings = ecs.fetch::<KeyBindings>();
+    let scroll = match ctx.key {
+        None => scroll,
+        Some(key) => match key {
+            VirtualKeyCode::Escape | VirtualKeyCode::Return => return GameOverResult::QuitToMenu,
+            VirtualKeyCode::PageUp => scroll.saturating_sub(page),
+            VirtualKeyCode::PageDown => scroll + page,
+            VirtualKeyCode::Home => 0,
+            VirtualKeyCode::End => max_scroll,
+            _ => match bindings.action(key) {
+                Some(Action::MoveNorth) => scroll.saturating_sub(1),
+                Some(Action::MoveSouth) => scroll + 1,
+                _ => scroll,
+            },
+        },
+    };
+
+    GameOverResult::NoSelection {
+        scroll: u16::min(scroll, max_scroll),
+    }
+}
+
+fn draw_recap(ctx: &mut Rltk, map: &Map, morgue: &Morgue, scroll: u16) {
+    ctx.cls();
     ctx.print_color_centered(
-        15,
+        0,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
         "Your journey has ended!",
     );
-    ctx.print_color_centered(
-        17,
-        RGB::named(rltk::WHITE),
-        RGB::named(rltk::BLACK),
-        "One day, we'll tell you all about how you did.",
-    );
-    ctx.print_color_centered(
-        18,
-        RGB::named(rltk::WHITE),
+
+    // The map rows are as wide as the level, so the recap uses the whole width of the screen
+    let page = map.window_height - 4;
+    for (i, (text, color)) in morgue
+        .lines
+        .iter()
+        .skip(scroll as usize)
+        .take(page as usize)
+        .enumerate()
+    {
+        ctx.print_color(0, 2 + i as i32, *color, RGB::named(rltk::BLACK), text);
+    }
+
+    let (file, color) = match &morgue.file {
+        Ok(path) => (
+            format!("Morgue file: {}", path.display()),
+            RGB::named(rltk::GREY),
+        ),
+        Err(error) => (
+            format!("Couldn't write the morgue file: {}", error),
+            RGB::named(rltk::RED),
+        ),
+    };
+    ctx.print_color(
+        0,
+        map.window_height - 2,
+        color,
         RGB::named(rltk::BLACK),
-        "This day, sadly, is not in this chapter...",
+        file,
     );
     ctx.print_color_centered(
-        20,
+        map.window_height - 1,
         RGB::named(rltk::MAGENTA),
         RGB::named(rltk::BLACK),
-        "Press any key to return to the menu.",
+        "UP/DOWN/PGUP/PGDN scroll, ENTER return to the menu",
     );
-
-    match ctx.key {
-        None => GameOverResult::NoSelection,
-        Some(_) => GameOverResult::QuitToMenu,
-    }
 }
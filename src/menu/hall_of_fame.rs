@@ -0,0 +1,159 @@
+use rltk::{Rltk, VirtualKeyCode, RGB};
+use specs::World;
+
+use crate::{
+    history::{self, RunOrder, RunRecord},
+    keybindings::{Action, KeyBindings},
+    menu::main_menu::MainMenuSelection,
+    state::GlobalState,
+    systems::save_slots,
+};
+
+/// The run history as the hall of fame last read it
+#[derive(Default)]
+pub struct HallOfFame {
+    pub runs: Vec<RunRecord>,
+}
+
+impl HallOfFame {
+    pub fn refresh(&mut self, order: RunOrder) {
+        self.runs = history::load_history();
+        history::sort_runs(&mut self.runs, order);
+    }
+}
+
+pub fn hall_of_fame(
+    ecs: &mut World,
+    ctx: &mut Rltk,
+    order: RunOrder,
+    selected: usize,
+) -> GlobalState {
+    let run_count = ecs.fetch::<HallOfFame>().runs.len();
+    let selected = selected.min(run_count.saturating_sub(1));
+    draw_runs(ecs, ctx, order, selected);
+
+    let bindings = ecs.fetch::<KeyBindings>();
+    let selected = match ctx.key {
+        None => selected,
+        Some(key) => match key {
+            VirtualKeyCode::Escape | VirtualKeyCode::Return => {
+                return GlobalState::MainMenu {
+                    selected_menu: MainMenuSelection::HallOfFame,
+                }
+            }
+            VirtualKeyCode::Tab => {
+                let order = order.next();
+                history::sort_runs(&mut ecs.fetch_mut::<HallOfFame>().runs, order);
+                return GlobalState::HallOfFame { order, selected: 0 };
+            }
+            VirtualKeyCode::Home => 0,
+            VirtualKeyCode::End => run_count.saturating_sub(1),
+            _ => match bindings.action(key) {
+                Some(Action::MoveNorth) => selected.saturating_sub(1),
+                Some(Action::MoveSouth) => selected + 1,
+                _ => selected,
+            },
+        },
+    };
+
+    GlobalState::HallOfFame { order, selected }
+}
+
+fn draw_runs(ecs: &World, ctx: &mut Rltk, order: RunOrder, selected: usize) {
+    let hall = ecs.fetch::<HallOfFame>();
+    let (width, height) = ctx.get_char_size();
+    let (width, height) = (width as i32, height as i32);
+    ctx.cls();
+    ctx.draw_box(
+        0,
+        0,
+        width - 1,
+        height - 1,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        3,
+        0,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!("Hall of Fame, by {}", order.name()),
+    );
+
+    let header_fg = RGB::named(rltk::GREY);
+    for (x, column) in [
+        (2, "#"),
+        (6, "Score"),
+        (13, "Character"),
+        (30, "Depth"),
+        (36, "Kills"),
+        (42, "Gold"),
+        (48, "Fate"),
+        (63, "Date (UTC)"),
+    ] {
+        ctx.print_color(x, 2, header_fg, RGB::named(rltk::BLACK), column);
+    }
+
+    if hall.runs.is_empty() {
+        ctx.print_color(
+            2,
+            4,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            "No runs have ended yet.",
+        );
+    }
+
+    // Keep the selected run on screen when there are more runs than lines
+    let visible_rows = (height - 8) as usize;
+    let first_row = selected.saturating_sub(visible_rows - 1);
+    for (row, run) in hall
+        .runs
+        .iter()
+        .enumerate()
+        .skip(first_row)
+        .take(visible_rows)
+    {
+        let y = 4 + (row - first_row) as i32;
+        let fg = if row == selected {
+            RGB::named(rltk::MAGENTA)
+        } else {
+            RGB::named(rltk::WHITE)
+        };
+        let bg = RGB::named(rltk::BLACK);
+        ctx.print_color(2, y, fg, bg, row + 1);
+        ctx.print_color(6, y, fg, bg, run.score);
+        ctx.print_color(13, y, fg, bg, truncate(&run.character, 16));
+        ctx.print_color(30, y, fg, bg, run.depth);
+        ctx.print_color(36, y, fg, bg, run.kills);
+        ctx.print_color(42, y, fg, bg, run.gold);
+        ctx.print_color(48, y, fg, bg, truncate(run.fate(), 14));
+        ctx.print_color(63, y, fg, bg, save_slots::format_timestamp(run.timestamp));
+    }
+
+    if let Some(run) = hall.runs.get(selected) {
+        ctx.print_color(
+            3,
+            height - 3,
+            RGB::named(rltk::GREY),
+            RGB::named(rltk::BLACK),
+            format!(
+                "Seed {:016x}, {} mode, {} turns",
+                run.seed,
+                run.mode.name(),
+                run.turns
+            ),
+        );
+    }
+    ctx.print_color(
+        3,
+        height - 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "TAB change order, ESC back",
+    );
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
@@ -0,0 +1,348 @@
+use rltk::{FontCharType, RandomNumberGenerator, Rltk, VirtualKeyCode, RGB};
+use specs::{saveload::SimpleMarkerAllocator, Join, World, WorldExt};
+
+use crate::{
+    components::{
+        self, EquipmentSlot, Equippable, Gold, Key, Name, ProvidesFood, ProvidesHealing, Ranged,
+        Renderable, SerializeMe,
+    },
+    gui::tile_glyph,
+    keybindings::{Action, KeyBindings},
+    map::map::{Map, TileType},
+    spawn::spawner,
+    state::RunState,
+    systems::{damage_system::MAX_RESISTANCE, melee_combat_system::WELL_FED_POWER_BONUS},
+};
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum HelpPage {
+    Keys,
+    Combat,
+    Items,
+    Legend,
+}
+
+impl HelpPage {
+    const ALL: [HelpPage; 4] = [
+        HelpPage::Keys,
+        HelpPage::Combat,
+        HelpPage::Items,
+        HelpPage::Legend,
+    ];
+
+    fn title(&self) -> &'static str {
+        match self {
+            HelpPage::Keys => "Keys",
+            HelpPage::Combat => "Combat",
+            HelpPage::Items => "Items",
+            HelpPage::Legend => "Legend",
+        }
+    }
+
+    fn offset(self, delta: i32) -> HelpPage {
+        let count = HelpPage::ALL.len() as i32;
+        let index = HelpPage::ALL.iter().position(|page| *page == self).unwrap() as i32;
+        HelpPage::ALL[(index + delta).rem_euclid(count) as usize]
+    }
+}
+
+struct LegendEntry {
+    glyph: FontCharType,
+    fg: RGB,
+    names: Vec<String>,
+}
+
+struct ItemType {
+    name: &'static str,
+    rule: &'static str,
+    items: Vec<String>,
+}
+
+/// Legend and item lists taken from one sample of every spawnable entity, built once at startup
+pub struct HelpContent {
+    legend: Vec<LegendEntry>,
+    item_types: Vec<ItemType>,
+}
+
+impl HelpContent {
+    pub fn build() -> Self {
+        // The samples live in a world of their own, so the game is never touched
+        let mut world = World::new();
+        components::register_components(&mut world);
+        world.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+        world.insert(RandomNumberGenerator::new());
+        let samples = spawner::spawn_samples(&mut world);
+
+        // Gold piles are named after their random amount
+        for (name, _) in (
+            &mut world.write_storage::<Name>(),
+            &world.read_storage::<Gold>(),
+        )
+            .join()
+        {
+            name.name = "Gold".to_string();
+        }
+
+        let names = world.read_storage::<Name>();
+        let renderables = world.read_storage::<Renderable>();
+
+        let mut legend: Vec<LegendEntry> = Vec::new();
+        for sample in samples.iter() {
+            let (render, name) = match (renderables.get(*sample), names.get(*sample)) {
+                (Some(render), Some(name)) => (render, name),
+                _ => continue,
+            };
+            match legend
+                .iter_mut()
+                .find(|entry| entry.glyph == render.glyph && entry.fg == render.fg)
+            {
+                Some(entry) => entry.names.push(name.name.clone()),
+                None => legend.push(LegendEntry {
+                    glyph: render.glyph,
+                    fg: render.fg,
+                    names: vec![name.name.clone()],
+                }),
+            }
+        }
+        for (tile, name) in [
+            (TileType::Floor, "Floor"),
+            (TileType::Wall, "Wall"),
+            (TileType::DownStairs, "Stairs down"),
+        ] {
+            let (glyph, fg) = tile_glyph(tile);
+            legend.push(LegendEntry {
+                glyph,
+                fg,
+                names: vec![name.to_string()],
+            });
+        }
+
+        let equippables = world.read_storage::<Equippable>();
+        let slot_of = |sample| equippables.get(sample).map(|equippable| equippable.slot);
+        let healing = world.read_storage::<ProvidesHealing>();
+        let ranged = world.read_storage::<Ranged>();
+        let food = world.read_storage::<ProvidesFood>();
+        let keys = world.read_storage::<Key>();
+        let gold = world.read_storage::<Gold>();
+        let item_types = vec![
+            ItemType {
+                name: "Potions",
+                rule: "Drink to restore hit points.",
+                items: names_matching(&samples, &names, |s| healing.contains(s)),
+            },
+            ItemType {
+                name: "Scrolls",
+                rule: "Read, then pick a target in range.",
+                items: names_matching(&samples, &names, |s| ranged.contains(s)),
+            },
+            ItemType {
+                name: "Weapons",
+                rule: "Equip to add to your power.",
+                items: names_matching(&samples, &names, |s| {
+                    slot_of(s) == Some(EquipmentSlot::Melee)
+                }),
+            },
+            ItemType {
+                name: "Shields",
+                rule: "Equip to add to your defense.",
+                items: names_matching(&samples, &names, |s| {
+                    slot_of(s) == Some(EquipmentSlot::Shield)
+                }),
+            },
+            ItemType {
+                name: "Food",
+                rule: "Eat to stave off hunger. Corpses rot.",
+                items: names_matching(&samples, &names, |s| food.contains(s)),
+            },
+            ItemType {
+                name: "Keys",
+                rule: "Each one opens a single locked door.",
+                items: names_matching(&samples, &names, |s| keys.contains(s)),
+            },
+            ItemType {
+                name: "Gold",
+                rule: "Picked up on the spot, spent at vendors.",
+                items: names_matching(&samples, &names, |s| gold.contains(s)),
+            },
+        ];
+
+        Self { legend, item_types }
+    }
+}
+
+fn names_matching<F>(
+    samples: &[specs::Entity],
+    names: &specs::ReadStorage<Name>,
+    filter: F,
+) -> Vec<String>
+where
+    F: Fn(specs::Entity) -> bool,
+{
+    samples
+        .iter()
+        .filter(|sample| filter(**sample))
+        .filter_map(|sample| names.get(*sample))
+        .map(|name| name.name.clone())
+        .collect()
+}
+
+pub fn help_menu(ecs: &mut World, ctx: &mut Rltk, page: HelpPage) -> RunState {
+    let bindings = ecs.fetch::<KeyBindings>();
+    let map = ecs.fetch::<Map>();
+    draw_frame(ctx, &map, page);
+    match page {
+        HelpPage::Keys => draw_keys(ctx, &bindings),
+        HelpPage::Combat => draw_combat(ctx, &bindings),
+        HelpPage::Items => draw_items(ctx, &ecs.fetch::<HelpContent>()),
+        HelpPage::Legend => draw_legend(ctx, &ecs.fetch::<HelpContent>()),
+    }
+
+    match ctx.key {
+        None => RunState::ShowHelp { page },
+        Some(key) => match key {
+            VirtualKeyCode::Escape => RunState::AwaitingInput,
+            VirtualKeyCode::Tab | VirtualKeyCode::Right => RunState::ShowHelp {
+                page: page.offset(1),
+            },
+            VirtualKeyCode::Left => RunState::ShowHelp {
+                page: page.offset(-1),
+            },
+            _ if bindings.is(key, Action::Help) => RunState::AwaitingInput,
+            _ => RunState::ShowHelp { page },
+        },
+    }
+}
+
+fn draw_frame(ctx: &mut Rltk, map: &Map, page: HelpPage) {
+    ctx.cls();
+    ctx.draw_box(
+        0,
+        0,
+        map.window_width - 1,
+        map.window_height - 1,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+
+    // Page tabs, the current one highlighted
+    let mut x = 3;
+    for tab in HelpPage::ALL {
+        let color = if tab == page {
+            RGB::named(rltk::YELLOW)
+        } else {
+            RGB::named(rltk::GREY)
+        };
+        ctx.print_color(x, 0, color, RGB::named(rltk::BLACK), tab.title());
+        x += tab.title().len() as i32 + 3;
+    }
+    ctx.print_color(
+        3,
+        map.window_height - 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "LEFT/RIGHT or TAB change page, ESC close",
+    );
+}
+
+fn draw_keys(ctx: &mut Rltk, bindings: &KeyBindings) {
+    let mut lines: Vec<(&str, String)> = Action::ALL
+        .iter()
+        .map(|action| (action.description(), bindings.describe(*action)))
+        .collect();
+    lines.push(("Run", "Shift + move".to_string()));
+    lines.push((
+        "Travel to the stairs",
+        format!("Shift + {}", bindings.describe(Action::Descend)),
+    ));
+    lines.push(("Travel to a tile", "Left click".to_string()));
+
+    for (i, (description, keys)) in lines.iter().enumerate() {
+        let y = 2 + i as i32;
+        ctx.print_color(
+            3,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            description,
+        );
+        ctx.print_color(30, y, RGB::named(rltk::CYAN), RGB::named(rltk::BLACK), keys);
+    }
+}
+
+fn draw_combat(ctx: &mut Rltk, bindings: &KeyBindings) {
+    let lines = [
+        "Walk into a monster to attack it.".to_string(),
+        "Damage is the attacker's power minus the target's defense.".to_string(),
+        "An attack that doesn't get through the defense does nothing.".to_string(),
+        "Weapons add to power, shields add to defense.".to_string(),
+        format!("Being well fed adds {} power.", WELL_FED_POWER_BONUS),
+        format!(
+            "Resistance from equipment lowers all damage taken, at most by {}%.",
+            MAX_RESISTANCE
+        ),
+        "Some enchanted equipment regenerates health or extends sight.".to_string(),
+        String::new(),
+        "Hunger goes from well fed to normal, hungry and starving.".to_string(),
+        "Starving hurts every turn until you eat.".to_string(),
+        String::new(),
+        "Traps are hidden until you notice them.".to_string(),
+        format!(
+            "Disarm a trap next to you with {}, it may go off.",
+            bindings.describe(Action::DisarmTrap)
+        ),
+        String::new(),
+        "Going down the stairs heals you to at least half of your hit points.".to_string(),
+        "Monsters drop their gold and items, and sometimes a corpse.".to_string(),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        ctx.print_color(
+            3,
+            2 + i as i32,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            line,
+        );
+    }
+}
+
+fn draw_items(ctx: &mut Rltk, content: &HelpContent) {
+    let mut y = 2;
+    for item_type in content.item_types.iter() {
+        ctx.print_color(
+            3,
+            y,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            item_type.name,
+        );
+        ctx.print_color(
+            13,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            item_type.rule,
+        );
+        ctx.print_color(
+            13,
+            y + 1,
+            RGB::named(rltk::CYAN),
+            RGB::named(rltk::BLACK),
+            item_type.items.join(", "),
+        );
+        y += 3;
+    }
+}
+
+fn draw_legend(ctx: &mut Rltk, content: &HelpContent) {
+    for (i, entry) in content.legend.iter().enumerate() {
+        let y = 2 + i as i32;
+        ctx.set(3, y, entry.fg, RGB::named(rltk::BLACK), entry.glyph);
+        ctx.print_color(
+            6,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            entry.names.join(", "),
+        );
+    }
+}
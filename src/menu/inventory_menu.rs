@@ -1,15 +1,15 @@
 use rltk::{VirtualKeyCode, RGB};
-use specs::{Entity, Join, World, WorldExt};
+use specs::{Entity, Join, ReadStorage, World, WorldExt};
 
 use crate::{
-    components::{
-        Equipped, InBackpack, Name, Position, Ranged, WantsToDropItem, WantsToUnequipItem,
-        WantsToUseItem,
-    },
+    components::{Equipped, InBackpack, Name, Ranged, Rarity},
     map::map::Map,
+    player::{self, PlayerAction},
     state::RunState,
 };
 
+use super::target_menu::start_targeting;
+
 #[derive(PartialEq)]
 pub enum ItemMenuResult {
     Cancel,
@@ -26,17 +26,13 @@ pub fn inventory(ecs: &mut World, ctx: &mut rltk::Rltk) -> RunState {
             let ranged_storage = ecs.read_storage::<Ranged>();
             let is_item_ranged = ranged_storage.get(item);
             if let Some(ranged) = is_item_ranged {
-                RunState::ShowTargeting {
-                    range: ranged.range,
-                    item,
-                }
+                let range = ranged.range;
+                drop(ranged_storage);
+                start_targeting(ecs, range, item)
             } else {
-                let mut intent = ecs.write_storage::<WantsToUseItem>();
-                let player_entity = ecs.fetch::<Entity>();
-                intent
-                    .insert(*player_entity, WantsToUseItem { item, target: None })
-                    .expect("Unable to insert intent");
-                RunState::PlayerTurn
+                drop(ranged_storage);
+                let item = player::marker(ecs, item);
+                player::perform(ecs, PlayerAction::UseItem { item, target: None })
             }
         }
     }
@@ -48,20 +44,8 @@ pub fn drop_item_menu(ecs: &mut World, ctx: &mut rltk::Rltk) -> RunState {
         ItemMenuResult::Cancel => RunState::AwaitingInput,
         ItemMenuResult::NoResponse => RunState::ShowDropItem,
         ItemMenuResult::Selected(item) => {
-            let mut intent = ecs.write_storage::<WantsToDropItem>();
-            let player_entity = ecs.fetch::<Entity>();
-            let positions = ecs.read_storage::<Position>();
-            let player_position = positions.get(*player_entity).unwrap();
-            intent
-                .insert(
-                    *player_entity,
-                    WantsToDropItem {
-                        item,
-                        position: *player_position,
-                    },
-                )
-                .expect("Unable to insert intent");
-            RunState::PlayerTurn
+            let item = player::marker(ecs, item);
+            player::perform(ecs, PlayerAction::DropItem { item })
         }
     }
 }
@@ -72,12 +56,8 @@ pub fn unequip_menu(ecs: &mut World, ctx: &mut rltk::Rltk) -> RunState {
         ItemMenuResult::Cancel => RunState::AwaitingInput,
         ItemMenuResult::NoResponse => RunState::ShowUnequipItem,
         ItemMenuResult::Selected(item) => {
-            let mut intent = ecs.write_storage::<WantsToUnequipItem>();
-            let player_entity = ecs.fetch::<Entity>();
-            intent
-                .insert(*player_entity, WantsToUnequipItem { item })
-                .expect("Unable to insert intent");
-            RunState::PlayerTurn
+            let item = player::marker(ecs, item);
+            player::perform(ecs, PlayerAction::UnequipItem { item })
         }
     }
 }
@@ -93,69 +73,21 @@ fn show_item_inventory(
     let map = ecs.fetch::<Map>();
     let entities = ecs.entities();
 
-    let inventory = (&backpack, &names)
-        .join()
-        .filter(|(item, _)| item.owner == *player_entity);
-    let count = usize::min((map.window_height - 4) as usize, inventory.count()) as u16;
+    let rarities = ecs.read_storage::<Rarity>();
 
-    let mut y = map.window_height / 2 - count / 2;
-    ctx.draw_box(
-        15,
-        y - 2,
-        31,
-        (count + 3) as i32,
-        RGB::named(rltk::WHITE),
-        RGB::named(rltk::BLACK),
-    );
-    ctx.print_color(
-        18,
-        y - 2,
-        RGB::named(rltk::YELLOW),
-        RGB::named(rltk::BLACK),
-        window_title,
-    );
-    ctx.print_color(
-        18,
-        y + count + 1,
-        RGB::named(rltk::YELLOW),
-        RGB::named(rltk::BLACK),
-        "ESCAPE to cancel",
-    );
-
-    let mut equippable: Vec<Entity> = Vec::new();
-    for (j, (entity, _pack, name)) in (&entities, &backpack, &names)
+    let (items, labels): (Vec<Entity>, Vec<(String, RGB)>) = (&entities, &backpack, &names)
         .join()
         .filter(|(_, item, _)| item.owner == *player_entity)
-        .enumerate()
-    {
-        ctx.set(
-            17,
-            y,
-            RGB::named(rltk::WHITE),
-            RGB::named(rltk::BLACK),
-            rltk::to_cp437('('),
-        );
-        ctx.set(
-            18,
-            y,
-            RGB::named(rltk::YELLOW),
-            RGB::named(rltk::BLACK),
-            97 + j as rltk::FontCharType,
-        );
-        ctx.set(
-            19,
-            y,
-            RGB::named(rltk::WHITE),
-            RGB::named(rltk::BLACK),
-            rltk::to_cp437(')'),
-        );
-
-        ctx.print(21, y, &name.name.to_string());
-        equippable.push(entity);
-        y += 1;
-    }
-
-    select_menu(ctx, count, equippable)
+        .map(|(entity, _, name)| {
+            (
+                entity,
+                (name.name.to_string(), item_color(&rarities, entity)),
+            )
+        })
+        .unzip();
+    let count = draw_item_list(ctx, &map, window_title, &labels);
+
+    select_menu(ctx, count, items)
 }
 
 fn show_unequip_item(ecs: &mut World, ctx: &mut rltk::Rltk) -> ItemMenuResult {
@@ -165,16 +97,49 @@ fn show_unequip_item(ecs: &mut World, ctx: &mut rltk::Rltk) -> ItemMenuResult {
     let map = ecs.fetch::<Map>();
     let entities = ecs.entities();
 
-    let inventory = (&backpack, &names)
+    let rarities = ecs.read_storage::<Rarity>();
+
+    let (items, labels): (Vec<Entity>, Vec<(String, RGB)>) = (&entities, &backpack, &names)
         .join()
-        .filter(|(item, _)| item.owner == *player_entity);
-    let count = usize::min((map.window_height - 4) as usize, inventory.count()) as u16;
+        .filter(|(_, item, _)| item.owner == *player_entity)
+        .map(|(entity, _, name)| {
+            (
+                entity,
+                (name.name.to_string(), item_color(&rarities, entity)),
+            )
+        })
+        .unzip();
+    let count = draw_item_list(ctx, &map, "Unequip which item?", &labels);
+
+    select_menu(ctx, count, items)
+}
+
+pub fn item_color(rarities: &ReadStorage<Rarity>, item: Entity) -> RGB {
+    rarities
+        .get(item)
+        .map_or(RGB::named(rltk::WHITE), |rarity| rarity.tier.color())
+}
+
+/// Draws a lettered list of items in a box and returns how many of them fit on the screen
+pub fn draw_item_list(
+    ctx: &mut rltk::Rltk,
+    map: &Map,
+    window_title: &str,
+    labels: &[(String, RGB)],
+) -> u16 {
+    let count = usize::min((map.window_height - 4) as usize, labels.len()) as u16;
+    let width = labels
+        .iter()
+        .map(|(label, _)| label.len() as i32 + 6)
+        .max()
+        .unwrap_or(0)
+        .max(31);
 
     let mut y = map.window_height / 2 - count / 2;
     ctx.draw_box(
         15,
         y - 2,
-        31,
+        width,
         (count + 3) as i32,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::BLACK),
@@ -184,7 +149,7 @@ fn show_unequip_item(ecs: &mut World, ctx: &mut rltk::Rltk) -> ItemMenuResult {
         y - 2,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "Unequip which item?",
+        window_title,
     );
     ctx.print_color(
         18,
@@ -194,12 +159,7 @@ fn show_unequip_item(ecs: &mut World, ctx: &mut rltk::Rltk) -> ItemMenuResult {
         "ESCAPE to cancel",
     );
 
-    let mut equippable: Vec<Entity> = Vec::new();
-    for (j, (entity, _pack, name)) in (&entities, &backpack, &names)
-        .join()
-        .filter(|(_, item, _)| item.owner == *player_entity)
-        .enumerate()
-    {
+    for (j, (label, color)) in labels.iter().take(count as usize).enumerate() {
         ctx.set(
             17,
             y,
@@ -222,15 +182,14 @@ fn show_unequip_item(ecs: &mut World, ctx: &mut rltk::Rltk) -> ItemMenuResult {
             rltk::to_cp437(')'),
         );
 
-        ctx.print(21, y, &name.name.to_string());
-        equippable.push(entity);
+        ctx.print_color(21, y, *color, RGB::named(rltk::BLACK), label);
         y += 1;
     }
 
-    select_menu(ctx, count, equippable)
+    count
 }
 
-fn select_menu(ctx: &mut rltk::Rltk, count: u16, equippable: Vec<Entity>) -> ItemMenuResult {
+pub fn select_menu(ctx: &mut rltk::Rltk, count: u16, equippable: Vec<Entity>) -> ItemMenuResult {
     match ctx.key {
         None => ItemMenuResult::NoResponse,
         Some(key) => match key {
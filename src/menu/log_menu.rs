@@ -0,0 +1,130 @@
+use rltk::{Rltk, VirtualKeyCode, RGB};
+use specs::World;
+
+use crate::{
+    gamelog::{GameLog, LogCategory, LogEntry},
+    gui::draw_log_entry,
+    keybindings::{Action, KeyBindings},
+    map::map::Map,
+    state::RunState,
+};
+
+pub fn log_menu(
+    ecs: &mut World,
+    ctx: &mut Rltk,
+    scroll: u16,
+    filter: Option<LogCategory>,
+) -> RunState {
+    let log = ecs.fetch::<GameLog>();
+    let map = ecs.fetch::<Map>();
+
+    let entries: Vec<&LogEntry> = log
+        .entries()
+        .filter(|entry| filter.is_none_or(|category| entry.category == category))
+        .collect();
+    let page = map.window_height - 4;
+    let max_scroll = entries.len().saturating_sub(page as usize) as u16;
+    let scroll = u16::min(scroll, max_scroll);
+
+    draw_log(ctx, &map, &entries, scroll, filter);
+
+    let bindings = ecs.fetch::<KeyBindings>();
+    let scroll = match ctx.key {
+        None => scroll,
+        Some(key) => match key {
+            VirtualKeyCode::Escape => return RunState::AwaitingInput,
+            VirtualKeyCode::Tab => {
+                return RunState::ShowLog {
+                    scroll: 0,
+                    filter: next_filter(filter),
+                }
+            }
+            VirtualKeyCode::PageUp => scroll + page,
+            VirtualKeyCode::PageDown => scroll.saturating_sub(page),
+            VirtualKeyCode::Home => max_scroll,
+            VirtualKeyCode::End => 0,
+            _ => match bindings.action(key) {
+                Some(Action::MessageLog) => return RunState::AwaitingInput,
+                Some(Action::MoveNorth) => scroll + 1,
+                Some(Action::MoveSouth) => scroll.saturating_sub(1),
+                _ => scroll,
+            },
+        },
+    };
+
+    RunState::ShowLog {
+        scroll: u16::min(scroll, max_scroll),
+        filter,
+    }
+}
+
+fn draw_log(
+    ctx: &mut Rltk,
+    map: &Map,
+    entries: &[&LogEntry],
+    scroll: u16,
+    filter: Option<LogCategory>,
+) {
+    ctx.cls();
+    ctx.draw_box(
+        0,
+        0,
+        map.window_width - 1,
+        map.window_height - 1,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    let title = match filter {
+        None => "Message log: All".to_string(),
+        Some(category) => format!("Message log: {}", category.name()),
+    };
+    ctx.print_color(
+        3,
+        0,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        title,
+    );
+    ctx.print_color(
+        3,
+        map.window_height - 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "UP/DOWN scroll, PGUP/PGDN page, TAB filter, ESC close",
+    );
+
+    // The newest entries are at the bottom, scrolling moves the view back in time
+    let mut y = map.window_height - 3;
+    for entry in entries.iter().rev().skip(scroll as usize) {
+        if y < 2 {
+            break;
+        }
+        ctx.print_color(
+            2,
+            y,
+            RGB::named(rltk::GREY),
+            RGB::named(rltk::BLACK),
+            format!("{:>5}", entry.turn),
+        );
+        ctx.print_color(
+            8,
+            y,
+            entry.category.color(),
+            RGB::named(rltk::BLACK),
+            entry.category.name(),
+        );
+        draw_log_entry(ctx, 15, y, entry);
+        y -= 1;
+    }
+}
+
+fn next_filter(filter: Option<LogCategory>) -> Option<LogCategory> {
+    let categories = LogCategory::ALL;
+    match filter {
+        None => Some(categories[0]),
+        Some(category) => {
+            let index = categories.iter().position(|c| *c == category).unwrap();
+            categories.get(index + 1).copied()
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use rltk::{Rltk, VirtualKeyCode, RGB};
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::{
+    camera::{Camera, VIEW_WIDTH},
+    components::{
+        Alarm, Alerted, AreaOfEffect, BaseValue, CombatStats, Confusion, DefenseBonus, Description,
+        Door, EntryTrigger, EquipmentSlot, Equippable, Freshness, Hidden, InflictsDamage, Item,
+        LifeRegen, Locked, MeleePowerBonus, Name, Pit, Position, ProvidesFood, ProvidesHealing,
+        Ranged, Rarity, Renderable, Resistance, SingleActivation, Teleports, VisionBonus,
+    },
+    keybindings::{Action, KeyBindings},
+    map::map::{Map, TileType},
+    state::RunState,
+};
+
+const PANEL_WIDTH: u16 = 34;
+
+pub fn look_menu(ecs: &mut World, ctx: &mut Rltk, x: u16, y: u16, index: usize) -> RunState {
+    let camera = Camera::from_ecs(ecs);
+    let entities = entities_at(ecs, x, y);
+    let index = if entities.is_empty() {
+        0
+    } else {
+        index % entities.len()
+    };
+
+    let lines = describe(ecs, x, y, &entities, index);
+    if let Some((screen_x, screen_y)) = camera.to_screen(x, y) {
+        ctx.set_bg(screen_x, screen_y, RGB::named(rltk::CYAN));
+        draw_panel(ctx, screen_x, &lines);
+    }
+
+    let bindings = ecs.fetch::<KeyBindings>();
+    let (delta_x, delta_y) = match ctx.key {
+        None => return RunState::Look { x, y, index },
+        Some(key) => match key {
+            VirtualKeyCode::Escape => return RunState::AwaitingInput,
+            VirtualKeyCode::Tab | VirtualKeyCode::Space => {
+                return RunState::Look {
+                    x,
+                    y,
+                    index: index + 1,
+                }
+            }
+            _ if bindings.is(key, Action::Look) => return RunState::AwaitingInput,
+            _ => match bindings.direction(key) {
+                None => return RunState::Look { x, y, index },
+                Some(delta) => delta,
+            },
+        },
+    };
+
+    // The cursor can't leave the visible part of the map
+    let new_x = x as i32 + delta_x;
+    let new_y = y as i32 + delta_y;
+    if new_x < 0 || new_y < 0 || camera.to_screen(new_x as u16, new_y as u16).is_none() {
+        return RunState::Look { x, y, index };
+    }
+    let map = ecs.fetch::<Map>();
+    if new_x >= map.width as i32 || new_y >= map.height as i32 {
+        return RunState::Look { x, y, index };
+    }
+    RunState::Look {
+        x: new_x as u16,
+        y: new_y as u16,
+        index: 0,
+    }
+}
+
+// Only the entities on visible tiles can be examined
+fn entities_at(ecs: &World, x: u16, y: u16) -> Vec<Entity> {
+    let map = ecs.fetch::<Map>();
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    let names = ecs.read_storage::<Name>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let renderables = ecs.read_storage::<Renderable>();
+
+    if !map.visible_tiles[map.index_from_xy(x, y)] {
+        return Vec::new();
+    }
+    let mut found: Vec<(Entity, i32)> = (&entities, &positions, &names, &renderables, !&hidden)
+        .join()
+        .filter(|(_, pos, _, _, _)| pos.x == x && pos.y == y)
+        .map(|(entity, _, _, render, _)| (entity, render.render_order))
+        .collect();
+    found.sort_by_key(|(_, render_order)| *render_order);
+    found.into_iter().map(|(entity, _)| entity).collect()
+}
+
+fn describe(ecs: &World, x: u16, y: u16, entities: &[Entity], index: usize) -> Vec<(String, RGB)> {
+    let map = ecs.fetch::<Map>();
+    let idx = map.index_from_xy(x, y);
+    let mut lines = Vec::new();
+
+    if !map.revealed_tiles[idx] {
+        lines.push(("You don't know what's there.".to_string(), grey()));
+        return lines;
+    }
+
+    let mut tile = match map.tiles[idx] {
+        TileType::Floor => "Floor",
+        TileType::Wall => "Wall",
+        TileType::DownStairs => "Stairs leading down",
+    }
+    .to_string();
+    if map.bloodstains.contains(&idx) {
+        tile += ", bloodstained";
+    }
+    if !map.visible_tiles[idx] {
+        tile += " (remembered)";
+    }
+    lines.push((tile, grey()));
+
+    let entity = match entities.get(index) {
+        None => return lines,
+        Some(entity) => *entity,
+    };
+
+    let names = ecs.read_storage::<Name>();
+    let rarities = ecs.read_storage::<Rarity>();
+    let name_color = rarities
+        .get(entity)
+        .map_or(RGB::named(rltk::WHITE), |rarity| rarity.tier.color());
+    lines.push((String::new(), white()));
+    lines.push((
+        format!(
+            "{} ({}/{})",
+            names.get(entity).unwrap().name,
+            index + 1,
+            entities.len()
+        ),
+        name_color,
+    ));
+
+    if let Some(description) = ecs.read_storage::<Description>().get(entity) {
+        for line in wrap(&description.text, PANEL_WIDTH as usize - 2) {
+            lines.push((line, grey()));
+        }
+    }
+    lines.push((String::new(), white()));
+
+    describe_creature(ecs, entity, &mut lines);
+    describe_item(ecs, entity, &mut lines);
+    describe_trap(ecs, entity, &mut lines);
+    if let Some(door) = ecs.read_storage::<Door>().get(entity) {
+        let state = if ecs.read_storage::<Locked>().contains(entity) {
+            "Locked"
+        } else if door.open {
+            "Open"
+        } else {
+            "Closed"
+        };
+        lines.push((state.to_string(), white()));
+    }
+
+    lines
+}
+
+fn describe_creature(ecs: &World, entity: Entity, lines: &mut Vec<(String, RGB)>) {
+    let (hp, max_hp) = match ecs.read_storage::<CombatStats>().get(entity) {
+        None => return,
+        Some(stats) => (stats.hp, stats.max_hp),
+    };
+
+    // Nobody can tell the exact amount of health just by looking
+    let health = hp * 100 / u32::max(1, max_hp);
+    let (condition, color) = match health {
+        100.. => ("Unhurt", RGB::named(rltk::GREEN)),
+        67..=99 => ("Lightly wounded", RGB::named(rltk::YELLOW)),
+        34..=66 => ("Wounded", RGB::named(rltk::ORANGE)),
+        11..=33 => ("Badly wounded", RGB::named(rltk::RED)),
+        _ => ("Near death", RGB::named(rltk::RED)),
+    };
+    lines.push((condition.to_string(), color));
+
+    if let Some(confusion) = ecs.read_storage::<Confusion>().get(entity) {
+        lines.push((
+            format!("Confused for {} turns", confusion.turns),
+            RGB::named(rltk::MAGENTA),
+        ));
+    }
+    if ecs.read_storage::<Alerted>().contains(entity) {
+        lines.push(("Alerted".to_string(), RGB::named(rltk::YELLOW)));
+    }
+}
+
+fn describe_item(ecs: &World, entity: Entity, lines: &mut Vec<(String, RGB)>) {
+    if !ecs.read_storage::<Item>().contains(entity) {
+        return;
+    }
+
+    if let Some(rarity) = ecs.read_storage::<Rarity>().get(entity) {
+        lines.push((rarity.tier.name().to_string(), rarity.tier.color()));
+    }
+    if let Some(equippable) = ecs.read_storage::<Equippable>().get(entity) {
+        let slot = match equippable.slot {
+            EquipmentSlot::Melee => "Melee weapon",
+            EquipmentSlot::Shield => "Shield",
+        };
+        lines.push((slot.to_string(), white()));
+    }
+    if let Some(bonus) = ecs.read_storage::<MeleePowerBonus>().get(entity) {
+        lines.push((format!("{:+} power", bonus.power), white()));
+    }
+    if let Some(bonus) = ecs.read_storage::<DefenseBonus>().get(entity) {
+        lines.push((format!("{:+} defense", bonus.defense), white()));
+    }
+    if let Some(resistance) = ecs.read_storage::<Resistance>().get(entity) {
+        lines.push((
+            format!("{}% damage resistance", resistance.percent),
+            white(),
+        ));
+    }
+    if let Some(regen) = ecs.read_storage::<LifeRegen>().get(entity) {
+        lines.push((
+            format!("Regenerates 1 hp every {} turns", regen.interval),
+            white(),
+        ));
+    }
+    if let Some(bonus) = ecs.read_storage::<VisionBonus>().get(entity) {
+        lines.push((format!("+{} sight range", bonus.range), white()));
+    }
+    if let Some(healing) = ecs.read_storage::<ProvidesHealing>().get(entity) {
+        lines.push((
+            format!("Heals {} hp", healing.amount),
+            RGB::named(rltk::GREEN),
+        ));
+    }
+    if let Some(damage) = ecs.read_storage::<InflictsDamage>().get(entity) {
+        lines.push((
+            format!("Deals {} damage", damage.damage),
+            RGB::named(rltk::RED),
+        ));
+    }
+    if let Some(ranged) = ecs.read_storage::<Ranged>().get(entity) {
+        lines.push((format!("Range {}", ranged.range), white()));
+    }
+    if let Some(area) = ecs.read_storage::<AreaOfEffect>().get(entity) {
+        lines.push((format!("Affects radius {}", area.radius), white()));
+    }
+    if let Some(confusion) = ecs.read_storage::<Confusion>().get(entity) {
+        lines.push((format!("Confuses for {} turns", confusion.turns), white()));
+    }
+    if ecs.read_storage::<ProvidesFood>().contains(entity) {
+        lines.push(("Satisfies hunger".to_string(), white()));
+    }
+    if let Some(freshness) = ecs.read_storage::<Freshness>().get(entity) {
+        lines.push((format!("Rots in {} turns", freshness.turns), white()));
+    }
+    if let Some(value) = ecs.read_storage::<BaseValue>().get(entity) {
+        lines.push((
+            format!("Worth {} gold", value.value),
+            RGB::named(rltk::GOLD),
+        ));
+    }
+}
+
+fn describe_trap(ecs: &World, entity: Entity, lines: &mut Vec<(String, RGB)>) {
+    if !ecs.read_storage::<EntryTrigger>().contains(entity) {
+        return;
+    }
+
+    let mut effects = Vec::new();
+    if let Some(damage) = ecs.read_storage::<InflictsDamage>().get(entity) {
+        effects.push(format!("Deals {} damage", damage.damage));
+    }
+    if let Some(area) = ecs.read_storage::<AreaOfEffect>().get(entity) {
+        effects.push(format!("Affects radius {}", area.radius));
+    }
+    if let Some(confusion) = ecs.read_storage::<Confusion>().get(entity) {
+        effects.push(format!("Confuses for {} turns", confusion.turns));
+    }
+    if ecs.read_storage::<Teleports>().contains(entity) {
+        effects.push("Teleports the victim".to_string());
+    }
+    if ecs.read_storage::<Alarm>().contains(entity) {
+        effects.push("Alerts all monsters".to_string());
+    }
+    if ecs.read_storage::<Pit>().contains(entity) {
+        effects.push("Drops the victim a level down".to_string());
+    }
+
+    lines.push(("Trap".to_string(), RGB::named(rltk::RED)));
+    for effect in effects {
+        lines.push((effect, white()));
+    }
+    if ecs.read_storage::<SingleActivation>().contains(entity) {
+        lines.push(("Triggers only once".to_string(), grey()));
+    } else {
+        lines.push(("Triggers every time".to_string(), grey()));
+    }
+}
+
+fn draw_panel(ctx: &mut Rltk, cursor_x: i32, lines: &[(String, RGB)]) {
+    // Keep the panel on the other side of the screen, so it doesn't cover the cursor
+    let x = if cursor_x < (VIEW_WIDTH / 2) as i32 {
+        VIEW_WIDTH - PANEL_WIDTH - 1
+    } else {
+        1
+    };
+    let height = lines.len() as u16 + 1;
+    ctx.draw_box(
+        x,
+        1,
+        PANEL_WIDTH,
+        height,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        x + 2,
+        1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Look",
+    );
+    for (i, (line, color)) in lines.iter().enumerate() {
+        ctx.print_color(x + 2, 2 + i as u16, *color, RGB::named(rltk::BLACK), line);
+    }
+    ctx.print_color(
+        x + 2,
+        1 + height,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "TAB next, ESC exit",
+    );
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + word.len() + 1 > width {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn white() -> RGB {
+    RGB::named(rltk::WHITE)
+}
+
+fn grey() -> RGB {
+    RGB::named(rltk::GREY)
+}
@@ -2,14 +2,20 @@ use rltk::RGB;
 use specs::World;
 
 use crate::{
+    history::RunOrder,
+    keybindings::{Action, KeyBindings},
+    menu::hall_of_fame::HallOfFame,
+    menu::slot_menu::{self, Failure, SlotBrowser, SlotMenuResult, SlotMode, SlotPrompt},
     state::{GlobalState, RunState},
-    systems::{saveload_system, Systems},
+    systems::{autosave, save_slots, Systems},
 };
 
 #[derive(PartialEq, Clone, Copy)]
 pub enum MainMenuSelection {
+    ResumeSession,
     NewGame,
     LoadGame,
+    HallOfFame,
     Quit,
 }
 
@@ -24,35 +30,114 @@ pub fn main_menu(
     ctx: &mut rltk::Rltk,
     mut current_selection: MainMenuSelection,
 ) -> GlobalState {
-    let save_exists = saveload_system::does_save_exist();
-    print_main_menu(ctx, current_selection, save_exists);
-    let selected_menu = select_menu(ctx, current_selection, save_exists);
+    let entries = menu_entries();
+    if !entries.iter().any(|(entry, _)| *entry == current_selection) {
+        current_selection = entries[0].0;
+    }
+    print_main_menu(ctx, &entries, current_selection);
+    let selected_menu = select_menu(
+        ctx,
+        &ecs.fetch::<KeyBindings>(),
+        &entries,
+        current_selection,
+    );
     match selected_menu {
         MainMenuResult::NoSelection { selected } => GlobalState::MainMenu {
             selected_menu: selected,
         },
         MainMenuResult::Selected { selected } => match selected {
-            MainMenuSelection::NewGame => GlobalState::Gameplay {
-                phase: RunState::NewGame,
-                systems: Systems::new(),
+            MainMenuSelection::ResumeSession => match autosave::resume(ecs) {
+                Ok(()) => {
+                    autosave::begin_session();
+                    GlobalState::Gameplay {
+                        phase: RunState::AwaitingInput,
+                        systems: Systems::new(),
+                    }
+                }
+                Err(error) => {
+                    // Neither autosave loads, so there is nothing left to resume
+                    autosave::end_session(ecs);
+                    let mut browser = ecs.fetch_mut::<SlotBrowser>();
+                    browser.refresh();
+                    browser.failure = Some(Failure {
+                        title: "Resume failed",
+                        error,
+                    });
+                    GlobalState::LoadGame {
+                        selected: 0,
+                        prompt: SlotPrompt::None,
+                    }
+                }
             },
-            MainMenuSelection::LoadGame => {
-                saveload_system::load_game(ecs);
-                saveload_system::delete_save();
+            MainMenuSelection::NewGame => {
+                autosave::begin_session();
                 GlobalState::Gameplay {
-                    phase: RunState::AwaitingInput,
+                    phase: RunState::NewGame,
                     systems: Systems::new(),
                 }
             }
+            MainMenuSelection::LoadGame => {
+                ecs.fetch_mut::<SlotBrowser>().refresh();
+                GlobalState::LoadGame {
+                    selected: 0,
+                    prompt: SlotPrompt::None,
+                }
+            }
+            MainMenuSelection::HallOfFame => {
+                ecs.fetch_mut::<HallOfFame>().refresh(RunOrder::Score);
+                GlobalState::HallOfFame {
+                    order: RunOrder::Score,
+                    selected: 0,
+                }
+            }
             MainMenuSelection::Quit => ::std::process::exit(0),
         },
     }
 }
 
+/// The slot browser opened from the main menu, backing out returns to it
+pub fn load_menu(
+    ecs: &mut World,
+    ctx: &mut rltk::Rltk,
+    selected: usize,
+    prompt: SlotPrompt,
+) -> GlobalState {
+    match slot_menu::slot_menu(ecs, ctx, SlotMode::Load, selected, prompt) {
+        SlotMenuResult::NoSelection { selected, prompt } => {
+            GlobalState::LoadGame { selected, prompt }
+        }
+        SlotMenuResult::Cancel => GlobalState::MainMenu {
+            selected_menu: MainMenuSelection::LoadGame,
+        },
+        SlotMenuResult::Done => {
+            autosave::begin_session();
+            GlobalState::Gameplay {
+                phase: RunState::AwaitingInput,
+                systems: Systems::new(),
+            }
+        }
+    }
+}
+
+// The entries shown, resuming only after a session that ended without quitting and loading only with saves
+fn menu_entries() -> Vec<(MainMenuSelection, &'static str)> {
+    let mut entries = Vec::new();
+    if autosave::interrupted_session() {
+        entries.push((MainMenuSelection::ResumeSession, "Resume Last Session"));
+    }
+    entries.push((MainMenuSelection::NewGame, "Begin New Game"));
+    if save_slots::any_slots() {
+        entries.push((MainMenuSelection::LoadGame, "Load Game"));
+    }
+    entries.push((MainMenuSelection::HallOfFame, "Hall of Fame"));
+    entries.push((MainMenuSelection::Quit, "Quit"));
+    entries
+}
+
 fn print_main_menu(
     ctx: &mut rltk::Rltk,
-    mut current_selection: MainMenuSelection,
-    save_exists: bool,
+    entries: &[(MainMenuSelection, &str)],
+    current_selection: MainMenuSelection,
 ) {
     ctx.print_color_centered(
         15,
@@ -61,30 +146,26 @@ fn print_main_menu(
         "Rust Roguelike Tutorial",
     );
 
-    let mut new_game_fg = RGB::named(rltk::WHITE);
-    let mut load_game_fg = RGB::named(rltk::WHITE);
-    let mut quit_game_fg = RGB::named(rltk::WHITE);
-
-    match current_selection {
-        MainMenuSelection::NewGame => new_game_fg = RGB::named(rltk::MAGENTA),
-        MainMenuSelection::LoadGame => load_game_fg = RGB::named(rltk::MAGENTA),
-        MainMenuSelection::Quit => quit_game_fg = RGB::named(rltk::MAGENTA),
-    };
-
-    ctx.print_color_centered(24, new_game_fg, RGB::named(rltk::BLACK), "Begin New Game");
-    if save_exists {
-        ctx.print_color_centered(25, load_game_fg, RGB::named(rltk::BLACK), "Load Game");
-    } else if current_selection == MainMenuSelection::LoadGame {
-        current_selection = MainMenuSelection::NewGame;
+    for (i, (entry, label)) in entries.iter().enumerate() {
+        let fg = if *entry == current_selection {
+            RGB::named(rltk::MAGENTA)
+        } else {
+            RGB::named(rltk::WHITE)
+        };
+        ctx.print_color_centered(24 + i as i32, fg, RGB::named(rltk::BLACK), label);
     }
-    ctx.print_color_centered(26, quit_game_fg, RGB::named(rltk::BLACK), "Quit");
 }
 
 fn select_menu(
     ctx: &mut rltk::Rltk,
+    bindings: &KeyBindings,
+    entries: &[(MainMenuSelection, &str)],
     current_selection: MainMenuSelection,
-    save_exists: bool,
 ) -> MainMenuResult {
+    let current = entries
+        .iter()
+        .position(|(entry, _)| *entry == current_selection)
+        .unwrap_or(0);
     match ctx.key {
         None => MainMenuResult::NoSelection {
             selected: current_selection,
@@ -92,38 +173,12 @@ fn select_menu(
         Some(rltk::VirtualKeyCode::Escape) => MainMenuResult::NoSelection {
             selected: MainMenuSelection::Quit,
         },
-        Some(rltk::VirtualKeyCode::Numpad8 | rltk::VirtualKeyCode::W) => {
-            let new_selection = match current_selection {
-                MainMenuSelection::NewGame => MainMenuSelection::Quit,
-                MainMenuSelection::LoadGame => MainMenuSelection::NewGame,
-                MainMenuSelection::Quit => {
-                    if save_exists {
-                        MainMenuSelection::LoadGame
-                    } else {
-                        MainMenuSelection::NewGame
-                    }
-                }
-            };
-            MainMenuResult::NoSelection {
-                selected: new_selection,
-            }
-        }
-        Some(rltk::VirtualKeyCode::Numpad2 | rltk::VirtualKeyCode::S) => {
-            let new_selection = match current_selection {
-                MainMenuSelection::NewGame => {
-                    if save_exists {
-                        MainMenuSelection::LoadGame
-                    } else {
-                        MainMenuSelection::Quit
-                    }
-                }
-                MainMenuSelection::LoadGame => MainMenuSelection::Quit,
-                MainMenuSelection::Quit => MainMenuSelection::NewGame,
-            };
-            MainMenuResult::NoSelection {
-                selected: new_selection,
-            }
-        }
+        Some(key) if bindings.is(key, Action::MoveNorth) => MainMenuResult::NoSelection {
+            selected: entries[(current + entries.len() - 1) % entries.len()].0,
+        },
+        Some(key) if bindings.is(key, Action::MoveSouth) => MainMenuResult::NoSelection {
+            selected: entries[(current + 1) % entries.len()].0,
+        },
         Some(rltk::VirtualKeyCode::Return) => MainMenuResult::Selected {
             selected: current_selection,
         },
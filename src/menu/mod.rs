@@ -1,5 +1,14 @@
+pub mod character_menu;
 pub mod game_over;
+pub mod hall_of_fame;
+pub mod help_menu;
 pub mod inventory_menu;
+pub mod log_menu;
+pub mod look_menu;
 pub mod main_menu;
+pub mod overview_menu;
 pub mod pause_menu;
+pub mod replay_viewer;
+pub mod slot_menu;
 pub mod target_menu;
+pub mod trade_menu;
@@ -0,0 +1,247 @@
+use rltk::{FontCharType, Rltk, VirtualKeyCode, RGB};
+use specs::{Join, World, WorldExt};
+
+use crate::{
+    components::{Hidden, Item, Position},
+    gui::tile_glyph,
+    keybindings::{typed_char, Action, KeyBindings},
+    map::map::{Map, TileType},
+    state::RunState,
+};
+
+// Longest note the player can leave on a tile
+const MAX_NOTE_LENGTH: usize = 40;
+
+// How much of the screen the overview may use, the rest is for the title and the status lines
+const OVERVIEW_WIDTH: u16 = 80;
+const OVERVIEW_HEIGHT: u16 = 46;
+
+pub fn overview_menu(ecs: &mut World, ctx: &mut Rltk, cursor: Position, editing: bool) -> RunState {
+    let scale = overview_scale(&ecs.fetch::<Map>());
+    draw_overview(ecs, ctx, cursor, scale, editing);
+
+    let key = match ctx.key {
+        None => return RunState::ShowOverview { cursor, editing },
+        Some(key) => key,
+    };
+
+    if editing {
+        edit_note(ecs, ctx, cursor, key);
+        let done = matches!(
+            key,
+            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter | VirtualKeyCode::Escape
+        );
+        return RunState::ShowOverview {
+            cursor,
+            editing: !done,
+        };
+    }
+
+    let bindings = ecs.fetch::<KeyBindings>();
+    match key {
+        VirtualKeyCode::Escape => return RunState::AwaitingInput,
+        VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+            return RunState::ShowOverview {
+                cursor,
+                editing: true,
+            }
+        }
+        VirtualKeyCode::Delete | VirtualKeyCode::Back => {
+            let mut map = ecs.fetch_mut::<Map>();
+            let idx = map.index_from_xy(cursor.x, cursor.y);
+            map.notes.remove(&idx);
+        }
+        _ if bindings.is(key, Action::Overview) => return RunState::AwaitingInput,
+        _ => {
+            // The cursor moves one overview cell at a time
+            if let Some((delta_x, delta_y)) = bindings.direction(key) {
+                let map = ecs.fetch::<Map>();
+                let x = cursor.x as i32 + delta_x * scale as i32;
+                let y = cursor.y as i32 + delta_y * scale as i32;
+                if x >= 0 && y >= 0 && x < map.width as i32 && y < map.height as i32 {
+                    return RunState::ShowOverview {
+                        cursor: Position {
+                            x: x as u16,
+                            y: y as u16,
+                        },
+                        editing,
+                    };
+                }
+            }
+        }
+    }
+    RunState::ShowOverview { cursor, editing }
+}
+
+// How many map tiles, in each direction, share one overview cell
+fn overview_scale(map: &Map) -> u16 {
+    u16::max(
+        map.width.div_ceil(OVERVIEW_WIDTH),
+        map.height.div_ceil(OVERVIEW_HEIGHT),
+    )
+    .max(1)
+}
+
+fn edit_note(ecs: &mut World, ctx: &Rltk, cursor: Position, key: VirtualKeyCode) {
+    let mut map = ecs.fetch_mut::<Map>();
+    let idx = map.index_from_xy(cursor.x, cursor.y);
+    let note = map.notes.entry(idx).or_default();
+    match key {
+        VirtualKeyCode::Back => {
+            note.pop();
+        }
+        _ => {
+            if let Some(typed) = typed_char(key, ctx.shift) {
+                if note.len() < MAX_NOTE_LENGTH {
+                    note.push(typed);
+                }
+            }
+        }
+    }
+    if note.trim().is_empty()
+        && matches!(
+            key,
+            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter | VirtualKeyCode::Escape
+        )
+    {
+        map.notes.remove(&idx);
+    }
+}
+
+// What one overview cell shows, the most important thing in it wins
+#[derive(Clone, Copy)]
+enum Mark {
+    Unknown,
+    Tile(TileType),
+    Item,
+    Note,
+    Stairs,
+    Player,
+}
+
+fn mark_glyph(mark: Mark) -> Option<(FontCharType, RGB)> {
+    match mark {
+        Mark::Unknown => None,
+        Mark::Tile(tile) => Some(tile_glyph(tile)),
+        Mark::Item => Some((rltk::to_cp437('!'), RGB::named(rltk::MAGENTA))),
+        Mark::Note => Some((rltk::to_cp437('?'), RGB::named(rltk::WHITE))),
+        Mark::Stairs => Some(tile_glyph(TileType::DownStairs)),
+        Mark::Player => Some((rltk::to_cp437('@'), RGB::named(rltk::YELLOW))),
+    }
+}
+
+fn tile_mark(tile: TileType) -> Mark {
+    match tile {
+        TileType::DownStairs => Mark::Stairs,
+        _ => Mark::Tile(tile),
+    }
+}
+
+// Floors are more telling than walls when a cell covers both
+fn priority(mark: Mark) -> u8 {
+    match mark {
+        Mark::Unknown => 0,
+        Mark::Tile(TileType::Wall) => 1,
+        Mark::Tile(_) => 2,
+        Mark::Item => 3,
+        Mark::Note => 4,
+        Mark::Stairs => 5,
+        Mark::Player => 6,
+    }
+}
+
+fn more_important(first: Mark, second: Mark) -> Mark {
+    if priority(second) > priority(first) {
+        second
+    } else {
+        first
+    }
+}
+
+fn draw_overview(ecs: &World, ctx: &mut Rltk, cursor: Position, scale: u16, editing: bool) {
+    let map = ecs.fetch::<Map>();
+    let player_pos = *ecs.fetch::<Position>();
+    ctx.cls();
+    let title = if scale == 1 {
+        format!("Depth {}", map.depth)
+    } else {
+        format!("Depth {} (1:{})", map.depth, scale)
+    };
+    ctx.print_color(
+        3,
+        0,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        title,
+    );
+
+    let cells_x = map.width.div_ceil(scale) as usize;
+    let cells_y = map.height.div_ceil(scale) as usize;
+    let mut marks = vec![Mark::Unknown; cells_x * cells_y];
+    let cell = |x: u16, y: u16| (y / scale) as usize * cells_x + (x / scale) as usize;
+
+    for (idx, tile) in map.tiles.iter().enumerate() {
+        if map.revealed_tiles[idx] {
+            let (x, y) = map.xy_from_index(&idx);
+            let c = cell(x, y);
+            marks[c] = more_important(marks[c], tile_mark(*tile));
+        }
+    }
+    let items = ecs.read_storage::<Item>();
+    let positions = ecs.read_storage::<Position>();
+    let hidden = ecs.read_storage::<Hidden>();
+    for (_, pos, _) in (&items, &positions, !&hidden).join() {
+        if map.revealed_tiles[map.index_from_xy(pos.x, pos.y)] {
+            let c = cell(pos.x, pos.y);
+            marks[c] = more_important(marks[c], Mark::Item);
+        }
+    }
+    for idx in map.notes.keys() {
+        let (x, y) = map.xy_from_index(idx);
+        let c = cell(x, y);
+        marks[c] = more_important(marks[c], Mark::Note);
+    }
+    let c = cell(player_pos.x, player_pos.y);
+    marks[c] = Mark::Player;
+
+    for (c, mark) in marks.iter().enumerate() {
+        if let Some((glyph, fg)) = mark_glyph(*mark) {
+            let screen_x = (c % cells_x) as i32;
+            let screen_y = 1 + (c / cells_x) as i32;
+            ctx.set(screen_x, screen_y, fg, RGB::named(rltk::BLACK), glyph);
+        }
+    }
+    ctx.set_bg(
+        (cursor.x / scale) as i32,
+        1 + (cursor.y / scale) as i32,
+        RGB::named(rltk::CYAN),
+    );
+
+    // The note under the cursor, or the controls
+    let idx = map.index_from_xy(cursor.x, cursor.y);
+    let status = match (map.notes.get(&idx), editing) {
+        (Some(note), true) => format!("Note: {}_", note),
+        (None, true) => "Note: _".to_string(),
+        (Some(note), false) => format!("Note: {}", note),
+        (None, false) => String::new(),
+    };
+    ctx.print_color(
+        2,
+        map.window_height - 2,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        status,
+    );
+    let help = if editing {
+        "Type the note, ENTER done"
+    } else {
+        "ENTER write note, DEL remove note, ESC close"
+    };
+    ctx.print_color(
+        3,
+        map.window_height - 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        help,
+    );
+}
@@ -2,8 +2,11 @@ use rltk::RGB;
 use specs::World;
 
 use crate::{
+    components::GameMode,
+    keybindings::{Action, KeyBindings},
+    menu::slot_menu::{self, Failure, SlotBrowser, SlotMenuResult, SlotMode, SlotPrompt},
     state::RunState,
-    systems::saveload_system::{self, save_game},
+    systems::{autosave, save_slots, saveload_system},
 };
 
 #[derive(PartialEq, Clone, Copy)]
@@ -26,37 +29,99 @@ pub fn pause_menu(
     ctx: &mut rltk::Rltk,
     mut current_selection: PauseMenuSelection,
 ) -> RunState {
-    let save_exists = saveload_system::does_save_exist();
-    print_pause_menu(ctx, current_selection, save_exists);
-    let selected_menu = select_menu(ctx, current_selection, save_exists);
+    // Strict runs can't go back to an earlier save, so saving one ends the session
+    let strict = saveload_system::run_stats(ecs).mode == GameMode::Strict;
+    let can_load = save_slots::any_slots() && !strict;
+    print_pause_menu(ctx, current_selection, can_load, strict);
+    let selected_menu = select_menu(
+        ctx,
+        &ecs.fetch::<KeyBindings>(),
+        current_selection,
+        can_load,
+    );
     match selected_menu {
         PauseMenuResult::NoSelection { selected } => RunState::PauseMenu {
             selected_menu: selected,
         },
         PauseMenuResult::Selected { selected } => match selected {
-            PauseMenuSelection::Restart => RunState::NewGame,
-            PauseMenuSelection::SaveGame => {
-                save_game(ecs);
-                RunState::AwaitingInput
-            }
-            PauseMenuSelection::LoadGame => {
-                saveload_system::load_game(ecs);
-                saveload_system::delete_save();
-                RunState::AwaitingInput
-            }
-            PauseMenuSelection::Quit => {
-                save_game(ecs);
-                ::std::process::exit(0);
+            PauseMenuSelection::Restart => {
+                saveload_system::forfeit_run(ecs);
+                RunState::NewGame
             }
+            PauseMenuSelection::SaveGame if strict => save_and_quit(ecs),
+            PauseMenuSelection::SaveGame => open_slots(ecs, SlotMode::Save),
+            PauseMenuSelection::LoadGame => open_slots(ecs, SlotMode::Load),
+            PauseMenuSelection::Quit => save_and_quit(ecs),
         },
         PauseMenuResult::Cancel => RunState::AwaitingInput,
     }
 }
 
+// A run that already has a slot is saved back to it, otherwise the player picks one
+fn save_and_quit(ecs: &mut World) -> RunState {
+    let active = ecs.fetch::<SlotBrowser>().active.clone();
+    match active.map(|slot| saveload_system::save_game(ecs, &slot)) {
+        Some(Ok(())) => quit(ecs),
+        Some(Err(error)) => {
+            let state = open_slots(ecs, SlotMode::SaveAndQuit);
+            ecs.fetch_mut::<SlotBrowser>().failure = Some(Failure {
+                title: "Save failed",
+                error,
+            });
+            state
+        }
+        None => open_slots(ecs, SlotMode::SaveAndQuit),
+    }
+}
+
+// The run is saved by now, so leaving ends the session cleanly
+fn quit(ecs: &mut World) -> ! {
+    autosave::end_session(ecs);
+    ::std::process::exit(0)
+}
+
+fn open_slots(ecs: &mut World, mode: SlotMode) -> RunState {
+    ecs.fetch_mut::<SlotBrowser>().refresh();
+    RunState::ShowSaveSlots {
+        mode,
+        selected: 0,
+        prompt: SlotPrompt::None,
+    }
+}
+
+/// The slot browser opened from the pause menu, backing out returns to it
+pub fn save_slots_menu(
+    ecs: &mut World,
+    ctx: &mut rltk::Rltk,
+    mode: SlotMode,
+    selected: usize,
+    prompt: SlotPrompt,
+) -> RunState {
+    match slot_menu::slot_menu(ecs, ctx, mode, selected, prompt) {
+        SlotMenuResult::NoSelection { selected, prompt } => RunState::ShowSaveSlots {
+            mode,
+            selected,
+            prompt,
+        },
+        SlotMenuResult::Cancel => RunState::PauseMenu {
+            selected_menu: match mode {
+                SlotMode::Load => PauseMenuSelection::LoadGame,
+                SlotMode::Save => PauseMenuSelection::SaveGame,
+                SlotMode::SaveAndQuit => PauseMenuSelection::Quit,
+            },
+        },
+        SlotMenuResult::Done => match mode {
+            SlotMode::SaveAndQuit => quit(ecs),
+            SlotMode::Load | SlotMode::Save => RunState::AwaitingInput,
+        },
+    }
+}
+
 fn print_pause_menu(
     ctx: &mut rltk::Rltk,
     mut current_selection: PauseMenuSelection,
-    save_exists: bool,
+    can_load: bool,
+    strict: bool,
 ) {
     let mut y = 13;
     ctx.draw_box(
@@ -89,8 +154,9 @@ fn print_pause_menu(
 
     y += 3;
     ctx.print_color_centered(y, restart_game_fg, RGB::named(rltk::BLACK), "Restart Game");
-    ctx.print_color_centered(y + 1, save_game_fg, RGB::named(rltk::BLACK), "Save Game");
-    if save_exists {
+    let save_label = if strict { "Save and Quit" } else { "Save Game" };
+    ctx.print_color_centered(y + 1, save_game_fg, RGB::named(rltk::BLACK), save_label);
+    if can_load {
         ctx.print_color_centered(y + 2, load_game_fg, RGB::named(rltk::BLACK), "Load Game");
     } else if current_selection == PauseMenuSelection::LoadGame {
         current_selection = PauseMenuSelection::SaveGame;
@@ -100,19 +166,20 @@ fn print_pause_menu(
 
 fn select_menu(
     ctx: &mut rltk::Rltk,
+    bindings: &KeyBindings,
     current_selection: PauseMenuSelection,
-    save_exists: bool,
+    can_load: bool,
 ) -> PauseMenuResult {
     match ctx.key {
         None => PauseMenuResult::NoSelection {
             selected: current_selection,
         },
         Some(rltk::VirtualKeyCode::Escape) => PauseMenuResult::Cancel,
-        Some(rltk::VirtualKeyCode::Numpad8 | rltk::VirtualKeyCode::W) => {
+        Some(key) if bindings.is(key, Action::MoveNorth) => {
             let new_selection = match current_selection {
                 PauseMenuSelection::Restart => PauseMenuSelection::Quit,
                 PauseMenuSelection::Quit => {
-                    if save_exists {
+                    if can_load {
                         PauseMenuSelection::LoadGame
                     } else {
                         PauseMenuSelection::SaveGame
@@ -125,11 +192,11 @@ fn select_menu(
                 selected: new_selection,
             }
         }
-        Some(rltk::VirtualKeyCode::Numpad2 | rltk::VirtualKeyCode::S) => {
+        Some(key) if bindings.is(key, Action::MoveSouth) => {
             let new_selection = match current_selection {
                 PauseMenuSelection::Restart => PauseMenuSelection::SaveGame,
                 PauseMenuSelection::SaveGame => {
-                    if save_exists {
+                    if can_load {
                         PauseMenuSelection::LoadGame
                     } else {
                         PauseMenuSelection::Quit
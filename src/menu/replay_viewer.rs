@@ -0,0 +1,86 @@
+use rltk::{Rltk, VirtualKeyCode, RGB};
+use specs::World;
+
+use crate::{gamelog::GameLog, gui, replay::Playback, state::GlobalState};
+
+// Turns a second a replay can be watched at, the speed doubles or halves between the two
+pub const MIN_SPEED: u16 = 1;
+pub const MAX_SPEED: u16 = 64;
+
+/// A recorded run played back in the window, paused or at a speed of turns a second
+pub fn replay_viewer(
+    ecs: &mut World,
+    ctx: &mut Rltk,
+    speed: u16,
+    paused: bool,
+    waited_ms: f32,
+) -> GlobalState {
+    let mut playback = ecs
+        .remove::<Playback>()
+        .expect("A replay is shown without a playback");
+
+    let mut speed = speed;
+    let mut paused = paused;
+    let mut waited_ms = waited_ms;
+    match ctx.key {
+        Some(VirtualKeyCode::Escape) => ctx.quit(),
+        Some(VirtualKeyCode::Space) => paused = !paused,
+        Some(VirtualKeyCode::Up) => speed = u16::min(speed * 2, MAX_SPEED),
+        Some(VirtualKeyCode::Down) => speed = u16::max(speed / 2, MIN_SPEED),
+        Some(VirtualKeyCode::Right) if paused => playback.next_turn(ecs),
+        _ => {}
+    }
+
+    if paused {
+        waited_ms = 0.0;
+    } else {
+        waited_ms += ctx.frame_time_ms;
+        let turn_ms = 1000.0 / speed as f32;
+        while waited_ms >= turn_ms && playback.end().is_none() {
+            playback.next_turn(ecs);
+            waited_ms -= turn_ms;
+        }
+    }
+
+    gui::draw(ecs, ctx);
+    draw_status(ecs, ctx, &playback, speed, paused);
+    ecs.insert(playback);
+
+    GlobalState::Replay {
+        speed,
+        paused,
+        waited_ms,
+    }
+}
+
+fn draw_status(ecs: &World, ctx: &mut Rltk, playback: &Playback, speed: u16, paused: bool) {
+    let turn = ecs.fetch::<GameLog>().turn;
+    let state = if paused {
+        "paused".to_string()
+    } else {
+        format!("{} turns/s", speed)
+    };
+    ctx.print_color(
+        1,
+        0,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!(
+            "Replay, turn {}, {}. SPACE pause, RIGHT step, UP/DOWN speed, ESC quit",
+            turn, state
+        ),
+    );
+
+    let end = match playback.end() {
+        None => return,
+        Some(end) => end,
+    };
+    let fg = if end.matched() {
+        RGB::named(rltk::GREEN)
+    } else {
+        RGB::named(rltk::RED)
+    };
+    for (i, line) in end.report().iter().enumerate() {
+        ctx.print_color(1, 2 + i as i32, fg, RGB::named(rltk::BLACK), line);
+    }
+}
@@ -0,0 +1,493 @@
+use rltk::{Rltk, VirtualKeyCode, RGB};
+use specs::{World, WorldExt};
+
+use crate::{
+    components::Name,
+    keybindings::{typed_char, Action, KeyBindings},
+    systems::{
+        save_slots::{self, SaveSlot, MAX_SLOT_NAME},
+        saveload_system::{self, SaveError},
+    },
+};
+
+// Width of the dialog shown when a slot can't be loaded, saved or deleted
+const DIALOG_WIDTH: i32 = 50;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum SlotMode {
+    Load,
+    Save,
+    SaveAndQuit,
+}
+
+/// What the browser is asking the player, if anything
+#[derive(PartialEq, Clone, Copy)]
+pub enum SlotPrompt {
+    None,
+    Naming,
+    Overwrite,
+    Delete,
+}
+
+#[derive(PartialEq)]
+pub enum SlotMenuResult {
+    NoSelection { selected: usize, prompt: SlotPrompt },
+    Cancel,
+    Done,
+}
+
+/// A load, save or delete that went wrong, shown until the player dismisses it
+pub struct Failure {
+    pub title: &'static str,
+    pub error: SaveError,
+}
+
+/// The slots as last read from disk, and the name typed for a new one
+#[derive(Default)]
+pub struct SlotBrowser {
+    pub slots: Vec<SaveSlot>,
+    pub name: String,
+    pub failure: Option<Failure>,
+    /// The slot the current run was loaded from or last saved to
+    pub active: Option<String>,
+}
+
+impl SlotBrowser {
+    pub fn refresh(&mut self) {
+        self.slots = save_slots::list_slots();
+        self.name.clear();
+        self.failure = None;
+    }
+
+    // When saving, the first row makes a new slot
+    fn slot_at(&self, mode: SlotMode, row: usize) -> Option<&SaveSlot> {
+        match mode {
+            SlotMode::Load => self.slots.get(row),
+            SlotMode::Save | SlotMode::SaveAndQuit => {
+                row.checked_sub(1).and_then(|i| self.slots.get(i))
+            }
+        }
+    }
+
+    fn row_count(&self, mode: SlotMode) -> usize {
+        match mode {
+            SlotMode::Load => self.slots.len(),
+            SlotMode::Save | SlotMode::SaveAndQuit => self.slots.len() + 1,
+        }
+    }
+
+    fn row_of(&self, mode: SlotMode, name: &str) -> Option<usize> {
+        (0..self.row_count(mode)).find(|row| {
+            self.slot_at(mode, *row)
+                .is_some_and(|slot| slot.name == name)
+        })
+    }
+}
+
+pub fn slot_menu(
+    ecs: &mut World,
+    ctx: &mut Rltk,
+    mode: SlotMode,
+    selected: usize,
+    prompt: SlotPrompt,
+) -> SlotMenuResult {
+    draw_slots(ecs, ctx, mode, selected, prompt);
+    if let Some(failure) = &ecs.fetch::<SlotBrowser>().failure {
+        draw_failure(ctx, failure);
+    }
+
+    let key = match ctx.key {
+        None => return SlotMenuResult::NoSelection { selected, prompt },
+        Some(key) => key,
+    };
+    // Any key dismisses the failure dialog
+    if ecs.fetch_mut::<SlotBrowser>().failure.take().is_some() {
+        return SlotMenuResult::NoSelection { selected, prompt };
+    }
+    match prompt {
+        SlotPrompt::None => browse(ecs, key, mode, selected),
+        SlotPrompt::Naming => name_slot(ecs, ctx, key, mode, selected),
+        SlotPrompt::Overwrite => match key {
+            VirtualKeyCode::Y | VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+                let name = {
+                    let browser = ecs.fetch::<SlotBrowser>();
+                    browser
+                        .slot_at(mode, selected)
+                        .map(|slot| slot.name.clone())
+                };
+                match name {
+                    Some(name) => finish(ecs, mode, selected, &name),
+                    None => SlotMenuResult::NoSelection {
+                        selected,
+                        prompt: SlotPrompt::None,
+                    },
+                }
+            }
+            VirtualKeyCode::N | VirtualKeyCode::Escape => SlotMenuResult::NoSelection {
+                selected,
+                prompt: SlotPrompt::None,
+            },
+            _ => SlotMenuResult::NoSelection { selected, prompt },
+        },
+        SlotPrompt::Delete => match key {
+            VirtualKeyCode::Y | VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+                let mut browser = ecs.fetch_mut::<SlotBrowser>();
+                let name = browser
+                    .slot_at(mode, selected)
+                    .map(|slot| slot.name.clone());
+                if let Some(name) = name {
+                    let deleted = save_slots::delete_slot(&name);
+                    browser.refresh();
+                    if let Err(error) = deleted {
+                        browser.failure = Some(Failure {
+                            title: "Delete failed",
+                            error,
+                        });
+                    }
+                    if browser.active.as_deref() == Some(name.as_str()) {
+                        browser.active = None;
+                    }
+                }
+                SlotMenuResult::NoSelection {
+                    selected: selected.min(browser.row_count(mode).saturating_sub(1)),
+                    prompt: SlotPrompt::None,
+                }
+            }
+            VirtualKeyCode::N | VirtualKeyCode::Escape => SlotMenuResult::NoSelection {
+                selected,
+                prompt: SlotPrompt::None,
+            },
+            _ => SlotMenuResult::NoSelection { selected, prompt },
+        },
+    }
+}
+
+fn browse(ecs: &mut World, key: VirtualKeyCode, mode: SlotMode, selected: usize) -> SlotMenuResult {
+    let bindings = ecs.fetch::<KeyBindings>();
+    let mut browser = ecs.fetch_mut::<SlotBrowser>();
+    let rows = browser.row_count(mode);
+    let stay = |selected| SlotMenuResult::NoSelection {
+        selected,
+        prompt: SlotPrompt::None,
+    };
+    match key {
+        VirtualKeyCode::Escape => SlotMenuResult::Cancel,
+        VirtualKeyCode::Delete | VirtualKeyCode::Back => {
+            if browser.slot_at(mode, selected).is_some() {
+                SlotMenuResult::NoSelection {
+                    selected,
+                    prompt: SlotPrompt::Delete,
+                }
+            } else {
+                stay(selected)
+            }
+        }
+        VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+            let name = browser
+                .slot_at(mode, selected)
+                .map(|slot| slot.name.clone());
+            match (mode, name) {
+                (SlotMode::Load, None) => stay(selected),
+                (SlotMode::Load, Some(name)) => {
+                    drop(browser);
+                    drop(bindings);
+                    finish(ecs, mode, selected, &name)
+                }
+                (_, Some(_)) => SlotMenuResult::NoSelection {
+                    selected,
+                    prompt: SlotPrompt::Overwrite,
+                },
+                (_, None) => {
+                    // New slots are named after the character to begin with
+                    let player = *ecs.fetch::<specs::Entity>();
+                    browser.name =
+                        ecs.read_storage::<Name>()
+                            .get(player)
+                            .map_or_else(String::new, |name| {
+                                name.name
+                                    .chars()
+                                    .filter(|c| save_slots::is_slot_char(*c))
+                                    .take(MAX_SLOT_NAME)
+                                    .collect()
+                            });
+                    SlotMenuResult::NoSelection {
+                        selected,
+                        prompt: SlotPrompt::Naming,
+                    }
+                }
+            }
+        }
+        _ if rows == 0 => stay(selected),
+        _ if bindings.is(key, Action::MoveNorth) || key == VirtualKeyCode::Up => {
+            stay((selected + rows - 1) % rows)
+        }
+        _ if bindings.is(key, Action::MoveSouth) || key == VirtualKeyCode::Down => {
+            stay((selected + 1) % rows)
+        }
+        _ => stay(selected),
+    }
+}
+
+fn name_slot(
+    ecs: &mut World,
+    ctx: &Rltk,
+    key: VirtualKeyCode,
+    mode: SlotMode,
+    selected: usize,
+) -> SlotMenuResult {
+    let mut browser = ecs.fetch_mut::<SlotBrowser>();
+    match key {
+        VirtualKeyCode::Escape => {
+            browser.name.clear();
+            SlotMenuResult::NoSelection {
+                selected,
+                prompt: SlotPrompt::None,
+            }
+        }
+        VirtualKeyCode::Back => {
+            browser.name.pop();
+            SlotMenuResult::NoSelection {
+                selected,
+                prompt: SlotPrompt::Naming,
+            }
+        }
+        VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+            let name = browser.name.trim().to_string();
+            if name.is_empty() {
+                return SlotMenuResult::NoSelection {
+                    selected,
+                    prompt: SlotPrompt::Naming,
+                };
+            }
+            // Naming a slot after an existing one means overwriting it
+            if let Some(row) = browser.row_of(mode, &name) {
+                return SlotMenuResult::NoSelection {
+                    selected: row,
+                    prompt: SlotPrompt::Overwrite,
+                };
+            }
+            drop(browser);
+            finish(ecs, mode, selected, &name)
+        }
+        _ => {
+            if let Some(typed) = typed_char(key, ctx.shift) {
+                if save_slots::is_slot_char(typed) && browser.name.len() < MAX_SLOT_NAME {
+                    browser.name.push(typed);
+                }
+            }
+            SlotMenuResult::NoSelection {
+                selected,
+                prompt: SlotPrompt::Naming,
+            }
+        }
+    }
+}
+
+// Load or save the slot, a failure stays in the browser with a dialog saying why
+fn finish(ecs: &mut World, mode: SlotMode, selected: usize, name: &str) -> SlotMenuResult {
+    let result = match mode {
+        SlotMode::Load => saveload_system::load_game(ecs, name),
+        SlotMode::Save | SlotMode::SaveAndQuit => saveload_system::save_game(ecs, name),
+    };
+    let mut browser = ecs.fetch_mut::<SlotBrowser>();
+    match result {
+        Ok(()) => {
+            browser.active = Some(name.to_string());
+            SlotMenuResult::Done
+        }
+        Err(error) => {
+            browser.refresh();
+            let title = match mode {
+                SlotMode::Load => "Load failed",
+                SlotMode::Save | SlotMode::SaveAndQuit => "Save failed",
+            };
+            browser.failure = Some(Failure { title, error });
+            SlotMenuResult::NoSelection {
+                selected,
+                prompt: SlotPrompt::None,
+            }
+        }
+    }
+}
+
+fn draw_slots(ecs: &World, ctx: &mut Rltk, mode: SlotMode, selected: usize, prompt: SlotPrompt) {
+    let browser = ecs.fetch::<SlotBrowser>();
+    let (width, height) = ctx.get_char_size();
+    let (width, height) = (width as i32, height as i32);
+    ctx.cls();
+    ctx.draw_box(
+        0,
+        0,
+        width - 1,
+        height - 1,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    let title = match mode {
+        SlotMode::Load => "Load Game",
+        SlotMode::Save | SlotMode::SaveAndQuit => "Save Game",
+    };
+    ctx.print_color(
+        3,
+        0,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        title,
+    );
+
+    let header_fg = RGB::named(rltk::GREY);
+    for (x, column) in [
+        (3, "Slot"),
+        (29, "Character"),
+        (44, "Depth"),
+        (51, "Turns"),
+        (59, "Saved (UTC)"),
+    ] {
+        ctx.print_color(x, 2, header_fg, RGB::named(rltk::BLACK), column);
+    }
+
+    // Keep the selected row on screen when there are more slots than lines
+    let visible_rows = (height - 10) as usize;
+    let first_row = selected.saturating_sub(visible_rows - 1);
+    let rows = browser.row_count(mode);
+    if rows == 0 {
+        ctx.print_color(
+            3,
+            4,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            "There are no saved games.",
+        );
+    }
+    for row in first_row..rows.min(first_row + visible_rows) {
+        let y = 4 + (row - first_row) as i32;
+        let fg = if row == selected {
+            RGB::named(rltk::MAGENTA)
+        } else {
+            RGB::named(rltk::WHITE)
+        };
+        match browser.slot_at(mode, row) {
+            None if prompt == SlotPrompt::Naming => {
+                let name = format!("{}_", browser.name);
+                ctx.print_color(3, y, RGB::named(rltk::CYAN), RGB::named(rltk::BLACK), name);
+            }
+            None => ctx.print_color(3, y, fg, RGB::named(rltk::BLACK), "<New slot>"),
+            Some(slot) => {
+                ctx.print_color(3, y, fg, RGB::named(rltk::BLACK), &slot.name);
+                match &slot.header {
+                    Some(header) => {
+                        ctx.print_color(29, y, fg, RGB::named(rltk::BLACK), &header.character);
+                        ctx.print_color(44, y, fg, RGB::named(rltk::BLACK), header.depth);
+                        ctx.print_color(51, y, fg, RGB::named(rltk::BLACK), header.turns);
+                        ctx.print_color(
+                            59,
+                            y,
+                            fg,
+                            RGB::named(rltk::BLACK),
+                            save_slots::format_timestamp(header.timestamp),
+                        );
+                    }
+                    None => ctx.print_color(
+                        29,
+                        y,
+                        RGB::named(rltk::RED),
+                        RGB::named(rltk::BLACK),
+                        "Unreadable",
+                    ),
+                }
+            }
+        }
+    }
+
+    if let Some(header) = browser
+        .slot_at(mode, selected)
+        .and_then(|slot| slot.header.as_ref())
+    {
+        ctx.print_color(
+            3,
+            height - 4,
+            RGB::named(rltk::GREY),
+            RGB::named(rltk::BLACK),
+            format!("Seed {:016x}, {} mode", header.seed, header.mode.name()),
+        );
+    }
+    let help = match prompt {
+        SlotPrompt::None => match mode {
+            SlotMode::Load => "ENTER load, DEL delete, ESC back",
+            SlotMode::Save | SlotMode::SaveAndQuit => "ENTER save, DEL delete, ESC back",
+        },
+        SlotPrompt::Naming => "Type a name for the slot, ENTER save, ESC cancel",
+        SlotPrompt::Overwrite => "Overwrite this slot? (Y/N)",
+        SlotPrompt::Delete => "Delete this slot? (Y/N)",
+    };
+    ctx.print_color(
+        3,
+        height - 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        help,
+    );
+}
+
+fn draw_failure(ctx: &mut Rltk, failure: &Failure) {
+    let (width, height) = ctx.get_char_size();
+    let (width, height) = (width as i32, height as i32);
+    let lines = wrap(&failure.error.to_string(), DIALOG_WIDTH as usize - 4);
+    let box_height = lines.len() as i32 + 3;
+    let x = (width - DIALOG_WIDTH) / 2;
+    let y = (height - box_height) / 2;
+    ctx.draw_box(
+        x,
+        y,
+        DIALOG_WIDTH,
+        box_height,
+        RGB::named(rltk::RED),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        x + 2,
+        y,
+        RGB::named(rltk::RED),
+        RGB::named(rltk::BLACK),
+        failure.title,
+    );
+    for (i, line) in lines.iter().enumerate() {
+        ctx.print_color(
+            x + 2,
+            y + 2 + i as i32,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            line,
+        );
+    }
+    ctx.print_color(
+        x + 2,
+        y + box_height,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Press any key",
+    );
+}
+
+// Splits text into lines no longer than the width, breaking between words where it can
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+        while line.len() > width {
+            let rest = line.split_off(width);
+            lines.push(std::mem::replace(&mut line, rest));
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
@@ -1,8 +1,12 @@
-use rltk::{console, Rltk, RGB};
-use specs::{Entity, World, WorldExt};
+use rltk::{console, Point, Rltk, VirtualKeyCode, RGB};
+use specs::{Entity, Join, World, WorldExt};
 
 use crate::{
-    components::{Position, Viewshed, WantsToUseItem},
+    camera::Camera,
+    components::{AreaOfEffect, Hidden, Monster, Position, Viewshed},
+    keybindings::KeyBindings,
+    map::map::Map,
+    player::{self, PlayerAction},
     state::RunState,
 };
 
@@ -10,94 +14,260 @@ use crate::{
 pub enum TargetSelectResult {
     Cancel,
     NoResponse,
+    Moved(Position),
     Selected(Position),
 }
 
-pub fn target_menu(ecs: &mut World, ctx: &mut Rltk, range: u16, item: Entity) -> RunState {
-    let target = show_target_menu(ecs, ctx, range);
+/// The monster the player aimed at the last time, so it can be picked again right away
+#[derive(Default)]
+pub struct LastTarget {
+    pub target: Option<Entity>,
+}
+
+/// Opens the targeting screen with the cursor on the last target, or on the closest hostile
+pub fn start_targeting(ecs: &mut World, range: u16, item: Entity) -> RunState {
+    let player_pos = *ecs.fetch::<Position>();
+    let hostiles = hostiles_in_range(ecs, range);
+    let last_target = ecs.fetch::<LastTarget>().target;
+    let cursor = hostiles
+        .iter()
+        .find(|(entity, _)| Some(*entity) == last_target)
+        .or_else(|| hostiles.first())
+        .map_or(player_pos, |(_, pos)| *pos);
+    RunState::ShowTargeting {
+        range,
+        item,
+        cursor,
+    }
+}
+
+pub fn target_menu(
+    ecs: &mut World,
+    ctx: &mut Rltk,
+    range: u16,
+    item: Entity,
+    cursor: Position,
+) -> RunState {
+    let target = show_target_menu(ecs, ctx, range, item, cursor);
     match target {
         TargetSelectResult::Cancel => RunState::AwaitingInput,
-        TargetSelectResult::NoResponse => RunState::ShowTargeting { range, item },
+        TargetSelectResult::NoResponse => RunState::ShowTargeting {
+            range,
+            item,
+            cursor,
+        },
+        TargetSelectResult::Moved(cursor) => RunState::ShowTargeting {
+            range,
+            item,
+            cursor,
+        },
         TargetSelectResult::Selected(position) => {
-            let mut intent = ecs.write_storage::<WantsToUseItem>();
-            let player = ecs.fetch::<Entity>();
-            intent
-                .insert(
-                    *player,
-                    WantsToUseItem {
-                        item,
-                        target: Some(position),
-                    },
-                )
-                .expect("Unable to insert intent");
-            RunState::PlayerTurn
+            remember_target(ecs, range, position);
+            let item = player::marker(ecs, item);
+            player::perform(
+                ecs,
+                PlayerAction::UseItem {
+                    item,
+                    target: Some(position),
+                },
+            )
         }
     }
 }
 
-fn show_target_menu(ecs: &mut World, ctx: &mut Rltk, range: u16) -> TargetSelectResult {
+fn show_target_menu(
+    ecs: &mut World,
+    ctx: &mut Rltk,
+    range: u16,
+    item: Entity,
+    cursor: Position,
+) -> TargetSelectResult {
+    let camera = Camera::from_ecs(ecs);
+    let available_cells = match available_cells(ecs, range) {
+        None => {
+            console::log("Player entity doesn't have a viewshed");
+            return TargetSelectResult::Cancel;
+        }
+        Some(cells) => cells,
+    };
+    let is_valid = |pos: Position| {
+        available_cells
+            .iter()
+            .any(|cell| cell.x == pos.x as i32 && cell.y == pos.y as i32)
+    };
+
+    let radius = ecs
+        .read_storage::<AreaOfEffect>()
+        .get(item)
+        .map(|area| area.radius);
+    draw_menu(ecs, ctx, &camera, &available_cells, cursor, radius);
+
+    // The mouse still works: clicking picks the tile under the pointer
+    let mouse_pos = ctx.mouse_pos();
+    if let Some(mouse_world_pos) = camera.to_world(mouse_pos.0, mouse_pos.1) {
+        if is_valid(mouse_world_pos) {
+            ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::CYAN));
+            if ctx.left_click {
+                return TargetSelectResult::Selected(mouse_world_pos);
+            }
+        } else {
+            ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::RED));
+            if ctx.left_click {
+                return TargetSelectResult::Cancel;
+            }
+        }
+    }
+
+    let (delta_x, delta_y) = match ctx.key {
+        None => return TargetSelectResult::NoResponse,
+        Some(key) => match key {
+            VirtualKeyCode::Escape => return TargetSelectResult::Cancel,
+            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+                if is_valid(cursor) {
+                    return TargetSelectResult::Selected(cursor);
+                }
+                return TargetSelectResult::NoResponse;
+            }
+            VirtualKeyCode::Tab => {
+                return match next_hostile(ecs, range, cursor) {
+                    None => TargetSelectResult::NoResponse,
+                    Some(pos) => TargetSelectResult::Moved(pos),
+                }
+            }
+            _ => match ecs.fetch::<KeyBindings>().direction(key) {
+                None => return TargetSelectResult::NoResponse,
+                Some(delta) => delta,
+            },
+        },
+    };
+
+    // The cursor moves freely, but stays on the visible part of the map
+    let new_x = cursor.x as i32 + delta_x;
+    let new_y = cursor.y as i32 + delta_y;
+    if new_x < 0 || new_y < 0 || camera.to_screen(new_x as u16, new_y as u16).is_none() {
+        return TargetSelectResult::NoResponse;
+    }
+    let map = ecs.fetch::<Map>();
+    if new_x >= map.width as i32 || new_y >= map.height as i32 {
+        return TargetSelectResult::NoResponse;
+    }
+    TargetSelectResult::Moved(Position {
+        x: new_x as u16,
+        y: new_y as u16,
+    })
+}
+
+// Visible tiles within the range of the item
+fn available_cells(ecs: &World, range: u16) -> Option<Vec<Point>> {
     let player_entity = ecs.fetch::<Entity>();
     let player_pos = ecs.fetch::<Position>();
     let viewsheds = ecs.read_storage::<Viewshed>();
 
-    let viewshed = viewsheds.get(*player_entity);
-    let mouse_pos = ctx.mouse_pos();
-    let valid_target = if let Some(viewshed) = viewshed {
-        draw_menu(ctx, viewshed, *player_pos, range, mouse_pos)
-    } else {
-        console::log("Player entity doesn't have a viewshed");
-        return TargetSelectResult::Cancel;
+    let viewshed = viewsheds.get(*player_entity)?;
+    Some(
+        viewshed
+            .visible_tiles
+            .iter()
+            .filter(|idx| {
+                rltk::DistanceAlg::Pythagoras.distance2d((*player_pos).into(), **idx) < range as f32
+            })
+            .copied()
+            .collect(),
+    )
+}
+
+/// Visible monsters within range, the closest ones first
+fn hostiles_in_range(ecs: &World, range: u16) -> Vec<(Entity, Position)> {
+    let player_pos = *ecs.fetch::<Position>();
+    let map = ecs.fetch::<Map>();
+    let entities = ecs.entities();
+    let monsters = ecs.read_storage::<Monster>();
+    let positions = ecs.read_storage::<Position>();
+    let hidden = ecs.read_storage::<Hidden>();
+
+    let distance =
+        |pos: Position| rltk::DistanceAlg::Pythagoras.distance2d(player_pos.into(), pos.into());
+    let mut hostiles: Vec<(Entity, Position)> = (&entities, &monsters, &positions, !&hidden)
+        .join()
+        .filter(|(_, _, pos, _)| {
+            map.visible_tiles[map.index_from_xy(pos.x, pos.y)] && distance(**pos) < range as f32
+        })
+        .map(|(entity, _, pos, _)| (entity, *pos))
+        .collect();
+    hostiles.sort_by(|(_, pos1), (_, pos2)| distance(*pos1).total_cmp(&distance(*pos2)));
+    hostiles
+}
+
+// Cycles to the hostile after the one under the cursor, wrapping around to the closest one
+fn next_hostile(ecs: &World, range: u16, cursor: Position) -> Option<Position> {
+    let hostiles = hostiles_in_range(ecs, range);
+    let current = hostiles.iter().position(|(_, pos)| *pos == cursor);
+    let next = match current {
+        None => 0,
+        Some(index) => (index + 1) % hostiles.len(),
     };
+    hostiles.get(next).map(|(_, pos)| *pos)
+}
 
-    if valid_target {
-        ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::CYAN));
-        if ctx.left_click {
-            return TargetSelectResult::Selected(Position {
-                x: mouse_pos.0 as u16,
-                y: mouse_pos.1 as u16,
-            });
-        }
-    } else {
-        ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::RED));
-        if ctx.left_click {
-            return TargetSelectResult::Cancel;
-        }
+fn remember_target(ecs: &mut World, range: u16, position: Position) {
+    let target = hostiles_in_range(ecs, range)
+        .into_iter()
+        .find(|(_, pos)| *pos == position)
+        .map(|(entity, _)| entity);
+    if target.is_some() {
+        ecs.fetch_mut::<LastTarget>().target = target;
     }
-
-    TargetSelectResult::NoResponse
 }
 
-fn draw_menu<'a>(
+fn draw_menu(
+    ecs: &World,
     ctx: &mut Rltk,
-    viewshed: &'a Viewshed,
-    player_pos: Position,
-    range: u16,
-    mouse_pos: (i32, i32),
-) -> bool {
+    camera: &Camera,
+    available_cells: &[Point],
+    cursor: Position,
+    radius: Option<u16>,
+) {
     ctx.print_color(
         5,
         0,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "Select Target:",
+        "Select Target: TAB next enemy, ENTER fire, ESC cancel",
     );
 
     // Highlight available target cells
-    let mut available_cells = Vec::new();
-    for idx in viewshed.visible_tiles.iter() {
-        let distance = rltk::DistanceAlg::Pythagoras.distance2d(player_pos.into(), *idx);
-        if distance < range as f32 {
-            ctx.set_bg(idx.x, idx.y, RGB::named(rltk::BLUE));
-            available_cells.push(idx);
+    for cell in available_cells.iter() {
+        if let Some((screen_x, screen_y)) = camera.to_screen(cell.x as u16, cell.y as u16) {
+            ctx.set_bg(screen_x, screen_y, RGB::named(rltk::BLUE));
         }
     }
 
-    let mut valid_target = false;
-    for idx in available_cells.iter() {
-        if idx.x == mouse_pos.0 && idx.y == mouse_pos.1 {
-            valid_target = true;
+    let valid_target = available_cells
+        .iter()
+        .any(|cell| cell.x == cursor.x as i32 && cell.y == cursor.y as i32);
+
+    // Preview everything the blast would hit
+    if let Some(radius) = radius {
+        if valid_target {
+            let map = ecs.fetch::<Map>();
+            let blast_tiles = rltk::field_of_view(cursor.into(), radius as i32, &*map);
+            for tile in blast_tiles.iter() {
+                if tile.x < 0 || tile.y < 0 {
+                    continue;
+                }
+                if let Some((screen_x, screen_y)) = camera.to_screen(tile.x as u16, tile.y as u16) {
+                    ctx.set_bg(screen_x, screen_y, RGB::named(rltk::ORANGE));
+                }
+            }
         }
     }
 
-    valid_target
+    if let Some((screen_x, screen_y)) = camera.to_screen(cursor.x, cursor.y) {
+        let color = if valid_target {
+            RGB::named(rltk::CYAN)
+        } else {
+            RGB::named(rltk::RED)
+        };
+        ctx.set_bg(screen_x, screen_y, color);
+    }
 }
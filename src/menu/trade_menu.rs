@@ -0,0 +1,162 @@
+use rltk::{VirtualKeyCode, RGB};
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::{
+    components::{BaseValue, InBackpack, Name, Rarity, Wallet},
+    gamelog::{GameLog, LogCategory},
+    map::map::Map,
+    player::{self, PlayerAction},
+    state::RunState,
+};
+
+use super::inventory_menu::{draw_item_list, item_color, select_menu, ItemMenuResult};
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum TradeMode {
+    Buy,
+    Sell,
+}
+
+pub fn trade_menu(
+    ecs: &mut World,
+    ctx: &mut rltk::Rltk,
+    vendor: Entity,
+    mode: TradeMode,
+) -> RunState {
+    if ctx.key == Some(VirtualKeyCode::Tab) {
+        let mode = match mode {
+            TradeMode::Buy => TradeMode::Sell,
+            TradeMode::Sell => TradeMode::Buy,
+        };
+        return RunState::ShowTrade { vendor, mode };
+    }
+
+    let selected_menu = show_trade_menu(ecs, ctx, vendor, mode);
+    match selected_menu {
+        ItemMenuResult::Cancel => RunState::AwaitingInput,
+        ItemMenuResult::NoResponse => RunState::ShowTrade { vendor, mode },
+        ItemMenuResult::Selected(item) => {
+            let item = player::marker(ecs, item);
+            let action = match mode {
+                TradeMode::Buy => PlayerAction::Buy { item },
+                TradeMode::Sell => PlayerAction::Sell {
+                    vendor: player::marker(ecs, vendor),
+                    item,
+                },
+            };
+            player::perform(ecs, action);
+            RunState::ShowTrade { vendor, mode }
+        }
+    }
+}
+
+fn show_trade_menu(
+    ecs: &mut World,
+    ctx: &mut rltk::Rltk,
+    vendor: Entity,
+    mode: TradeMode,
+) -> ItemMenuResult {
+    let player_entity = ecs.fetch::<Entity>();
+    let names = ecs.read_storage::<Name>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let values = ecs.read_storage::<BaseValue>();
+    let wallets = ecs.read_storage::<Wallet>();
+    let map = ecs.fetch::<Map>();
+    let entities = ecs.entities();
+
+    let (owner, title) = match mode {
+        TradeMode::Buy => (vendor, "Buy which item?"),
+        TradeMode::Sell => (*player_entity, "Sell which item?"),
+    };
+
+    let rarities = ecs.read_storage::<Rarity>();
+
+    let (items, labels): (Vec<Entity>, Vec<(String, RGB)>) =
+        (&entities, &backpack, &names, &values)
+            .join()
+            .filter(|(_, item, _, _)| item.owner == owner)
+            .map(|(entity, _, name, value)| {
+                let price = match mode {
+                    TradeMode::Buy => value.buy_price(),
+                    TradeMode::Sell => value.sell_price(),
+                };
+                let label = format!("{} ({} gold)", name.name, price);
+                (entity, (label, item_color(&rarities, entity)))
+            })
+            .unzip();
+    let count = draw_item_list(ctx, &map, title, &labels);
+
+    let gold = wallets.get(*player_entity).map_or(0, |wallet| wallet.gold);
+    let bottom = map.window_height / 2 - count / 2 + count + 2;
+    ctx.print_color(
+        18,
+        bottom,
+        RGB::named(rltk::GOLD),
+        RGB::named(rltk::BLACK),
+        format!("You have {} gold. TAB to switch", gold),
+    );
+
+    select_menu(ctx, count, items)
+}
+
+pub fn buy_item(ecs: &mut World, item: Entity) {
+    let player_entity = ecs.fetch::<Entity>();
+    let names = ecs.read_storage::<Name>();
+    let values = ecs.read_storage::<BaseValue>();
+    let mut wallets = ecs.write_storage::<Wallet>();
+    let mut backpack = ecs.write_storage::<InBackpack>();
+    let mut gamelog = ecs.fetch_mut::<GameLog>();
+
+    let price = values.get(item).map_or(0, |value| value.buy_price());
+    let wallet = match wallets.get_mut(*player_entity) {
+        None => return,
+        Some(wallet) => wallet,
+    };
+    let item_name = &names.get(item).unwrap().name;
+    if wallet.gold < price {
+        gamelog.log(
+            LogCategory::Loot,
+            format!("You can't afford the {}.", item_name),
+        );
+        return;
+    }
+
+    wallet.gold -= price;
+    backpack
+        .insert(
+            item,
+            InBackpack {
+                owner: *player_entity,
+            },
+        )
+        .expect("Unable to insert backpack entry");
+    gamelog.log(
+        LogCategory::Loot,
+        format!("You buy the {} for {} gold.", item_name, price),
+    );
+}
+
+pub fn sell_item(ecs: &mut World, vendor: Entity, item: Entity) {
+    let player_entity = ecs.fetch::<Entity>();
+    let names = ecs.read_storage::<Name>();
+    let values = ecs.read_storage::<BaseValue>();
+    let mut wallets = ecs.write_storage::<Wallet>();
+    let mut backpack = ecs.write_storage::<InBackpack>();
+    let mut gamelog = ecs.fetch_mut::<GameLog>();
+
+    let price = values.get(item).map_or(0, |value| value.sell_price());
+    if let Some(wallet) = wallets.get_mut(*player_entity) {
+        wallet.gold += price;
+    }
+    backpack
+        .insert(item, InBackpack { owner: vendor })
+        .expect("Unable to insert backpack entry");
+    gamelog.log(
+        LogCategory::Loot,
+        format!(
+            "You sell the {} for {} gold.",
+            names.get(item).unwrap().name,
+            price
+        ),
+    );
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rltk::RGB;
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::{
+    components::{EquipmentSlot, Equipped, InBackpack, Name, Position, Wallet},
+    gamelog::GameLog,
+    map::map::Map,
+    systems::{save_slots, saveload_system},
+};
+
+// How many of the last log lines the recap ends with
+const LOG_LINES: usize = 20;
+
+/// The recap of a run that ended in death, written to the morgue folder and shown on the game over screen
+pub struct Morgue {
+    pub lines: Vec<(String, RGB)>,
+    /// The file the recap went to, or why it couldn't be written
+    pub file: Result<PathBuf, io::Error>,
+}
+
+impl Morgue {
+    pub fn record(ecs: &World) -> Self {
+        let lines = recap(ecs);
+        let file = write(ecs, &lines);
+        Self { lines, file }
+    }
+}
+
+fn heading<S: ToString>(text: S) -> (String, RGB) {
+    (text.to_string(), RGB::named(rltk::YELLOW))
+}
+
+fn white<S: ToString>(text: S) -> (String, RGB) {
+    (text.to_string(), RGB::named(rltk::WHITE))
+}
+
+fn blank() -> (String, RGB) {
+    (String::new(), RGB::named(rltk::BLACK))
+}
+
+fn recap(ecs: &World) -> Vec<(String, RGB)> {
+    let player = *ecs.fetch::<Entity>();
+    let map = ecs.fetch::<Map>();
+    let gamelog = ecs.fetch::<GameLog>();
+    let names = ecs.read_storage::<Name>();
+    let run = saveload_system::run_stats(ecs);
+    let name = names
+        .get(player)
+        .map_or_else(|| "The player".to_string(), |name| name.name.clone());
+
+    let mut lines = vec![
+        heading(format!(
+            "{} died on depth {} after {} turns.",
+            name, map.depth, gamelog.turn
+        )),
+        white(format!(
+            "Cause of death: {}",
+            run.killed_by.as_deref().unwrap_or("unknown")
+        )),
+        white(format!("Deepest depth: {}", run.deepest_depth)),
+    ];
+    if let Some(wallet) = ecs.read_storage::<Wallet>().get(player) {
+        lines.push((format!("Gold: {}", wallet.gold), RGB::named(rltk::GOLD)));
+    }
+    lines.push(white(format!(
+        "Seed: {:016x}, {} mode",
+        run.seed,
+        run.mode.name()
+    )));
+    lines.push(blank());
+
+    let total: u32 = run.kills.values().sum();
+    lines.push(heading(format!("Kills: {}", total)));
+    for (victim, count) in run.kills.iter() {
+        lines.push(white(format!("  {:<24}{:>4}", victim, count)));
+    }
+    lines.push(blank());
+
+    lines.push(heading("Equipment"));
+    let equipped = ecs.read_storage::<Equipped>();
+    for (slot, slot_name) in [
+        (EquipmentSlot::Melee, "Melee"),
+        (EquipmentSlot::Shield, "Shield"),
+    ] {
+        let item = (&equipped, &names)
+            .join()
+            .find(|(equipped_by, _)| equipped_by.owner == player && equipped_by.slot == slot)
+            .map_or("(empty)".to_string(), |(_, name)| name.name.clone());
+        lines.push(white(format!("  {:<8}{}", slot_name, item)));
+    }
+    lines.push(blank());
+
+    lines.push(heading("Inventory"));
+    let mut carried = BTreeMap::new();
+    for (pack, item) in (&ecs.read_storage::<InBackpack>(), &names).join() {
+        if pack.owner == player {
+            *carried.entry(item.name.clone()).or_insert(0) += 1;
+        }
+    }
+    if carried.is_empty() {
+        lines.push(white("  (empty)"));
+    }
+    for (item, count) in carried {
+        lines.push(white(format!("  {:<24}{:>4}", item, count)));
+    }
+    lines.push(blank());
+
+    lines.push(heading("Last messages"));
+    let skipped = gamelog.entries().len().saturating_sub(LOG_LINES);
+    for entry in gamelog.entries().skip(skipped) {
+        let text: String = entry
+            .fragments
+            .iter()
+            .map(|fragment| fragment.text.as_str())
+            .collect();
+        lines.push((
+            format!("  {:>5}  {}", entry.turn, text),
+            entry.category.color(),
+        ));
+    }
+    lines.push(blank());
+
+    lines.push(heading(format!("Map of depth {}", map.depth)));
+    let mut rows = map.ascii_rows();
+    if let Some(position) = ecs.read_storage::<Position>().get(player) {
+        let x = position.x as usize;
+        if let Some(row) = rows.get_mut(position.y as usize) {
+            row.replace_range(x..x + 1, "@");
+        }
+    }
+    lines.extend(rows.into_iter().map(white));
+
+    lines
+}
+
+// Each death gets a file of its own, named after the character and the time of death
+fn write(ecs: &World, lines: &[(String, RGB)]) -> Result<PathBuf, io::Error> {
+    let player = *ecs.fetch::<Entity>();
+    let character: String = ecs
+        .read_storage::<Name>()
+        .get(player)
+        .map_or("player", |name| name.name.as_str())
+        .chars()
+        .filter(|c| save_slots::is_slot_char(*c))
+        .collect();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+
+    let dir = save_slots::game_dir().join("morgue");
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!(
+        "{}-{}.txt",
+        character,
+        save_slots::file_timestamp(timestamp)
+    ));
+    let text: String = lines
+        .iter()
+        .map(|(line, _)| format!("{}\n", line))
+        .collect();
+    fs::write(&path, text)?;
+    Ok(path)
+}
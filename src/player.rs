@@ -1,89 +1,265 @@
-use rltk::{Rltk, VirtualKeyCode, RGB};
-use specs::{prelude::*, Entity, World};
+use rltk::{RandomNumberGenerator, Rltk, RGB};
+use serde::{Deserialize, Serialize};
+use specs::{
+    prelude::*,
+    saveload::{Marker, SimpleMarker},
+    Entity, World,
+};
 use std::cmp::{max, min};
 
 use crate::{
+    automove,
+    camera::Camera,
     components::{
-        CombatStats, Confusion, EntityMoved, HungerClock, HungerState, Item, Monster, Player,
-        Position, Viewshed, WantsToMelee, WantsToPickupItem,
+        CombatStats, Confusion, Door, EntityMoved, EntryTrigger, Hidden, HungerClock, HungerState,
+        Item, Monster, Name, Player, Position, SerializeMe, Vendor, Viewshed, WantsToCloseDoor,
+        WantsToDropItem, WantsToMelee, WantsToOpenDoor, WantsToPickupItem, WantsToUnequipItem,
+        WantsToUseItem,
     },
-    gamelog::GameLog,
+    gamelog::{GameLog, LogCategory},
+    keybindings::{Action, KeyBindings},
     map::map::{Map, TileType},
-    menu::pause_menu::PauseMenuSelection,
+    menu::{
+        help_menu::HelpPage,
+        pause_menu::PauseMenuSelection,
+        trade_menu::{self, TradeMode},
+    },
+    replay::{self, Event},
     state::RunState,
-    systems::particle_system::ParticleBuilder,
+    systems::{particle_system::ParticleBuilder, trigger_system::SprungTraps},
 };
 
-pub fn player_input(ecs: &mut World, ctx: &mut Rltk) -> RunState {
-    {
-        let player = ecs.fetch::<Entity>();
-        let mut confusion = ecs.write_storage::<Confusion>();
-        if let Some(confused) = confusion.get_mut(*player) {
-            let mut gamelog = ecs.fetch_mut::<GameLog>();
-            gamelog.entries.push("You are still confused".to_string());
-            confused.turns -= 1;
-            if confused.turns == 0 {
-                confusion.remove(*player);
+// One in DISARM_CHANCE attempts to disarm a trap fails and sets it off
+pub const DISARM_CHANCE: i32 = 3;
+
+/// Something the player does that changes the world, and the only way the player changes it
+///
+/// Items and vendors are named by their save marker, which a replay hands out the same way
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum PlayerAction {
+    Move { delta_x: i32, delta_y: i32 },
+    Pickup,
+    Descend,
+    DisarmTrap,
+    CloseDoor,
+    SkipTurn,
+    UseItem { item: u64, target: Option<Position> },
+    DropItem { item: u64 },
+    UnequipItem { item: u64 },
+    Buy { item: u64 },
+    Sell { vendor: u64, item: u64 },
+}
+
+/// Carries out the action and records it when the run is being recorded
+pub fn perform(ecs: &mut World, action: PlayerAction) -> RunState {
+    replay::record(ecs, Event::Action(action));
+    match action {
+        PlayerAction::Move { delta_x, delta_y } => try_move_player(ecs, delta_x, delta_y),
+        PlayerAction::Pickup => {
+            pickup(ecs);
+            RunState::PlayerTurn
+        }
+        PlayerAction::Descend => {
+            if try_next_level(ecs) {
+                RunState::NextLevel
+            } else {
+                RunState::PlayerTurn
             }
-            let player_pos = ecs.write_resource::<Position>();
-            ecs.fetch_mut::<ParticleBuilder>().request(
-                player_pos.x,
-                player_pos.y,
-                RGB::named(rltk::MAGENTA),
-                RGB::named(rltk::BLACK),
-                rltk::to_cp437('?'),
-                200.0,
-            );
-            return RunState::PlayerTurn;
         }
+        PlayerAction::DisarmTrap => disarm_trap(ecs),
+        PlayerAction::CloseDoor => close_door(ecs),
+        PlayerAction::SkipTurn => skip_turn(ecs),
+        PlayerAction::UseItem { item, target } => match marked_entity(ecs, item) {
+            None => item_gone(ecs),
+            Some(item) => {
+                let player = *ecs.fetch::<Entity>();
+                ecs.write_storage::<WantsToUseItem>()
+                    .insert(player, WantsToUseItem { item, target })
+                    .expect("Unable to insert intent");
+                RunState::PlayerTurn
+            }
+        },
+        PlayerAction::DropItem { item } => match marked_entity(ecs, item) {
+            None => item_gone(ecs),
+            Some(item) => {
+                let player = *ecs.fetch::<Entity>();
+                let position = *ecs.fetch::<Position>();
+                ecs.write_storage::<WantsToDropItem>()
+                    .insert(player, WantsToDropItem { item, position })
+                    .expect("Unable to insert intent");
+                RunState::PlayerTurn
+            }
+        },
+        PlayerAction::UnequipItem { item } => match marked_entity(ecs, item) {
+            None => item_gone(ecs),
+            Some(item) => {
+                let player = *ecs.fetch::<Entity>();
+                ecs.write_storage::<WantsToUnequipItem>()
+                    .insert(player, WantsToUnequipItem { item })
+                    .expect("Unable to insert intent");
+                RunState::PlayerTurn
+            }
+        },
+        PlayerAction::Buy { item } => {
+            if let Some(item) = marked_entity(ecs, item) {
+                trade_menu::buy_item(ecs, item);
+            }
+            RunState::AwaitingInput
+        }
+        PlayerAction::Sell { vendor, item } => {
+            if let (Some(vendor), Some(item)) =
+                (marked_entity(ecs, vendor), marked_entity(ecs, item))
+            {
+                trade_menu::sell_item(ecs, vendor, item);
+            }
+            RunState::AwaitingInput
+        }
+    }
+}
+
+/// The save marker of an entity, what actions name items and vendors by
+pub fn marker(ecs: &World, entity: Entity) -> u64 {
+    ecs.read_storage::<SimpleMarker<SerializeMe>>()
+        .get(entity)
+        .map_or(0, |marker| marker.id())
+}
+
+/// The entity with the save marker, if it's still around
+pub fn marked_entity(ecs: &World, id: u64) -> Option<Entity> {
+    let entities = ecs.entities();
+    let markers = ecs.read_storage::<SimpleMarker<SerializeMe>>();
+    (&entities, &markers)
+        .join()
+        .find(|(_, marker)| marker.id() == id)
+        .map(|(entity, _)| entity)
+}
+
+fn item_gone(ecs: &mut World) -> RunState {
+    ecs.fetch_mut::<GameLog>()
+        .log(LogCategory::System, "That item is gone.");
+    RunState::AwaitingInput
+}
+
+/// A confused player stumbles through the turn instead of acting, true while the confusion lasts
+pub fn confused_turn(ecs: &mut World) -> bool {
+    let player = ecs.fetch::<Entity>();
+    let mut confusion = ecs.write_storage::<Confusion>();
+    let confused = match confusion.get_mut(*player) {
+        None => return false,
+        Some(confused) => confused,
     };
+    let mut gamelog = ecs.fetch_mut::<GameLog>();
+    gamelog.log(LogCategory::Status, "You are still confused");
+    confused.turns -= 1;
+    if confused.turns == 0 {
+        confusion.remove(*player);
+    }
+    let player_pos = ecs.write_resource::<Position>();
+    ecs.fetch_mut::<ParticleBuilder>().request(
+        player_pos.x,
+        player_pos.y,
+        RGB::named(rltk::MAGENTA),
+        RGB::named(rltk::BLACK),
+        rltk::to_cp437('?'),
+        200.0,
+    );
+    true
+}
+
+pub fn player_input(ecs: &mut World, ctx: &mut Rltk) -> RunState {
+    if confused_turn(ecs) {
+        return RunState::PlayerTurn;
+    }
 
-    // Player movement
-    match ctx.key {
+    // Clicking a tile travels there
+    if ctx.left_click {
+        let (mouse_x, mouse_y) = ctx.mouse_pos();
+        if let Some(target) = Camera::from_ecs(ecs).to_world(mouse_x, mouse_y) {
+            return automove::start_travel(ecs, target);
+        }
+    }
+
+    let action = match ctx.key {
         None => return RunState::AwaitingInput,
-        Some(key) => match key {
-            VirtualKeyCode::Numpad4 | VirtualKeyCode::A => try_move_player(ecs, -1, 0),
-            VirtualKeyCode::Numpad6 | VirtualKeyCode::D => try_move_player(ecs, 1, 0),
-            VirtualKeyCode::Numpad8 | VirtualKeyCode::W => try_move_player(ecs, 0, -1),
-            VirtualKeyCode::Numpad2 | VirtualKeyCode::X => try_move_player(ecs, 0, 1),
-
-            // Diagonals
-            VirtualKeyCode::Numpad9 | VirtualKeyCode::E => try_move_player(ecs, 1, -1),
-            VirtualKeyCode::Numpad7 | VirtualKeyCode::Q => try_move_player(ecs, -1, -1),
-            VirtualKeyCode::Numpad3 | VirtualKeyCode::C => try_move_player(ecs, 1, 1),
-            VirtualKeyCode::Numpad1 | VirtualKeyCode::Z => try_move_player(ecs, -1, 1),
-
-            // Pickup
-            VirtualKeyCode::Numpad5 | VirtualKeyCode::S => pickup(ecs),
-
-            // Inventory
-            VirtualKeyCode::I => return RunState::ShowInventory,
-            VirtualKeyCode::R => return RunState::ShowDropItem,
-            VirtualKeyCode::U => return RunState::ShowUnequipItem,
-
-            // Save and Quit
-            VirtualKeyCode::Escape => {
-                return RunState::PauseMenu {
-                    selected_menu: PauseMenuSelection::Restart,
-                }
-            }
+        Some(key) => ecs.fetch::<KeyBindings>().action(key),
+    };
+    let action = match action {
+        None => return RunState::AwaitingInput,
+        Some(action) => action,
+    };
 
-            // Level changes
-            VirtualKeyCode::Period => {
-                if try_next_level(ecs) {
-                    return RunState::NextLevel;
-                }
+    // Player movement, running with shift
+    if let Some((delta_x, delta_y)) = action.direction() {
+        if ctx.shift {
+            return automove::start_run(ecs, delta_x, delta_y);
+        }
+        return perform(ecs, PlayerAction::Move { delta_x, delta_y });
+    }
+
+    match action {
+        // Pickup
+        Action::Pickup => perform(ecs, PlayerAction::Pickup),
+
+        // Inventory
+        Action::Inventory => RunState::ShowInventory,
+        Action::DropItem => RunState::ShowDropItem,
+        Action::UnequipItem => RunState::ShowUnequipItem,
+
+        // Look around
+        Action::Look => {
+            let player_pos = ecs.fetch::<Position>();
+            RunState::Look {
+                x: player_pos.x,
+                y: player_pos.y,
+                index: 0,
             }
+        }
+
+        // Character sheet
+        Action::CharacterSheet => RunState::ShowCharacter,
+
+        // Message log
+        Action::MessageLog => RunState::ShowLog {
+            scroll: 0,
+            filter: None,
+        },
+
+        // Help
+        Action::Help => RunState::ShowHelp {
+            page: HelpPage::Keys,
+        },
+
+        // Save and Quit
+        Action::Menu => RunState::PauseMenu {
+            selected_menu: PauseMenuSelection::Restart,
+        },
+
+        // Level changes, with shift travel to the stairs first
+        Action::Descend if ctx.shift => automove::travel_to_stairs(ecs),
+        Action::Descend => perform(ecs, PlayerAction::Descend),
+
+        // Traps
+        Action::DisarmTrap => perform(ecs, PlayerAction::DisarmTrap),
 
-            // Skip turn
-            VirtualKeyCode::Space => return skip_turn(ecs),
-            _ => return RunState::AwaitingInput,
+        // Doors
+        Action::CloseDoor => perform(ecs, PlayerAction::CloseDoor),
+
+        // Map overview
+        Action::Overview => RunState::ShowOverview {
+            cursor: *ecs.fetch::<Position>(),
+            editing: false,
         },
+
+        // Auto-explore
+        Action::AutoExplore => automove::start_explore(ecs),
+
+        // Skip turn
+        Action::SkipTurn => perform(ecs, PlayerAction::SkipTurn),
+        _ => RunState::AwaitingInput,
     }
-    RunState::PlayerTurn
 }
 
-fn try_move_player(ecs: &mut World, delta_x: i32, delta_y: i32) {
+fn try_move_player(ecs: &mut World, delta_x: i32, delta_y: i32) -> RunState {
     let mut positions = ecs.write_storage::<Position>();
     let mut players = ecs.write_storage::<Player>();
     let mut viewsheds = ecs.write_storage::<Viewshed>();
@@ -93,6 +269,9 @@ fn try_move_player(ecs: &mut World, delta_x: i32, delta_y: i32) {
     let entities = ecs.entities();
     let mut wants_to_melee = ecs.write_storage::<WantsToMelee>();
     let mut entity_moved = ecs.write_storage::<EntityMoved>();
+    let vendors = ecs.read_storage::<Vendor>();
+    let doors = ecs.read_storage::<Door>();
+    let mut wants_to_open = ecs.write_storage::<WantsToOpenDoor>();
 
     for (entity, _player, pos, viewshed) in
         (&entities, &mut players, &mut positions, &mut viewsheds).join()
@@ -100,12 +279,33 @@ fn try_move_player(ecs: &mut World, delta_x: i32, delta_y: i32) {
         let new_x = (pos.x as i32 + delta_x) as u16;
         let new_y = (pos.y as i32 + delta_y) as u16;
         if new_x < 1 || new_x > map.width - 1 || new_y < 1 || new_y > map.height - 1 {
-            return;
+            return RunState::PlayerTurn;
         }
 
         let destination_idx = map.index_from_xy(new_x, new_y);
 
         for potential_target in map.tile_content[destination_idx].iter() {
+            if vendors.contains(*potential_target) {
+                return RunState::ShowTrade {
+                    vendor: *potential_target,
+                    mode: TradeMode::Buy,
+                };
+            }
+
+            if let Some(door) = doors.get(*potential_target) {
+                if !door.open {
+                    wants_to_open
+                        .insert(
+                            entity,
+                            WantsToOpenDoor {
+                                door: *potential_target,
+                            },
+                        )
+                        .expect("Unable to insert door intent");
+                    return RunState::PlayerTurn;
+                }
+            }
+
             let target = combat_stats.get(*potential_target);
             if let Some(_target) = target {
                 wants_to_melee
@@ -116,7 +316,7 @@ fn try_move_player(ecs: &mut World, delta_x: i32, delta_y: i32) {
                         },
                     )
                     .expect("Add target failed");
-                return;
+                return RunState::PlayerTurn;
             }
         }
         if !map.blocked[destination_idx] {
@@ -130,6 +330,7 @@ fn try_move_player(ecs: &mut World, delta_x: i32, delta_y: i32) {
                 .expect("Unable to insert marker");
         }
     }
+    RunState::PlayerTurn
 }
 
 fn pickup(ecs: &mut World) {
@@ -148,9 +349,7 @@ fn pickup(ecs: &mut World) {
     }
 
     match target_item {
-        None => gamelog
-            .entries
-            .push("There is nothing here to pickup".to_string()),
+        None => gamelog.log(LogCategory::System, "There is nothing here to pickup"),
         Some(item) => {
             let mut pickup = ecs.write_storage::<WantsToPickupItem>();
             pickup
@@ -166,6 +365,91 @@ fn pickup(ecs: &mut World) {
     }
 }
 
+fn close_door(ecs: &mut World) -> RunState {
+    let player_pos = *ecs.fetch::<Position>();
+    let player_entity = *ecs.fetch::<Entity>();
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    let doors = ecs.read_storage::<Door>();
+    let map = ecs.fetch::<Map>();
+    let mut wants_to_close = ecs.write_storage::<WantsToCloseDoor>();
+    let mut gamelog = ecs.fetch_mut::<GameLog>();
+
+    let door = (&entities, &doors, &positions)
+        .join()
+        .find(|(_, door, pos)| {
+            door.open
+                && i32::abs(pos.x as i32 - player_pos.x as i32) <= 1
+                && i32::abs(pos.y as i32 - player_pos.y as i32) <= 1
+        })
+        .map(|(entity, _, pos)| (entity, *pos));
+    let (door, door_pos) = match door {
+        None => {
+            gamelog.log(LogCategory::System, "There is no open door nearby.");
+            return RunState::AwaitingInput;
+        }
+        Some(door) => door,
+    };
+
+    // Something standing in the doorway keeps the door from closing
+    let idx = map.index_from_xy(door_pos.x, door_pos.y);
+    if map.tile_content[idx].iter().any(|content| *content != door) {
+        gamelog.log(LogCategory::System, "Something is blocking the doorway.");
+        return RunState::AwaitingInput;
+    }
+
+    wants_to_close
+        .insert(player_entity, WantsToCloseDoor { door })
+        .expect("Unable to insert door intent");
+    RunState::PlayerTurn
+}
+
+fn disarm_trap(ecs: &mut World) -> RunState {
+    let player_pos = *ecs.fetch::<Position>();
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    let triggers = ecs.read_storage::<EntryTrigger>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let names = ecs.read_storage::<Name>();
+    let mut gamelog = ecs.fetch_mut::<GameLog>();
+
+    // Only traps the player knows about can be disarmed
+    let trap = (&entities, &triggers, &positions, !&hidden)
+        .join()
+        .find(|(_, _, pos, _)| {
+            i32::abs(pos.x as i32 - player_pos.x as i32) <= 1
+                && i32::abs(pos.y as i32 - player_pos.y as i32) <= 1
+        })
+        .map(|(entity, _, _, _)| entity);
+    let trap = match trap {
+        None => {
+            gamelog.log(LogCategory::System, "There are no known traps next to you.");
+            return RunState::AwaitingInput;
+        }
+        Some(trap) => trap,
+    };
+
+    let trap_name = names
+        .get(trap)
+        .map_or("trap".to_string(), |name| name.name.to_string());
+    let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+    if rng.roll_dice(1, DISARM_CHANCE) == 1 {
+        gamelog.log(
+            LogCategory::Status,
+            format!("You fail to disarm the {}, and it goes off!", trap_name),
+        );
+        ecs.fetch_mut::<SprungTraps>()
+            .request(*ecs.fetch::<Entity>(), trap);
+    } else {
+        gamelog.log(
+            LogCategory::Status,
+            format!("You disarm the {}.", trap_name),
+        );
+        entities.delete(trap).expect("Unable to delete trap");
+    }
+    RunState::PlayerTurn
+}
+
 fn try_next_level(ecs: &mut World) -> bool {
     let player_pos = ecs.fetch::<Position>();
     let map = ecs.fetch::<Map>();
@@ -174,9 +458,7 @@ fn try_next_level(ecs: &mut World) -> bool {
         true
     } else {
         let mut gamelog = ecs.fetch_mut::<GameLog>();
-        gamelog
-            .entries
-            .push("There is no way down from here.".to_string());
+        gamelog.log(LogCategory::System, "There is no way down from here.");
         false
     }
 }
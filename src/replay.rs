@@ -0,0 +1,591 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specs::World;
+
+use crate::{
+    components::{self, GameMode},
+    game_loop,
+    gamelog::GameLog,
+    level,
+    player::{self, PlayerAction},
+    settings::Settings,
+    state::RunState,
+    systems::{
+        save_format, save_slots,
+        saveload_system::{self, SaveError},
+        Systems,
+    },
+};
+
+// Format of new recordings, a replay refuses recordings of any other version
+const RECORDING_VERSION: u32 = 1;
+
+// Turns between the world hashes a recording keeps to check a replay against
+const CHECKPOINT_TURNS: u32 = 10;
+
+/// The first line of a recording, what a replay needs to start the same run
+#[derive(Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub version: u32,
+    pub seed: u64,
+    pub mode: GameMode,
+    pub timestamp: u64,
+}
+
+/// One line of a recording after the header, in the order it happened
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Event {
+    /// The systems ran, entities with a lifetime aged by the milliseconds since they last ran
+    Step {
+        elapsed_ms: u32,
+    },
+    Action(PlayerAction),
+    /// The world hash once the turn was over
+    Checkpoint {
+        turn: u32,
+        hash: u64,
+    },
+}
+
+/// The recording of the run being played, runs without this resource aren't recorded
+pub struct Recorder {
+    file: Option<File>,
+}
+
+impl Recorder {
+    // A recording that can't be written to stops, the run goes on without it
+    fn write(&mut self, event: &Event) {
+        let file = match self.file.as_mut() {
+            None => return,
+            Some(file) => file,
+        };
+        let result = serde_json::to_string(event)
+            .map_err(io::Error::from)
+            .and_then(|line| file.write_all(format!("{}\n", line).as_bytes()));
+        if let Err(error) = result {
+            rltk::console::log(format!("Couldn't write to the recording: {}", error));
+            self.file = None;
+        }
+    }
+}
+
+pub fn recordings_dir() -> PathBuf {
+    save_slots::game_dir().join("recordings")
+}
+
+/// Starts recording the run the world holds, it has to be on its first turn still
+pub fn start_recording(ecs: &mut World) {
+    let run = saveload_system::run_stats(ecs);
+    let header = RecordingHeader {
+        version: RECORDING_VERSION,
+        seed: run.seed,
+        mode: run.mode,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs()),
+    };
+    match create_recording(&header) {
+        Ok(file) => {
+            ecs.insert(Recorder { file: Some(file) });
+            checkpoint(ecs);
+        }
+        Err(error) => rltk::console::log(format!("Couldn't start recording the run: {}", error)),
+    }
+}
+
+// Each run gets a file of its own, named after its seed and when it started
+fn create_recording(header: &RecordingHeader) -> Result<File, io::Error> {
+    fs::create_dir_all(recordings_dir())?;
+    let path = recordings_dir().join(format!(
+        "{:016x}-{}.jsonl",
+        header.seed,
+        save_slots::file_timestamp(header.timestamp)
+    ));
+    let mut file = File::create(path)?;
+    writeln!(file, "{}", serde_json::to_string(header)?)?;
+    Ok(file)
+}
+
+/// Adds the event to the recording, when the run is being recorded
+pub fn record(ecs: &World, event: Event) {
+    if let Some(mut recorder) = ecs.try_fetch_mut::<Recorder>() {
+        recorder.write(&event);
+    }
+}
+
+/// Records the world hash when the turn is a multiple of the checkpoint interval
+pub fn checkpoint_if_due(ecs: &mut World) {
+    if ecs.fetch::<GameLog>().turn.is_multiple_of(CHECKPOINT_TURNS) {
+        checkpoint(ecs);
+    }
+}
+
+/// Records the world hash, for a replay to check it reached the same world
+pub fn checkpoint(ecs: &mut World) {
+    if !ecs.has_value::<Recorder>() {
+        return;
+    }
+    let turn = ecs.fetch::<GameLog>().turn;
+    match world_hash(ecs) {
+        Ok(hash) => record(ecs, Event::Checkpoint { turn, hash }),
+        Err(error) => rltk::console::log(format!("Couldn't hash the world: {}", error)),
+    }
+}
+
+/// A hash of everything a save holds, except the message log and the notes left on the map
+///
+/// Those two also change with what the player looks at, which a recording leaves out
+pub fn world_hash(ecs: &mut World) -> Result<u64, SaveError> {
+    let mut components = saveload_system::snapshot(ecs)?;
+    components.remove("GameLogSerializationHelper");
+    if let Some(Value::Array(helpers)) = components.get_mut("MapSerializationHelper") {
+        for helper in helpers.iter_mut() {
+            if let Some(map) = helper
+                .pointer_mut("/components/0/map")
+                .and_then(Value::as_object_mut)
+            {
+                map.remove("notes");
+            }
+        }
+    }
+    let data = serde_json::to_vec(&Value::Object(components)).map_err(SaveError::Body)?;
+    Ok(save_format::hash(&data))
+}
+
+pub enum ReplayError {
+    Io(io::Error),
+    Empty,
+    Version(u32),
+    Line {
+        number: usize,
+        error: serde_json::Error,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(error) => write!(f, "Couldn't read the recording: {}", error),
+            ReplayError::Empty => write!(f, "The recording is empty."),
+            ReplayError::Version(version) => write!(
+                f,
+                "The recording is version {}, only version {} can be replayed.",
+                version, RECORDING_VERSION
+            ),
+            ReplayError::Line { number, error } => {
+                write!(f, "Line {} of the recording is invalid: {}", number, error)
+            }
+        }
+    }
+}
+
+/// A recording read back from its file
+pub struct Recording {
+    pub header: RecordingHeader,
+    pub events: Vec<Event>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let data = fs::read_to_string(path).map_err(ReplayError::Io)?;
+        let mut lines = data
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty());
+        let header: RecordingHeader = match lines.next() {
+            None => return Err(ReplayError::Empty),
+            Some((index, line)) => parse_line(index, line)?,
+        };
+        if header.version != RECORDING_VERSION {
+            return Err(ReplayError::Version(header.version));
+        }
+
+        let lines: Vec<(usize, &str)> = lines.collect();
+        let mut events = Vec::with_capacity(lines.len());
+        for (position, (index, line)) in lines.iter().enumerate() {
+            match parse_line(*index, line) {
+                Ok(event) => events.push(event),
+                // A game that crashed may have left its last line half written
+                Err(_) if position == lines.len() - 1 && !data.ends_with('\n') => break,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(Self { header, events })
+    }
+}
+
+fn parse_line<'a, T: Deserialize<'a>>(index: usize, line: &'a str) -> Result<T, ReplayError> {
+    serde_json::from_str(line).map_err(|error| ReplayError::Line {
+        number: index + 1,
+        error,
+    })
+}
+
+/// How a replay ended
+pub enum ReplayEnd {
+    /// Every event played out and every checkpoint matched
+    Finished { turns: u32, checkpoints: u32 },
+    /// The world at a checkpoint isn't the one that was recorded
+    Diverged {
+        last_match: u32,
+        turn: u32,
+        expected: u64,
+        found: u64,
+        actions: Vec<PlayerAction>,
+    },
+    /// The recording asks for something the replay can't do at that point
+    Broken { turn: u32, reason: String },
+}
+
+impl ReplayEnd {
+    pub fn matched(&self) -> bool {
+        matches!(self, ReplayEnd::Finished { .. })
+    }
+
+    /// What happened, a line at a time
+    pub fn report(&self) -> Vec<String> {
+        match self {
+            ReplayEnd::Finished { turns, checkpoints } => vec![format!(
+                "The replay matched the recording, {} checkpoints over {} turns.",
+                checkpoints, turns
+            )],
+            ReplayEnd::Diverged {
+                last_match,
+                turn,
+                expected,
+                found,
+                actions,
+            } => {
+                let mut lines = vec![
+                    format!(
+                        "The replay diverged between turn {} and turn {}.",
+                        last_match, turn
+                    ),
+                    format!(
+                        "The recording has world hash {:016x}, the replay has {:016x}.",
+                        expected, found
+                    ),
+                    format!("Actions since turn {}:", last_match),
+                ];
+                lines.extend(actions.iter().map(|action| format!("  {:?}", action)));
+                lines
+            }
+            ReplayEnd::Broken { turn, reason } => {
+                vec![format!("The replay stopped on turn {}: {}.", turn, reason)]
+            }
+        }
+    }
+}
+
+/// A world to play recordings back in, it never autosaves, records or adds to the history
+pub fn replay_world() -> World {
+    let mut ecs = components::new_world();
+    ecs.insert(Settings::default());
+    ecs
+}
+
+/// A recorded run being played back through the same turn phases as the game
+pub struct Playback {
+    events: Vec<Event>,
+    next: usize,
+    phase: RunState,
+    systems: Systems,
+    checkpoints: u32,
+    last_match: u32,
+    actions: Vec<PlayerAction>,
+    end: Option<ReplayEnd>,
+}
+
+impl Playback {
+    /// Starts the recorded run over, in a world that has no run in it yet
+    pub fn start(ecs: &mut World, recording: Recording) -> Self {
+        level::start_run(ecs, recording.header.seed, recording.header.mode);
+        Self {
+            events: recording.events,
+            next: 0,
+            phase: RunState::PreRun,
+            systems: Systems::new(),
+            checkpoints: 0,
+            last_match: 0,
+            actions: Vec::new(),
+            end: None,
+        }
+    }
+
+    pub fn end(&self) -> Option<&ReplayEnd> {
+        self.end.as_ref()
+    }
+
+    /// Plays the events up to the end of the next turn, or up to the end of the replay
+    pub fn next_turn(&mut self, ecs: &mut World) {
+        while self.end.is_none() {
+            if self.step(ecs) {
+                return;
+            }
+        }
+    }
+
+    /// Plays every event that's left, as fast as it goes
+    pub fn play_to_end(mut self, ecs: &mut World) -> ReplayEnd {
+        loop {
+            self.next_turn(ecs);
+            if let Some(end) = self.end.take() {
+                return end;
+            }
+        }
+    }
+
+    // One event or phase change, true when it ended a turn
+    fn step(&mut self, ecs: &mut World) -> bool {
+        // Checkpoints come right after the turn they were taken at, whatever comes next
+        if let Some(Event::Checkpoint { turn, hash }) = self.events.get(self.next).copied() {
+            self.next += 1;
+            self.check(ecs, turn, hash);
+            return false;
+        }
+
+        match self.phase {
+            RunState::PreRun | RunState::PlayerTurn | RunState::MonsterTurn => {
+                let elapsed_ms = match self.events.get(self.next) {
+                    Some(Event::Step { elapsed_ms }) => *elapsed_ms,
+                    // The game was closed between two turns
+                    None => {
+                        self.finish(ecs);
+                        return true;
+                    }
+                    other => {
+                        let reason =
+                            format!("the systems ran but the recording has {}", describe(other));
+                        self.stop(ecs, reason);
+                        return true;
+                    }
+                };
+                self.next += 1;
+                let phase = self.phase;
+                self.phase = game_loop::run_turn_phase(ecs, &mut self.systems, phase, elapsed_ms);
+                phase == RunState::MonsterTurn
+            }
+            RunState::NextLevel => {
+                level::next_level(ecs);
+                self.phase = RunState::PreRun;
+                false
+            }
+            RunState::Dead { .. } => {
+                self.finish(ecs);
+                true
+            }
+            // Waiting for the player, who acts or stumbles about confused the same way as in the game
+            _ => {
+                ecs.insert(self.phase);
+                self.systems.run_during_pause(ecs);
+                if player::confused_turn(ecs) {
+                    self.phase = RunState::PlayerTurn;
+                    return false;
+                }
+                let action = match self.events.get(self.next) {
+                    None => {
+                        self.finish(ecs);
+                        return true;
+                    }
+                    Some(Event::Action(action)) => *action,
+                    other => {
+                        let reason =
+                            format!("the player acts but the recording has {}", describe(other));
+                        self.stop(ecs, reason);
+                        return true;
+                    }
+                };
+                self.next += 1;
+                self.actions.push(action);
+                self.phase = match player::perform(ecs, action) {
+                    RunState::PlayerTurn => RunState::PlayerTurn,
+                    RunState::NextLevel => RunState::NextLevel,
+                    _ => RunState::AwaitingInput,
+                };
+                false
+            }
+        }
+    }
+
+    fn check(&mut self, ecs: &mut World, turn: u32, expected: u64) {
+        let current = ecs.fetch::<GameLog>().turn;
+        if current != turn {
+            self.stop(
+                ecs,
+                format!("the recording checks turn {} on turn {}", turn, current),
+            );
+            return;
+        }
+        let found = match world_hash(ecs) {
+            Ok(found) => found,
+            Err(error) => {
+                self.stop(ecs, format!("the world couldn't be hashed, {}", error));
+                return;
+            }
+        };
+        if found != expected {
+            self.end = Some(ReplayEnd::Diverged {
+                last_match: self.last_match,
+                turn,
+                expected,
+                found,
+                actions: std::mem::take(&mut self.actions),
+            });
+            return;
+        }
+        self.checkpoints += 1;
+        self.last_match = turn;
+        self.actions.clear();
+    }
+
+    fn finish(&mut self, ecs: &World) {
+        if self.next < self.events.len() {
+            self.stop(ecs, "the run ended before the recording did".to_string());
+            return;
+        }
+        self.end = Some(ReplayEnd::Finished {
+            turns: ecs.fetch::<GameLog>().turn,
+            checkpoints: self.checkpoints,
+        });
+    }
+
+    fn stop(&mut self, ecs: &World, reason: String) {
+        self.end = Some(ReplayEnd::Broken {
+            turn: ecs.fetch::<GameLog>().turn,
+            reason,
+        });
+    }
+}
+
+fn describe(event: Option<&Event>) -> String {
+    event.map_or("nothing more".to_string(), |event| format!("{:?}", event))
+}
+
+/// Plays the recording back without a window and reports how it went
+pub fn replay_headless(path: &Path) -> Result<ReplayEnd, ReplayError> {
+    let recording = Recording::load(path)?;
+    let mut ecs = replay_world();
+    let playback = Playback::start(&mut ecs, recording);
+    Ok(playback.play_to_end(&mut ecs))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use rltk::RandomNumberGenerator;
+
+    use super::*;
+
+    // Records a run of random actions through the same turn phases as the game, until it ends or
+    // reaches the given turn
+    fn record_run(seed: u64, path: &Path, turns: u32) {
+        let mut ecs = replay_world();
+        level::start_run(&mut ecs, seed, GameMode::Casual);
+        let header = RecordingHeader {
+            version: RECORDING_VERSION,
+            seed,
+            mode: GameMode::Casual,
+            timestamp: 0,
+        };
+        let mut file = File::create(path).unwrap();
+        writeln!(file, "{}", serde_json::to_string(&header).unwrap()).unwrap();
+        ecs.insert(Recorder { file: Some(file) });
+        checkpoint(&mut ecs);
+
+        let mut systems = Systems::new();
+        let mut phase = RunState::PreRun;
+        let mut script = RandomNumberGenerator::seeded(seed);
+        while ecs.fetch::<GameLog>().turn < turns {
+            phase = match phase {
+                RunState::PreRun | RunState::PlayerTurn | RunState::MonsterTurn => {
+                    // Uneven frame times, so particles expire on different turns
+                    let elapsed_ms = script.roll_dice(1, 300) as u32;
+                    game_loop::run_turn_phase(&mut ecs, &mut systems, phase, elapsed_ms)
+                }
+                RunState::NextLevel => {
+                    level::next_level(&mut ecs);
+                    RunState::PreRun
+                }
+                RunState::Dead { .. } => break,
+                _ => {
+                    ecs.insert(phase);
+                    systems.run_during_pause(&mut ecs);
+                    if player::confused_turn(&mut ecs) {
+                        RunState::PlayerTurn
+                    } else {
+                        let action = match script.roll_dice(1, 6) {
+                            1 => PlayerAction::SkipTurn,
+                            2 => PlayerAction::Pickup,
+                            3 => PlayerAction::Descend,
+                            _ => PlayerAction::Move {
+                                delta_x: script.roll_dice(1, 3) - 2,
+                                delta_y: script.roll_dice(1, 3) - 2,
+                            },
+                        };
+                        match player::perform(&mut ecs, action) {
+                            RunState::PlayerTurn => RunState::PlayerTurn,
+                            RunState::NextLevel => RunState::NextLevel,
+                            _ => RunState::AwaitingInput,
+                        }
+                    }
+                }
+            };
+        }
+    }
+
+    fn recording_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("replay-{}-{}.jsonl", process::id(), name))
+    }
+
+    #[test]
+    fn replay_matches_recording() {
+        for seed in 1..4 {
+            let path = recording_path(&seed.to_string());
+            record_run(seed, &path, 100);
+            let end = replay_headless(&path).ok().unwrap();
+            fs::remove_file(&path).unwrap();
+            assert!(end.matched(), "seed {}: {}", seed, end.report().join(" "));
+        }
+    }
+
+    #[test]
+    fn changed_checkpoint_diverges() {
+        let path = recording_path("diverged");
+        record_run(1, &path, 40);
+        let mut recording = Recording::load(&path).ok().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let checkpoints: Vec<(usize, u32)> = recording
+            .events
+            .iter()
+            .enumerate()
+            .filter_map(|(index, event)| match event {
+                Event::Checkpoint { turn, .. } => Some((index, *turn)),
+                _ => None,
+            })
+            .collect();
+        let (index, changed_turn) = checkpoints[2];
+        if let Event::Checkpoint { hash, .. } = &mut recording.events[index] {
+            *hash ^= 1;
+        }
+
+        let mut ecs = replay_world();
+        let end = Playback::start(&mut ecs, recording).play_to_end(&mut ecs);
+        match end {
+            ReplayEnd::Diverged {
+                last_match, turn, ..
+            } => {
+                assert_eq!(last_match, checkpoints[1].1);
+                assert_eq!(turn, changed_turn);
+            }
+            _ => panic!("{}", end.report().join(" ")),
+        }
+    }
+}
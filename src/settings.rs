@@ -0,0 +1,71 @@
+use std::{fmt, fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::components::GameMode;
+use crate::systems::save_format::{SaveEncoding, SaveFormat};
+
+// User config with the game settings, read from the working directory
+const SETTINGS_FILE: &str = "./settings.json";
+
+// Turns between autosaves when the settings don't say
+const DEFAULT_AUTOSAVE_TURNS: u32 = 100;
+
+pub enum SettingsError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingsError::Io(err) => write!(f, "Unable to read {}: {}", SETTINGS_FILE, err),
+            SettingsError::Parse(err) => write!(f, "Invalid {}: {}", SETTINGS_FILE, err),
+        }
+    }
+}
+
+/// Options that aren't key bindings, every one of them may be left out of the file
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub save_encoding: SaveEncoding,
+    pub compress_saves: bool,
+    /// Turns between autosaves, zero leaves only the autosave on every level change
+    pub autosave_turns: u32,
+    /// The mode new runs are played in, a run keeps its mode for good
+    pub game_mode: GameMode,
+    /// Writes the seed and every action of new runs to the recordings folder, for replaying them
+    pub record_runs: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            save_encoding: SaveEncoding::default(),
+            compress_saves: false,
+            autosave_turns: DEFAULT_AUTOSAVE_TURNS,
+            game_mode: GameMode::default(),
+            record_runs: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings file, a missing file means the default settings
+    pub fn load() -> Result<Self, SettingsError> {
+        let data = match fs::read_to_string(SETTINGS_FILE) {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(SettingsError::Io(err)),
+        };
+        serde_json::from_str(&data).map_err(SettingsError::Parse)
+    }
+
+    pub fn save_format(&self) -> SaveFormat {
+        SaveFormat {
+            encoding: self.save_encoding,
+            compressed: self.compress_saves,
+        }
+    }
+}
@@ -0,0 +1,729 @@
+use specs::{Entity, Join, RunNow, World, WorldExt};
+
+use crate::{
+    components::{CombatStats, Equipped, GameMode, InBackpack, Position, Viewshed},
+    game_loop,
+    gamelog::GameLog,
+    level,
+    map::map::{Map, TileType},
+    player::{self, PlayerAction},
+    replay,
+    spawn::{random_table::SpawnEntity, spawner},
+    state::RunState,
+    systems::{map_indexing_system::MapIndexingSystem, Systems},
+};
+
+// Milliseconds every simulated turn takes, for the entities with a lifetime
+const TURN_MS: u32 = 100;
+
+// The eight steps a player can take, clockwise from north
+const DIRECTIONS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+/// A run played without a window, through the same turn phases as the game
+///
+/// It never autosaves, records or adds to the history, so tests can step it as they like
+pub struct Simulation {
+    pub ecs: World,
+    systems: Systems,
+    phase: RunState,
+}
+
+impl Simulation {
+    /// A new run from the seed, waiting for the player's first action
+    pub fn new(seed: u64) -> Self {
+        let mut ecs = replay::replay_world();
+        level::start_run(&mut ecs, seed, GameMode::Casual);
+        Self::from_world(ecs)
+    }
+
+    /// Takes over a world whose run was just started, and plays up to the first action
+    pub fn from_world(ecs: World) -> Self {
+        let mut simulation = Self {
+            ecs,
+            systems: Systems::new(),
+            phase: RunState::PreRun,
+        };
+        simulation.run_until_input();
+        simulation
+    }
+
+    pub fn phase(&self) -> RunState {
+        self.phase
+    }
+
+    /// The player acts, or stumbles about when confused, then the turn plays out
+    ///
+    /// Returns where the run stopped, waiting for the next action or dead
+    pub fn act(&mut self, action: PlayerAction) -> RunState {
+        if let RunState::Dead { .. } = self.phase {
+            return self.phase;
+        }
+        self.ecs.insert(self.phase);
+        self.systems.run_during_pause(&mut self.ecs);
+        self.phase = if player::confused_turn(&mut self.ecs) {
+            RunState::PlayerTurn
+        } else {
+            match player::perform(&mut self.ecs, action) {
+                RunState::PlayerTurn => RunState::PlayerTurn,
+                RunState::NextLevel => RunState::NextLevel,
+                _ => RunState::AwaitingInput,
+            }
+        };
+        self.run_until_input();
+        self.phase
+    }
+
+    /// Skips the given number of turns, or fewer when the player dies
+    pub fn wait(&mut self, turns: u32) -> RunState {
+        for _ in 0..turns {
+            self.act(PlayerAction::SkipTurn);
+        }
+        self.phase
+    }
+
+    // Runs the turn phases until the player has to act again
+    fn run_until_input(&mut self) {
+        loop {
+            self.phase = match self.phase {
+                RunState::PreRun | RunState::PlayerTurn | RunState::MonsterTurn => {
+                    game_loop::run_turn_phase(&mut self.ecs, &mut self.systems, self.phase, TURN_MS)
+                }
+                RunState::NextLevel => {
+                    level::next_level(&mut self.ecs);
+                    RunState::PreRun
+                }
+                _ => return,
+            }
+        }
+    }
+
+    pub fn player(&self) -> Entity {
+        *self.ecs.fetch::<Entity>()
+    }
+
+    pub fn player_position(&self) -> Position {
+        *self.ecs.fetch::<Position>()
+    }
+
+    pub fn player_stats(&self) -> CombatStats {
+        self.ecs
+            .read_storage::<CombatStats>()
+            .get(self.player())
+            .cloned()
+            .unwrap()
+    }
+
+    pub fn set_player_hp(&mut self, hp: u32) {
+        let player = self.player();
+        self.ecs
+            .write_storage::<CombatStats>()
+            .get_mut(player)
+            .unwrap()
+            .hp = hp;
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.ecs.fetch::<Map>().depth
+    }
+
+    pub fn turn(&self) -> u32 {
+        self.ecs.fetch::<GameLog>().turn
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.ecs.entities().is_alive(entity)
+    }
+
+    /// The text of the latest message in the log
+    pub fn last_message(&self) -> String {
+        let log = self.ecs.fetch::<GameLog>();
+        log.entries().last().map_or(String::new(), |entry| {
+            entry
+                .fragments
+                .iter()
+                .map(|fragment| fragment.text.as_str())
+                .collect()
+        })
+    }
+
+    /// Removes everything from the level but the player and what they carry
+    pub fn clear_level(&mut self) {
+        let player = self.player();
+        let doomed: Vec<Entity> = {
+            let entities = self.ecs.entities();
+            let backpacks = self.ecs.read_storage::<InBackpack>();
+            let equipped = self.ecs.read_storage::<Equipped>();
+            (&entities)
+                .join()
+                .filter(|entity| *entity != player)
+                .filter(|entity| {
+                    backpacks
+                        .get(*entity)
+                        .is_none_or(|item| item.owner != player)
+                })
+                .filter(|entity| {
+                    equipped
+                        .get(*entity)
+                        .is_none_or(|item| item.owner != player)
+                })
+                .collect()
+        };
+        self.ecs
+            .delete_entities(&doomed)
+            .expect("Unable to clear the level");
+        self.ecs.maintain();
+        self.index_map();
+    }
+
+    /// A step from the player onto open floor, the first one clockwise from north
+    pub fn open_direction(&self) -> (i32, i32) {
+        self.open_line(1)
+    }
+
+    /// A direction with open floor for the given number of steps from the player
+    pub fn open_line(&self, steps: i32) -> (i32, i32) {
+        let map = self.ecs.fetch::<Map>();
+        let pos = self.player_position();
+        DIRECTIONS
+            .iter()
+            .copied()
+            .find(|(delta_x, delta_y)| {
+                (1..=steps).all(|step| {
+                    let idx = map.index_from_xy(
+                        (pos.x as i32 + delta_x * step) as u16,
+                        (pos.y as i32 + delta_y * step) as u16,
+                    );
+                    map.tiles[idx] == TileType::Floor
+                        && !map.blocked[idx]
+                        && map.tile_content[idx].is_empty()
+                })
+            })
+            .expect("The player is walled in")
+    }
+
+    /// Spawns a plain entity of the kind a step away from the player
+    pub fn spawn_next_to_player(&mut self, kind: SpawnEntity, delta: (i32, i32)) -> Entity {
+        let pos = self.player_position();
+        let x = (pos.x as i32 + delta.0) as u16;
+        let y = (pos.y as i32 + delta.1) as u16;
+        let depth = self.depth();
+        let entity = spawner::spawn_kind(&mut self.ecs, kind, x, y, depth);
+        self.index_map();
+        entity
+    }
+
+    /// Spawns a plain entity of the kind straight into the player's backpack
+    pub fn give(&mut self, kind: SpawnEntity) -> Entity {
+        let player = self.player();
+        let depth = self.depth();
+        let item = spawner::spawn_kind(&mut self.ecs, kind, 0, 0, depth);
+        self.ecs.write_storage::<Position>().remove(item);
+        self.ecs
+            .write_storage::<InBackpack>()
+            .insert(item, InBackpack { owner: player })
+            .expect("Unable to put the item in the backpack");
+        item
+    }
+
+    /// Moves the player to the tile, as if they had always stood there
+    pub fn place_player(&mut self, x: u16, y: u16) {
+        let player = self.player();
+        self.ecs.insert(Position { x, y });
+        self.ecs
+            .write_storage::<Position>()
+            .insert(player, Position { x, y })
+            .expect("Unable to move the player");
+        if let Some(viewshed) = self.ecs.write_storage::<Viewshed>().get_mut(player) {
+            viewshed.dirty = true;
+        }
+        self.index_map();
+    }
+
+    /// The first tile of the type on the level, top row first
+    pub fn find_tile(&self, tile: TileType) -> Option<(u16, u16)> {
+        let map = self.ecs.fetch::<Map>();
+        map.tiles
+            .iter()
+            .position(|t| *t == tile)
+            .map(|idx| map.xy_from_index(&idx))
+    }
+
+    // Entities added or moved by a test are only known to the map once it's indexed again
+    fn index_map(&mut self) {
+        MapIndexingSystem {}.run_now(&self.ecs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{
+        self, EntityMoved, EquipmentSlot, Hidden, HungerClock, HungerState, Name, RunStats,
+    };
+    use crate::settings::Settings;
+    use crate::systems::autosave::{self, Autosave};
+    use crate::systems::{save_slots, saveload_system};
+    use rltk::RandomNumberGenerator;
+
+    // The level every test starts on
+    const SEED: u64 = 7;
+
+    // A fresh run on an empty level, so nothing but the test's own entities gets in the way
+    fn empty_level() -> Simulation {
+        let mut simulation = Simulation::new(SEED);
+        simulation.clear_level();
+        simulation
+    }
+
+    fn run_stats(simulation: &Simulation) -> RunStats {
+        simulation
+            .ecs
+            .read_storage::<RunStats>()
+            .get(simulation.player())
+            .cloned()
+            .unwrap()
+    }
+
+    fn hunger(simulation: &Simulation) -> HungerClock {
+        *simulation
+            .ecs
+            .read_storage::<HungerClock>()
+            .get(simulation.player())
+            .unwrap()
+    }
+
+    fn set_hunger(simulation: &mut Simulation, state: HungerState, duration: u32) {
+        let player = simulation.player();
+        simulation
+            .ecs
+            .write_storage::<HungerClock>()
+            .insert(player, HungerClock { state, duration })
+            .unwrap();
+    }
+
+    fn marker(simulation: &Simulation, entity: Entity) -> u64 {
+        player::marker(&simulation.ecs, entity)
+    }
+
+    #[test]
+    fn same_seed_same_level() {
+        let first = Simulation::new(SEED);
+        let second = Simulation::new(SEED);
+        assert_eq!(first.player_position(), second.player_position());
+        assert_eq!(
+            first.ecs.fetch::<Map>().ascii_rows(),
+            second.ecs.fetch::<Map>().ascii_rows()
+        );
+        assert!(matches!(first.phase(), RunState::AwaitingInput));
+    }
+
+    #[test]
+    fn moving_takes_a_turn() {
+        let mut simulation = empty_level();
+        let start = simulation.player_position();
+        let (delta_x, delta_y) = simulation.open_direction();
+
+        simulation.act(PlayerAction::Move { delta_x, delta_y });
+
+        let end = simulation.player_position();
+        assert_eq!(end.x as i32, start.x as i32 + delta_x);
+        assert_eq!(end.y as i32, start.y as i32 + delta_y);
+        assert_eq!(simulation.turn(), 1);
+        assert!(matches!(simulation.phase(), RunState::AwaitingInput));
+    }
+
+    #[test]
+    fn attacking_kills_a_goblin() {
+        let mut simulation = empty_level();
+        let direction = simulation.open_direction();
+        let goblin = simulation.spawn_next_to_player(SpawnEntity::Goblin, direction);
+
+        // A goblin has 16 hp and 1 defense, the player hits for 5
+        for _ in 0..4 {
+            assert!(simulation.is_alive(goblin));
+            simulation.act(PlayerAction::Move {
+                delta_x: direction.0,
+                delta_y: direction.1,
+            });
+        }
+
+        assert!(!simulation.is_alive(goblin));
+        assert_eq!(run_stats(&simulation).kills.get("Goblin"), Some(&1));
+        let corpses = simulation.ecs.read_storage::<Name>();
+        assert!((&corpses).join().any(|name| name.name.contains("Goblin")));
+    }
+
+    #[test]
+    fn monsters_fight_back() {
+        let mut simulation = empty_level();
+        let direction = simulation.open_direction();
+        simulation.spawn_next_to_player(SpawnEntity::Orc, direction);
+        simulation.set_player_hp(3);
+
+        // An orc hits for 4 against the player's 2 defense
+        simulation.wait(1);
+        assert_eq!(simulation.player_stats().hp, 1);
+        simulation.wait(1);
+
+        assert!(matches!(simulation.phase(), RunState::Dead { scroll: 0 }));
+        assert_eq!(run_stats(&simulation).killed_by.as_deref(), Some("Orc"));
+        // A dead player doesn't act any more
+        let turn = simulation.turn();
+        simulation.wait(5);
+        assert_eq!(simulation.turn(), turn);
+    }
+
+    #[test]
+    fn monsters_reach_a_player_standing_on_a_trap() {
+        let mut simulation = empty_level();
+        let trap = simulation.spawn_next_to_player(SpawnEntity::SpikeTrap, (0, 0));
+        simulation.ecs.write_storage::<Hidden>().remove(trap);
+        let (delta_x, delta_y) = simulation.open_line(2);
+        let goblin =
+            simulation.spawn_next_to_player(SpawnEntity::Goblin, (delta_x * 2, delta_y * 2));
+
+        simulation.wait(1);
+
+        let player = simulation.player_position();
+        let goblin_pos = *simulation
+            .ecs
+            .read_storage::<Position>()
+            .get(goblin)
+            .unwrap();
+        assert!(player.x.abs_diff(goblin_pos.x) <= 1 && player.y.abs_diff(goblin_pos.y) <= 1);
+    }
+
+    #[test]
+    fn health_potion_heals_and_is_used_up() {
+        let mut simulation = empty_level();
+        let potion = simulation.give(SpawnEntity::HealthPotion);
+        simulation.set_player_hp(10);
+
+        let item = marker(&simulation, potion);
+        simulation.act(PlayerAction::UseItem { item, target: None });
+
+        assert_eq!(simulation.player_stats().hp, 18);
+        assert!(!simulation.is_alive(potion));
+        // The potion is gone, so using it again does nothing and takes no turn
+        let turn = simulation.turn();
+        simulation.act(PlayerAction::UseItem { item, target: None });
+        assert_eq!(simulation.turn(), turn);
+        assert_eq!(simulation.last_message(), "That item is gone.");
+    }
+
+    #[test]
+    fn dropped_items_can_be_picked_up() {
+        let mut simulation = empty_level();
+        let dagger = simulation.give(SpawnEntity::Dagger);
+        let item = marker(&simulation, dagger);
+
+        simulation.act(PlayerAction::DropItem { item });
+        assert_eq!(
+            simulation.ecs.read_storage::<Position>().get(dagger),
+            Some(&simulation.player_position())
+        );
+
+        simulation.act(PlayerAction::Pickup);
+        assert!(simulation
+            .ecs
+            .read_storage::<Position>()
+            .get(dagger)
+            .is_none());
+        assert!(simulation.ecs.read_storage::<InBackpack>().contains(dagger));
+    }
+
+    #[test]
+    fn spike_trap_hurts_every_time() {
+        let mut simulation = empty_level();
+        let (delta_x, delta_y) = simulation.open_direction();
+        let trap = simulation.spawn_next_to_player(SpawnEntity::SpikeTrap, (delta_x, delta_y));
+
+        simulation.act(PlayerAction::Move { delta_x, delta_y });
+        assert_eq!(simulation.player_stats().hp, 27);
+        assert!(!simulation.ecs.read_storage::<Hidden>().contains(trap));
+
+        simulation.act(PlayerAction::Move {
+            delta_x: -delta_x,
+            delta_y: -delta_y,
+        });
+        simulation.act(PlayerAction::Move { delta_x, delta_y });
+        assert_eq!(simulation.player_stats().hp, 24);
+        assert!(simulation.is_alive(trap));
+    }
+
+    #[test]
+    fn bear_trap_springs_once() {
+        let mut simulation = empty_level();
+        let (delta_x, delta_y) = simulation.open_direction();
+        let trap = simulation.spawn_next_to_player(SpawnEntity::BearTrap, (delta_x, delta_y));
+
+        simulation.act(PlayerAction::Move { delta_x, delta_y });
+
+        assert_eq!(simulation.player_stats().hp, 24);
+        assert!(!simulation.is_alive(trap));
+    }
+
+    #[test]
+    fn disarming_removes_the_trap() {
+        let mut simulation = empty_level();
+        let direction = simulation.open_direction();
+        let trap = simulation.spawn_next_to_player(SpawnEntity::SpikeTrap, direction);
+        simulation.ecs.write_storage::<Hidden>().remove(trap);
+
+        // Disarming can fail and spring the trap, but it goes in the end
+        for _ in 0..20 {
+            if !simulation.is_alive(trap) {
+                break;
+            }
+            simulation.act(PlayerAction::DisarmTrap);
+        }
+        assert!(!simulation.is_alive(trap));
+    }
+
+    #[test]
+    fn a_botched_disarm_springs_the_trap() {
+        let mut simulation = empty_level();
+        let direction = simulation.open_direction();
+        let trap = simulation.spawn_next_to_player(SpawnEntity::BearTrap, direction);
+        simulation.ecs.write_storage::<Hidden>().remove(trap);
+        // Nothing else rolls before the disarm, so its roll is the generator's first
+        let seed = (0..)
+            .find(|seed| {
+                RandomNumberGenerator::seeded(*seed).roll_dice(1, player::DISARM_CHANCE) == 1
+            })
+            .unwrap();
+        simulation.ecs.insert(RandomNumberGenerator::seeded(seed));
+        let start = simulation.player_position();
+
+        simulation.act(PlayerAction::DisarmTrap);
+
+        // The trap bites the player where they stand, once, like when it is stepped on
+        assert_eq!(simulation.player_stats().hp, 24);
+        assert_eq!(simulation.player_position(), start);
+        assert!(!simulation.is_alive(trap));
+        assert_eq!(simulation.last_message(), "Bear Trap triggers!");
+    }
+
+    #[test]
+    fn monsters_falling_into_a_pit_drop_their_gear() {
+        let mut simulation = empty_level();
+        let direction = simulation.open_direction();
+        simulation.spawn_next_to_player(SpawnEntity::PitTrap, direction);
+        let goblin = simulation.spawn_next_to_player(SpawnEntity::Goblin, direction);
+        let dagger = simulation.spawn_next_to_player(SpawnEntity::Dagger, direction);
+        simulation.ecs.write_storage::<Position>().remove(dagger);
+        simulation
+            .ecs
+            .write_storage::<Equipped>()
+            .insert(
+                dagger,
+                Equipped {
+                    owner: goblin,
+                    slot: EquipmentSlot::Melee,
+                },
+            )
+            .unwrap();
+        // As if the goblin had just stepped onto the pit
+        simulation
+            .ecs
+            .write_storage::<EntityMoved>()
+            .insert(goblin, EntityMoved {})
+            .unwrap();
+
+        simulation.wait(1);
+
+        assert!(!simulation.is_alive(goblin));
+        assert!(simulation.is_alive(dagger));
+        assert!(!simulation.ecs.read_storage::<Equipped>().contains(dagger));
+        assert!(simulation.ecs.read_storage::<Position>().contains(dagger));
+        assert!(saveload_system::snapshot(&mut simulation.ecs).is_ok());
+    }
+
+    #[test]
+    fn the_dead_leave_nothing_owned_for_a_snapshot() {
+        let mut simulation = empty_level();
+        let direction = simulation.open_direction();
+        let goblin = simulation.spawn_next_to_player(SpawnEntity::Goblin, direction);
+        let dagger = simulation.spawn_next_to_player(SpawnEntity::Dagger, direction);
+        let potion = simulation.spawn_next_to_player(SpawnEntity::HealthPotion, direction);
+        simulation.ecs.write_storage::<Position>().remove(dagger);
+        simulation.ecs.write_storage::<Position>().remove(potion);
+        simulation
+            .ecs
+            .write_storage::<Equipped>()
+            .insert(
+                dagger,
+                Equipped {
+                    owner: goblin,
+                    slot: EquipmentSlot::Melee,
+                },
+            )
+            .unwrap();
+        simulation
+            .ecs
+            .write_storage::<InBackpack>()
+            .insert(potion, InBackpack { owner: goblin })
+            .unwrap();
+        simulation
+            .ecs
+            .write_storage::<CombatStats>()
+            .get_mut(goblin)
+            .unwrap()
+            .hp = 0;
+
+        // Only the monster turn the goblin dies in, the snapshot comes before the next one
+        game_loop::run_turn_phase(
+            &mut simulation.ecs,
+            &mut simulation.systems,
+            RunState::MonsterTurn,
+            TURN_MS,
+        );
+
+        assert!(!simulation.is_alive(goblin));
+        for item in [dagger, potion] {
+            assert!(!simulation.ecs.read_storage::<Equipped>().contains(item));
+            assert!(!simulation.ecs.read_storage::<InBackpack>().contains(item));
+            assert!(simulation.ecs.read_storage::<Position>().contains(item));
+        }
+        assert!(saveload_system::snapshot(&mut simulation.ecs).is_ok());
+    }
+
+    #[test]
+    fn autosave_on_the_turn_a_monster_dies_loads() {
+        let mut simulation = empty_level();
+        simulation.ecs.insert(Settings {
+            autosave_turns: 1,
+            ..Settings::default()
+        });
+        simulation.ecs.insert(Autosave::default());
+        let (delta_x, delta_y) = simulation.open_line(2);
+        // A goblin on its last legs, walking up to the player over a trap it doesn't know about
+        let trap = simulation.spawn_next_to_player(SpawnEntity::SpikeTrap, (delta_x, delta_y));
+        simulation
+            .ecs
+            .write_storage::<Hidden>()
+            .insert(trap, Hidden {})
+            .unwrap();
+        let goblin =
+            simulation.spawn_next_to_player(SpawnEntity::Goblin, (delta_x * 2, delta_y * 2));
+        let dagger = simulation.spawn_next_to_player(SpawnEntity::Dagger, (delta_x, delta_y));
+        simulation.ecs.write_storage::<Position>().remove(dagger);
+        simulation
+            .ecs
+            .write_storage::<Equipped>()
+            .insert(
+                dagger,
+                Equipped {
+                    owner: goblin,
+                    slot: EquipmentSlot::Melee,
+                },
+            )
+            .unwrap();
+        simulation
+            .ecs
+            .write_storage::<CombatStats>()
+            .get_mut(goblin)
+            .unwrap()
+            .hp = 1;
+        let dagger_marker = marker(&simulation, dagger);
+        let trap_pos = *simulation.ecs.read_storage::<Position>().get(trap).unwrap();
+
+        let _slot = autosave::SLOT_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        save_slots::delete_slot(autosave::AUTOSAVE_SLOT).unwrap();
+        simulation.wait(1);
+        assert!(!simulation.is_alive(goblin));
+        autosave::wait_for_writer(&mut simulation.ecs);
+
+        // The goblin's dagger was saved where it fell, and the goblin not at all
+        let mut loaded = components::new_world();
+        saveload_system::load_game(&mut loaded, autosave::AUTOSAVE_SLOT).unwrap();
+        let dagger = player::marked_entity(&loaded, dagger_marker).unwrap();
+        assert!(!loaded.read_storage::<Equipped>().contains(dagger));
+        assert_eq!(
+            loaded.read_storage::<Position>().get(dagger),
+            Some(&trap_pos)
+        );
+        assert!(!loaded
+            .read_storage::<Name>()
+            .join()
+            .any(|name| name.name == "Goblin"));
+    }
+
+    #[test]
+    fn hunger_sets_in_and_food_ends_it() {
+        let mut simulation = empty_level();
+        set_hunger(&mut simulation, HungerState::Normal, 2);
+
+        simulation.wait(2);
+        assert!(hunger(&simulation).state == HungerState::Hungry);
+        assert_eq!(simulation.last_message(), "You are hungry.");
+
+        // Hungry players don't heal by waiting
+        simulation.set_player_hp(20);
+        simulation.wait(3);
+        assert_eq!(simulation.player_stats().hp, 20);
+
+        // Every meal is a step back from starving to well fed
+        for state in [HungerState::Normal, HungerState::WellFed] {
+            let rations = simulation.give(SpawnEntity::Ration);
+            let item = marker(&simulation, rations);
+            simulation.act(PlayerAction::UseItem { item, target: None });
+            assert!(hunger(&simulation).state == state);
+            assert!(!simulation.is_alive(rations));
+        }
+    }
+
+    #[test]
+    fn starving_hurts_every_turn() {
+        let mut simulation = empty_level();
+        set_hunger(&mut simulation, HungerState::Starving, 0);
+
+        simulation.wait(4);
+
+        assert_eq!(simulation.player_stats().hp, 26);
+    }
+
+    #[test]
+    fn stairs_lead_down() {
+        let mut simulation = empty_level();
+        let potion = simulation.give(SpawnEntity::HealthPotion);
+
+        simulation.act(PlayerAction::Descend);
+        assert_eq!(simulation.depth(), 1);
+        assert_eq!(simulation.last_message(), "There is no way down from here.");
+
+        let (x, y) = simulation.find_tile(TileType::DownStairs).unwrap();
+        simulation.place_player(x, y);
+        simulation.act(PlayerAction::Descend);
+
+        assert_eq!(simulation.depth(), 2);
+        assert_eq!(run_stats(&simulation).deepest_depth, 2);
+        assert!(matches!(simulation.phase(), RunState::AwaitingInput));
+        // The player and what they carry come along
+        assert!(simulation.is_alive(potion));
+        assert!(simulation.ecs.read_storage::<InBackpack>().contains(potion));
+    }
+
+    #[test]
+    fn pit_trap_drops_to_the_next_level() {
+        let mut simulation = empty_level();
+        let (delta_x, delta_y) = simulation.open_direction();
+        simulation.spawn_next_to_player(SpawnEntity::PitTrap, (delta_x, delta_y));
+
+        simulation.act(PlayerAction::Move { delta_x, delta_y });
+
+        assert_eq!(simulation.depth(), 2);
+        assert_eq!(simulation.player_stats().hp, 28);
+    }
+}
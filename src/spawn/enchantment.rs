@@ -0,0 +1,213 @@
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+use crate::components::{
+    BaseValue, DefenseBonus, EquipmentSlot, Equippable, LifeRegen, MeleePowerBonus, Name, Rarity,
+    RarityTier, Renderable, Resistance, VisionBonus,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Affix {
+    Power,
+    Defense,
+    Resistance,
+    Regeneration,
+    Vision,
+}
+
+impl Affix {
+    fn suffix(&self) -> &'static str {
+        match self {
+            Affix::Power => "Might",
+            Affix::Defense => "Warding",
+            Affix::Resistance => "Resistance",
+            Affix::Regeneration => "Regeneration",
+            Affix::Vision => "Far Sight",
+        }
+    }
+}
+
+struct AffixTable {
+    entries: Vec<(Affix, i32)>,
+}
+
+impl AffixTable {
+    fn for_slot(slot: EquipmentSlot, map_depth: u32) -> Self {
+        let depth = map_depth as i32;
+        let table = AffixTable {
+            entries: Vec::new(),
+        };
+        match slot {
+            EquipmentSlot::Melee => table
+                .add(Affix::Power, 10)
+                .add(Affix::Defense, 3)
+                .add(Affix::Resistance, depth)
+                .add(Affix::Regeneration, depth - 1)
+                .add(Affix::Vision, 3),
+            EquipmentSlot::Shield => table
+                .add(Affix::Power, 3)
+                .add(Affix::Defense, 10)
+                .add(Affix::Resistance, 2 + depth)
+                .add(Affix::Regeneration, depth - 1)
+                .add(Affix::Vision, 3),
+        }
+    }
+
+    fn add(mut self, affix: Affix, weight: i32) -> Self {
+        if weight > 0 {
+            self.entries.push((affix, weight));
+        }
+        self
+    }
+
+    // Removes the rolled affix from the table, so the same affix can't be rolled twice
+    fn take(&mut self, rng: &mut RandomNumberGenerator) -> Option<Affix> {
+        let total_weight: i32 = self.entries.iter().map(|(_, weight)| weight).sum();
+        if total_weight == 0 {
+            return None;
+        }
+        let mut roll = rng.roll_dice(1, total_weight) - 1;
+        for (index, (affix, weight)) in self.entries.iter().enumerate() {
+            if roll < *weight {
+                let affix = *affix;
+                self.entries.remove(index);
+                return Some(affix);
+            }
+            roll -= weight;
+        }
+        None
+    }
+}
+
+fn roll_tier(rng: &mut RandomNumberGenerator, map_depth: u32) -> RarityTier {
+    let rare_chance = i32::min(25, 2 + map_depth as i32 * 2);
+    let magic_chance = i32::min(50, 15 + map_depth as i32 * 4);
+    let roll = rng.roll_dice(1, 100);
+    if roll <= rare_chance {
+        RarityTier::Rare
+    } else if roll <= rare_chance + magic_chance {
+        RarityTier::Magic
+    } else {
+        RarityTier::Common
+    }
+}
+
+/// Rolls a rarity tier for a freshly spawned piece of equipment and applies random affixes
+pub fn enchant(ecs: &mut World, item: Entity, map_depth: u32) {
+    let slot = match ecs.read_storage::<Equippable>().get(item) {
+        None => return,
+        Some(equippable) => equippable.slot,
+    };
+
+    let mut affixes = Vec::new();
+    let tier = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        let tier = roll_tier(&mut rng, map_depth);
+        let affix_count = match tier {
+            RarityTier::Common => 0,
+            RarityTier::Magic => 1,
+            RarityTier::Rare => 1 + rng.roll_dice(1, 2),
+        };
+        let mut table = AffixTable::for_slot(slot, map_depth);
+        for _ in 0..affix_count {
+            if let Some(affix) = table.take(&mut rng) {
+                affixes.push(affix);
+            }
+        }
+        tier
+    };
+
+    ecs.write_storage::<Rarity>()
+        .insert(item, Rarity { tier })
+        .expect("Unable to insert rarity");
+    if affixes.is_empty() {
+        return;
+    }
+
+    for affix in affixes.iter() {
+        apply_affix(ecs, item, *affix, map_depth);
+    }
+
+    if let Some(renderable) = ecs.write_storage::<Renderable>().get_mut(item) {
+        renderable.fg = tier.color();
+    }
+    if let Some(value) = ecs.write_storage::<BaseValue>().get_mut(item) {
+        value.value *= match tier {
+            RarityTier::Common => 1,
+            RarityTier::Magic => 2,
+            RarityTier::Rare => 4,
+        };
+    }
+    if let Some(name) = ecs.write_storage::<Name>().get_mut(item) {
+        name.name = format!("{} of {}", name.name, affix_suffixes(&affixes));
+    }
+}
+
+fn apply_affix(ecs: &mut World, item: Entity, affix: Affix, map_depth: u32) {
+    let bonus = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        rng.roll_dice(1, 1 + map_depth as i32 / 2)
+    };
+
+    match affix {
+        Affix::Power => {
+            let mut storage = ecs.write_storage::<MeleePowerBonus>();
+            match storage.get_mut(item) {
+                Some(power) => power.power += bonus,
+                None => {
+                    storage
+                        .insert(item, MeleePowerBonus { power: bonus })
+                        .expect("Unable to insert power bonus");
+                }
+            }
+        }
+        Affix::Defense => {
+            let mut storage = ecs.write_storage::<DefenseBonus>();
+            match storage.get_mut(item) {
+                Some(defense) => defense.defense += bonus,
+                None => {
+                    storage
+                        .insert(item, DefenseBonus { defense: bonus })
+                        .expect("Unable to insert defense bonus");
+                }
+            }
+        }
+        Affix::Resistance => {
+            let percent = u32::min(50, 5 * (bonus as u32 + 1));
+            ecs.write_storage::<Resistance>()
+                .insert(item, Resistance { percent })
+                .expect("Unable to insert resistance");
+        }
+        Affix::Regeneration => {
+            let interval = u32::max(3, 12 - u32::min(9, map_depth + bonus as u32));
+            ecs.write_storage::<LifeRegen>()
+                .insert(
+                    item,
+                    LifeRegen {
+                        interval,
+                        countdown: interval,
+                    },
+                )
+                .expect("Unable to insert life regen");
+        }
+        Affix::Vision => {
+            ecs.write_storage::<VisionBonus>()
+                .insert(
+                    item,
+                    VisionBonus {
+                        range: u16::min(3, bonus as u16),
+                    },
+                )
+                .expect("Unable to insert vision bonus");
+        }
+    }
+}
+
+fn affix_suffixes(affixes: &[Affix]) -> String {
+    let suffixes: Vec<&str> = affixes.iter().map(|affix| affix.suffix()).collect();
+    match suffixes.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+    }
+}
@@ -1,2 +1,3 @@
+pub mod enchantment;
 pub mod random_table;
 pub mod spawner;
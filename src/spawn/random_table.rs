@@ -14,6 +14,37 @@ pub enum SpawnEntity {
     TowerShield,
     Ration,
     BearTrap,
+    SpikeTrap,
+    TeleportTrap,
+    ConfusionTrap,
+    AlarmTrap,
+    PitTrap,
+    FireTrap,
+    Gold,
+}
+
+impl SpawnEntity {
+    pub const ALL: [SpawnEntity; 19] = [
+        SpawnEntity::Goblin,
+        SpawnEntity::Orc,
+        SpawnEntity::HealthPotion,
+        SpawnEntity::FireballScroll,
+        SpawnEntity::ConfusionScroll,
+        SpawnEntity::MagicMissileScroll,
+        SpawnEntity::Dagger,
+        SpawnEntity::Longsword,
+        SpawnEntity::Shield,
+        SpawnEntity::TowerShield,
+        SpawnEntity::Ration,
+        SpawnEntity::BearTrap,
+        SpawnEntity::SpikeTrap,
+        SpawnEntity::TeleportTrap,
+        SpawnEntity::ConfusionTrap,
+        SpawnEntity::AlarmTrap,
+        SpawnEntity::PitTrap,
+        SpawnEntity::FireTrap,
+        SpawnEntity::Gold,
+    ];
 }
 
 struct RandomEntry {
@@ -47,6 +78,46 @@ impl RandomTable {
             .add(SpawnEntity::TowerShield, map_depth as i32 - 1)
             .add(SpawnEntity::Ration, 8)
             .add(SpawnEntity::BearTrap, 2)
+            .add(SpawnEntity::SpikeTrap, map_depth as i32 - 1)
+            .add(SpawnEntity::TeleportTrap, 1)
+            .add(SpawnEntity::ConfusionTrap, map_depth as i32 - 1)
+            .add(SpawnEntity::AlarmTrap, map_depth as i32 - 2)
+            .add(SpawnEntity::PitTrap, map_depth as i32 - 1)
+            .add(SpawnEntity::FireTrap, map_depth as i32 - 2)
+            .add(SpawnEntity::Gold, 4 + map_depth as i32)
+    }
+
+    pub fn generate_vendor_table(map_depth: u32) -> Self {
+        RandomTable::new()
+            .add(SpawnEntity::HealthPotion, 10)
+            .add(SpawnEntity::FireballScroll, map_depth as i32)
+            .add(SpawnEntity::ConfusionScroll, 1 + map_depth as i32)
+            .add(SpawnEntity::MagicMissileScroll, 4)
+            .add(SpawnEntity::Dagger, 4)
+            .add(SpawnEntity::Longsword, 2 * map_depth as i32 - 1)
+            .add(SpawnEntity::Shield, 4)
+            .add(SpawnEntity::TowerShield, 2 * map_depth as i32 - 1)
+            .add(SpawnEntity::Ration, 8)
+    }
+
+    pub fn goblin_loot_table(map_depth: u32) -> Self {
+        RandomTable::new()
+            .add(SpawnEntity::HealthPotion, 4)
+            .add(SpawnEntity::MagicMissileScroll, 2)
+            .add(SpawnEntity::Dagger, 3)
+            .add(SpawnEntity::Ration, 6)
+            .add(SpawnEntity::ConfusionScroll, map_depth as i32)
+    }
+
+    pub fn orc_loot_table(map_depth: u32) -> Self {
+        RandomTable::new()
+            .add(SpawnEntity::HealthPotion, 5)
+            .add(SpawnEntity::FireballScroll, map_depth as i32)
+            .add(SpawnEntity::Dagger, 2)
+            .add(SpawnEntity::Longsword, map_depth as i32)
+            .add(SpawnEntity::Shield, 3)
+            .add(SpawnEntity::TowerShield, map_depth as i32 - 1)
+            .add(SpawnEntity::Ration, 4)
     }
 
     fn new() -> Self {
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use rltk::{RandomNumberGenerator, RGB};
 use specs::{
@@ -8,16 +8,31 @@ use specs::{
 
 use crate::{
     components::{
-        AreaOfEffect, BlocksTile, CombatStats, Confusion, Consumable, DefenseBonus, EntryTrigger,
-        EquipmentSlot, Equippable, Hidden, HungerClock, HungerState, InflictsDamage, Item,
-        MeleePowerBonus, Monster, Name, Player, Position, ProvidesFood, ProvidesHealing, Ranged,
-        Renderable, SerializeMe, SingleActivation, Viewshed,
+        Alarm, AreaOfEffect, BaseValue, BlocksTile, BlocksVisibility, CombatStats, Confusion,
+        Consumable, DefenseBonus, Description, Door, EntryTrigger, EquipmentSlot, Equippable,
+        Equipped, Freshness, GameMode, Gold, Hidden, HungerClock, HungerState, InBackpack,
+        InflictsDamage, Item, Key, LeavesCorpse, Locked, MeleePowerBonus, Monster, Name, Pit,
+        Player, Position, ProvidesFood, ProvidesHealing, Ranged, Renderable, RunStats, SerializeMe,
+        SingleActivation, Teleports, Vendor, Viewshed, Wallet,
     },
     map::map::{Map, TileType},
     rect::Rect,
 };
 
-use super::random_table::{RandomTable, SpawnEntity};
+use super::{
+    enchantment,
+    random_table::{RandomTable, SpawnEntity},
+};
+
+// One in LOCKED_DOOR_CHANCE doors is locked
+const LOCKED_DOOR_CHANCE: i32 = 8;
+
+// How many turns a corpse stays edible
+const CORPSE_FRESHNESS: u32 = 150;
+
+// Chance in percents that a monster carries an item from its loot table
+const GOBLIN_LOOT_CHANCE: i32 = 25;
+const ORC_LOOT_CHANCE: i32 = 40;
 
 pub fn player(ecs: &mut World, player_pos: Position) -> Entity {
     ecs.create_entity()
@@ -37,6 +52,9 @@ pub fn player(ecs: &mut World, player_pos: Position) -> Entity {
         .with(Name {
             name: "Player".to_string(),
         })
+        .with(Description {
+            text: "That's you, trapped deep under the ground.".to_string(),
+        })
         .with(CombatStats {
             max_hp: 30,
             hp: 30,
@@ -47,6 +65,14 @@ pub fn player(ecs: &mut World, player_pos: Position) -> Entity {
             state: HungerState::WellFed,
             duration: 20,
         })
+        .with(Wallet { gold: 0 })
+        .with(RunStats {
+            kills: BTreeMap::new(),
+            deepest_depth: 1,
+            seed: 0,
+            mode: GameMode::Casual,
+            killed_by: None,
+        })
         .marked::<SimpleMarker<SerializeMe>>()
         .build()
 }
@@ -69,7 +95,7 @@ pub fn spawn_room(ecs: &mut World, map: &Map, room: &Rect, max_entities: u16) {
 
 pub fn spawn_region(ecs: &mut World, possible_targets: &[usize], map: &Map, max_entities: u16) {
     let spawn_table = RandomTable::generate_loot_table(map.depth);
-    let mut spawn_points = HashMap::new();
+    let mut spawn_points = BTreeMap::new();
     let mut areas = Vec::from(possible_targets);
 
     {
@@ -95,17 +121,39 @@ pub fn spawn_region(ecs: &mut World, possible_targets: &[usize], map: &Map, max_
     }
 
     for (idx, entity) in spawn_points {
-        spawn_entity(ecs, entity, idx, map)
+        spawn_entity(ecs, entity, idx, map);
     }
 }
 
-fn spawn_entity(ecs: &mut World, entity: Option<SpawnEntity>, idx: usize, map: &Map) {
-    let entity = match entity {
-        None => return,
-        Some(e) => e,
-    };
+fn spawn_entity(
+    ecs: &mut World,
+    entity: Option<SpawnEntity>,
+    idx: usize,
+    map: &Map,
+) -> Option<Entity> {
+    let entity = entity?;
     let (x, y) = map.xy_from_index(&idx);
+    let spawned = spawn_kind(ecs, entity, x, y, map.depth);
     match entity {
+        SpawnEntity::Goblin => {
+            let loot_table = RandomTable::goblin_loot_table(map.depth);
+            give_loot(ecs, spawned, &loot_table, GOBLIN_LOOT_CHANCE, idx, map);
+        }
+        SpawnEntity::Orc => {
+            let loot_table = RandomTable::orc_loot_table(map.depth);
+            give_loot(ecs, spawned, &loot_table, ORC_LOOT_CHANCE, idx, map);
+        }
+        _ => {}
+    }
+    if ecs.read_storage::<Equippable>().contains(spawned) {
+        enchantment::enchant(ecs, spawned, map.depth);
+    }
+    Some(spawned)
+}
+
+/// Spawns a plain entity of the given kind, without loot or enchantments
+pub fn spawn_kind(ecs: &mut World, kind: SpawnEntity, x: u16, y: u16, depth: u32) -> Entity {
+    match kind {
         SpawnEntity::Goblin => new_goblin(ecs, x, y),
         SpawnEntity::Orc => new_orc(ecs, x, y),
         SpawnEntity::HealthPotion => health_potion(ecs, x, y),
@@ -118,18 +166,69 @@ fn spawn_entity(ecs: &mut World, entity: Option<SpawnEntity>, idx: usize, map: &
         SpawnEntity::TowerShield => tower_shield(ecs, x, y),
         SpawnEntity::Ration => rations(ecs, x, y),
         SpawnEntity::BearTrap => bear_trap(ecs, x, y),
-    };
+        SpawnEntity::SpikeTrap => spike_trap(ecs, x, y),
+        SpawnEntity::TeleportTrap => teleport_trap(ecs, x, y),
+        SpawnEntity::ConfusionTrap => confusion_trap(ecs, x, y),
+        SpawnEntity::AlarmTrap => alarm_trap(ecs, x, y),
+        SpawnEntity::PitTrap => pit_trap(ecs, x, y),
+        SpawnEntity::FireTrap => fire_trap(ecs, x, y),
+        SpawnEntity::Gold => {
+            let amount = {
+                let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+                rng.roll_dice(2, 5 + depth as i32) as u32
+            };
+            gold_pile(ecs, x, y, amount)
+        }
+    }
 }
 
-fn new_orc(ecs: &mut World, x: u16, y: u16) {
-    generic_mob(ecs, x, y, rltk::to_cp437('o'), "Orc");
+/// One of everything that can show up in the dungeon, for the legend on the help screen
+pub fn spawn_samples(ecs: &mut World) -> Vec<Entity> {
+    let mut samples = vec![player(ecs, Position { x: 0, y: 0 }), vendor(ecs, 0, 0)];
+    for kind in SpawnEntity::ALL {
+        samples.push(spawn_kind(ecs, kind, 0, 0, 1));
+    }
+    samples.push(door(ecs, 0, 0, false));
+    samples.push(door(ecs, 0, 0, true));
+    samples.push(key(ecs, 0, 0));
+    samples.push(corpse(ecs, 0, 0, "Goblin"));
+    samples
 }
 
-fn new_goblin(ecs: &mut World, x: u16, y: u16) {
-    generic_mob(ecs, x, y, rltk::to_cp437('g'), "Goblin");
+fn new_orc(ecs: &mut World, x: u16, y: u16) -> Entity {
+    generic_mob(
+        ecs,
+        x,
+        y,
+        rltk::to_cp437('o'),
+        "Orc",
+        "A brutish warrior that hits hard and often carries a weapon.",
+    )
 }
 
-fn generic_mob(ecs: &mut World, x: u16, y: u16, glyph: rltk::FontCharType, name: &str) {
+fn new_goblin(ecs: &mut World, x: u16, y: u16) -> Entity {
+    generic_mob(
+        ecs,
+        x,
+        y,
+        rltk::to_cp437('g'),
+        "Goblin",
+        "A small, sneaky creature with a taste for shiny things.",
+    )
+}
+
+fn generic_mob(
+    ecs: &mut World,
+    x: u16,
+    y: u16,
+    glyph: rltk::FontCharType,
+    name: &str,
+    description: &str,
+) -> Entity {
+    let gold = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        rng.roll_dice(1, 10) as u32
+    };
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -147,6 +246,9 @@ fn generic_mob(ecs: &mut World, x: u16, y: u16, glyph: rltk::FontCharType, name:
         .with(Name {
             name: name.to_string(),
         })
+        .with(Description {
+            text: description.to_string(),
+        })
         .with(BlocksTile {})
         .with(CombatStats {
             max_hp: 16,
@@ -154,11 +256,13 @@ fn generic_mob(ecs: &mut World, x: u16, y: u16, glyph: rltk::FontCharType, name:
             defense: 1,
             power: 4,
         })
+        .with(Wallet { gold })
+        .with(LeavesCorpse {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn health_potion(ecs: &mut World, x: u16, y: u16) {
+fn health_potion(ecs: &mut World, x: u16, y: u16) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -170,14 +274,18 @@ fn health_potion(ecs: &mut World, x: u16, y: u16) {
         .with(Name {
             name: "Health potion".to_string(),
         })
+        .with(Description {
+            text: "A small vial of red liquid that closes wounds.".to_string(),
+        })
         .with(Item {})
         .with(Consumable {})
         .with(ProvidesHealing { amount: 8 })
+        .with(BaseValue { value: 25 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn magic_missile_scroll(ecs: &mut World, x: u16, y: u16) {
+fn magic_missile_scroll(ecs: &mut World, x: u16, y: u16) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -189,15 +297,19 @@ fn magic_missile_scroll(ecs: &mut World, x: u16, y: u16) {
         .with(Name {
             name: "Magic Missile Scroll".to_string(),
         })
+        .with(Description {
+            text: "A scroll that fires a bolt of pure force at a single target.".to_string(),
+        })
         .with(Item {})
         .with(Consumable {})
         .with(Ranged { range: 6 })
         .with(InflictsDamage { damage: 8 })
+        .with(BaseValue { value: 40 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn fireball_scroll(ecs: &mut World, x: u16, y: u16) {
+fn fireball_scroll(ecs: &mut World, x: u16, y: u16) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -209,16 +321,20 @@ fn fireball_scroll(ecs: &mut World, x: u16, y: u16) {
         .with(Name {
             name: "Fireball Scroll".to_string(),
         })
+        .with(Description {
+            text: "A scroll that engulfs an area in roaring flames.".to_string(),
+        })
         .with(Item {})
         .with(Consumable {})
         .with(Ranged { range: 6 })
         .with(InflictsDamage { damage: 20 })
         .with(AreaOfEffect { radius: 3 })
+        .with(BaseValue { value: 80 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn confusion_scroll(ecs: &mut World, x: u16, y: u16) {
+fn confusion_scroll(ecs: &mut World, x: u16, y: u16) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -230,15 +346,19 @@ fn confusion_scroll(ecs: &mut World, x: u16, y: u16) {
         .with(Name {
             name: "Confusion Scroll".to_string(),
         })
+        .with(Description {
+            text: "A scroll that clouds the mind of its target.".to_string(),
+        })
         .with(Item {})
         .with(Consumable {})
         .with(Ranged { range: 6 })
         .with(Confusion { turns: 4 })
+        .with(BaseValue { value: 50 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn dagger(ecs: &mut World, x: u16, y: u16) {
+fn dagger(ecs: &mut World, x: u16, y: u16) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -250,16 +370,20 @@ fn dagger(ecs: &mut World, x: u16, y: u16) {
         .with(Name {
             name: "Dagger".to_string(),
         })
+        .with(Description {
+            text: "A short, sharp blade. Better than bare hands.".to_string(),
+        })
         .with(Item {})
         .with(Equippable {
             slot: EquipmentSlot::Melee,
         })
         .with(MeleePowerBonus { power: 2 })
+        .with(BaseValue { value: 15 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn longsword(ecs: &mut World, x: u16, y: u16) {
+fn longsword(ecs: &mut World, x: u16, y: u16) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -271,16 +395,20 @@ fn longsword(ecs: &mut World, x: u16, y: u16) {
         .with(Name {
             name: "Longsword".to_string(),
         })
+        .with(Description {
+            text: "A well balanced blade with a long reach.".to_string(),
+        })
         .with(Item {})
         .with(Equippable {
             slot: EquipmentSlot::Melee,
         })
         .with(MeleePowerBonus { power: 4 })
+        .with(BaseValue { value: 60 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn shield(ecs: &mut World, x: u16, y: u16) {
+fn shield(ecs: &mut World, x: u16, y: u16) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -292,16 +420,20 @@ fn shield(ecs: &mut World, x: u16, y: u16) {
         .with(Name {
             name: "Shield".to_string(),
         })
+        .with(Description {
+            text: "A simple wooden shield.".to_string(),
+        })
         .with(Item {})
         .with(Equippable {
             slot: EquipmentSlot::Shield,
         })
         .with(DefenseBonus { defense: 1 })
+        .with(BaseValue { value: 20 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn tower_shield(ecs: &mut World, x: u16, y: u16) {
+fn tower_shield(ecs: &mut World, x: u16, y: u16) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -311,18 +443,22 @@ fn tower_shield(ecs: &mut World, x: u16, y: u16) {
             render_order: 2,
         })
         .with(Name {
-            name: "Tower Shield {}".to_string(),
+            name: "Tower Shield".to_string(),
+        })
+        .with(Description {
+            text: "A huge shield that covers most of the body.".to_string(),
         })
         .with(Item {})
         .with(Equippable {
             slot: EquipmentSlot::Shield,
         })
         .with(DefenseBonus { defense: 3 })
+        .with(BaseValue { value: 70 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn rations(ecs: &mut World, x: u16, y: u16) {
+fn rations(ecs: &mut World, x: u16, y: u16) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -334,29 +470,403 @@ fn rations(ecs: &mut World, x: u16, y: u16) {
         .with(Name {
             name: "Rations".to_string(),
         })
+        .with(Description {
+            text: "Dried meat and hard bread. Not tasty, but filling.".to_string(),
+        })
         .with(Item {})
         .with(ProvidesFood {})
         .with(Consumable {})
+        .with(BaseValue { value: 10 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn bear_trap(ecs: &mut World, x: u16, y: u16) {
+fn bear_trap(ecs: &mut World, x: u16, y: u16) -> Entity {
+    trap(
+        ecs,
+        x,
+        y,
+        "Bear Trap",
+        "Steel jaws that snap shut on whoever steps in.",
+        RGB::named(rltk::RED),
+    )
+    .with(InflictsDamage { damage: 6 })
+    .with(SingleActivation {})
+    .marked::<SimpleMarker<SerializeMe>>()
+    .build()
+}
+
+fn spike_trap(ecs: &mut World, x: u16, y: u16) -> Entity {
+    trap(
+        ecs,
+        x,
+        y,
+        "Spike Trap",
+        "Sharp spikes spring out of the floor every time it's stepped on.",
+        RGB::named(rltk::LIGHT_GRAY),
+    )
+    .with(InflictsDamage { damage: 3 })
+    .marked::<SimpleMarker<SerializeMe>>()
+    .build()
+}
+
+fn teleport_trap(ecs: &mut World, x: u16, y: u16) -> Entity {
+    trap(
+        ecs,
+        x,
+        y,
+        "Teleport Trap",
+        "A glowing rune that flings its victim somewhere else on the level.",
+        RGB::named(rltk::CYAN),
+    )
+    .with(Teleports {})
+    .with(SingleActivation {})
+    .marked::<SimpleMarker<SerializeMe>>()
+    .build()
+}
+
+fn confusion_trap(ecs: &mut World, x: u16, y: u16) -> Entity {
+    trap(
+        ecs,
+        x,
+        y,
+        "Confusion Gas Trap",
+        "A vent that releases a cloud of dizzying gas.",
+        RGB::named(rltk::PINK),
+    )
+    .with(Confusion { turns: 4 })
+    .with(SingleActivation {})
+    .marked::<SimpleMarker<SerializeMe>>()
+    .build()
+}
+
+fn alarm_trap(ecs: &mut World, x: u16, y: u16) -> Entity {
+    trap(
+        ecs,
+        x,
+        y,
+        "Alarm Trap",
+        "A tripwire attached to a bell. Every monster will hear it.",
+        RGB::named(rltk::YELLOW),
+    )
+    .with(Alarm {})
+    .with(SingleActivation {})
+    .marked::<SimpleMarker<SerializeMe>>()
+    .build()
+}
+
+fn pit_trap(ecs: &mut World, x: u16, y: u16) -> Entity {
+    trap(
+        ecs,
+        x,
+        y,
+        "Pit Trap",
+        "A thin cover over a deep shaft to the level below.",
+        RGB::named(rltk::BROWN1),
+    )
+    .with(Pit {})
+    .with(InflictsDamage { damage: 2 })
+    .marked::<SimpleMarker<SerializeMe>>()
+    .build()
+}
+
+fn fire_trap(ecs: &mut World, x: u16, y: u16) -> Entity {
+    trap(
+        ecs,
+        x,
+        y,
+        "Fire Trap",
+        "A pressure plate that bursts into flames, burning everyone nearby.",
+        RGB::named(rltk::ORANGE),
+    )
+    .with(InflictsDamage { damage: 5 })
+    .with(AreaOfEffect { radius: 1 })
+    .marked::<SimpleMarker<SerializeMe>>()
+    .build()
+}
+
+fn trap<'a>(
+    ecs: &'a mut World,
+    x: u16,
+    y: u16,
+    name: &str,
+    description: &str,
+    fg: RGB,
+) -> EntityBuilder<'a> {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
             glyph: rltk::to_cp437('^'),
-            fg: RGB::named(rltk::RED),
+            fg,
             bg: RGB::named(rltk::BLACK),
             render_order: 2,
         })
         .with(Name {
-            name: "Bear Trap".to_string(),
+            name: name.to_string(),
+        })
+        .with(Description {
+            text: description.to_string(),
         })
         .with(Hidden {})
         .with(EntryTrigger {})
-        .with(InflictsDamage { damage: 6 })
-        .with(SingleActivation {})
+}
+
+pub fn gold_pile(ecs: &mut World, x: u16, y: u16, amount: u32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('$'),
+            fg: RGB::named(rltk::GOLD),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: format!("{} gold", amount),
+        })
+        .with(Description {
+            text: "Shiny coins. Vendors will happily take them.".to_string(),
+        })
+        .with(Item {})
+        .with(Gold { amount })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
+}
+
+/// Put doors into the room entrances. Some of them are locked, and for each locked door
+/// there's a key somewhere in the starting room.
+pub fn spawn_doors(ecs: &mut World, map: &Map, doors: &[usize], start_room: &Rect) {
+    let mut keys = 0;
+    for idx in doors.iter() {
+        let locked = {
+            let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+            rng.roll_dice(1, LOCKED_DOOR_CHANCE) == 1
+        };
+        let (x, y) = map.xy_from_index(idx);
+        door(ecs, x, y, locked);
+        if locked {
+            keys += 1;
+        }
+    }
+
+    let mut key_spots = Vec::new();
+    for y in start_room.y1 + 1..=start_room.y2 {
+        for x in start_room.x1 + 1..=start_room.x2 {
+            if map.tiles[map.index_from_xy(x, y)] == TileType::Floor {
+                key_spots.push((x, y));
+            }
+        }
+    }
+    for _ in 0..keys {
+        if key_spots.is_empty() {
+            return;
+        }
+        let (x, y) = {
+            let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+            let spot = rng.roll_dice(1, key_spots.len() as i32) as usize - 1;
+            key_spots.remove(spot)
+        };
+        key(ecs, x, y);
+    }
+}
+
+fn door(ecs: &mut World, x: u16, y: u16, locked: bool) -> Entity {
+    let builder = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('+'),
+            fg: if locked {
+                RGB::named(rltk::RED)
+            } else {
+                RGB::named(rltk::CHOCOLATE)
+            },
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: if locked { "Locked Door" } else { "Door" }.to_string(),
+        })
+        .with(Description {
+            text: if locked {
+                "A heavy door, locked tight. You need a key to open it."
+            } else {
+                "A wooden door. Closed doors block the view."
+            }
+            .to_string(),
+        })
+        .with(Door { open: false })
+        .with(BlocksVisibility {});
+    let builder = if locked {
+        builder.with(Locked {}).with(BlocksTile {})
+    } else {
+        builder
+    };
+    builder.marked::<SimpleMarker<SerializeMe>>().build()
+}
+
+fn key(ecs: &mut World, x: u16, y: u16) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('-'),
+            fg: RGB::named(rltk::GOLD),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Key".to_string(),
+        })
+        .with(Description {
+            text: "An old iron key. It should fit one of the locked doors on this level."
+                .to_string(),
+        })
+        .with(Item {})
+        .with(Key {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Place a vendor on a random free floor tile, stocked with items for the current depth
+pub fn spawn_vendor(ecs: &mut World, map: &Map, player_pos: Position) {
+    let mut free_tiles = Vec::new();
+    {
+        let positions = ecs.read_storage::<Position>();
+        for (idx, tile) in map.tiles.iter().enumerate() {
+            if *tile != TileType::Floor {
+                continue;
+            }
+            let (x, y) = map.xy_from_index(&idx);
+            if x == player_pos.x && y == player_pos.y {
+                continue;
+            }
+            if positions.join().any(|pos| pos.x == x && pos.y == y) {
+                continue;
+            }
+            free_tiles.push(idx);
+        }
+    }
+    if free_tiles.is_empty() {
+        return;
+    }
+
+    let (vendor_idx, stock_size) = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        let tile = rng.roll_dice(1, free_tiles.len() as i32) as usize - 1;
+        let stock_size = 3 + rng.roll_dice(1, 2 + map.depth as i32 / 2);
+        (free_tiles[tile], stock_size)
+    };
+    let (x, y) = map.xy_from_index(&vendor_idx);
+    let vendor = vendor(ecs, x, y);
+
+    let stock_table = RandomTable::generate_vendor_table(map.depth);
+    for _ in 0..stock_size {
+        let roll = {
+            let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+            stock_table.roll(&mut rng)
+        };
+        spawn_carried(ecs, roll, vendor, vendor_idx, map);
+    }
+}
+
+fn vendor(ecs: &mut World, x: u16, y: u16) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('@'),
+            fg: RGB::named(rltk::GOLD),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 1,
+        })
+        .with(Name {
+            name: "Vendor".to_string(),
+        })
+        .with(Description {
+            text: "A merchant who somehow makes a living down here.".to_string(),
+        })
+        .with(Vendor {})
+        .with(BlocksTile {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn give_loot(
+    ecs: &mut World,
+    monster: Entity,
+    loot_table: &RandomTable,
+    chance: i32,
+    idx: usize,
+    map: &Map,
+) {
+    let roll = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        if rng.roll_dice(1, 100) > chance {
+            return;
+        }
+        loot_table.roll(&mut rng)
+    };
+
+    // Monsters wield the weapons and shields they carry
+    let item = match spawn_carried(ecs, roll, monster, idx, map) {
+        None => return,
+        Some(item) => item,
+    };
+    let slot = match ecs.read_storage::<Equippable>().get(item) {
+        None => return,
+        Some(equippable) => equippable.slot,
+    };
+    ecs.write_storage::<InBackpack>().remove(item);
+    ecs.write_storage::<Equipped>()
+        .insert(
+            item,
+            Equipped {
+                owner: monster,
+                slot,
+            },
+        )
+        .expect("Unable to equip loot");
+}
+
+/// Spawns an item directly into the backpack of the owner
+fn spawn_carried(
+    ecs: &mut World,
+    entity: Option<SpawnEntity>,
+    owner: Entity,
+    idx: usize,
+    map: &Map,
+) -> Option<Entity> {
+    let item = spawn_entity(ecs, entity, idx, map)?;
+    ecs.write_storage::<Position>().remove(item);
+    ecs.write_storage::<InBackpack>()
+        .insert(item, InBackpack { owner })
+        .expect("Unable to insert backpack entry");
+    Some(item)
+}
+
+/// Leaves an edible corpse of the slain monster behind
+pub fn corpse(ecs: &mut World, x: u16, y: u16, monster_name: &str) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('%'),
+            fg: RGB::named(rltk::DARK_RED),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: format!("{} corpse", monster_name),
+        })
+        .with(Description {
+            text: format!(
+                "The remains of a slain {}. Edible, while it's fresh.",
+                monster_name.to_lowercase()
+            ),
+        })
+        .with(Item {})
+        .with(ProvidesFood {})
+        .with(Consumable {})
+        .with(Freshness {
+            turns: CORPSE_FRESHNESS,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
 }
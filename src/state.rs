@@ -2,9 +2,19 @@ use rltk::{GameState, Rltk};
 use specs::prelude::*;
 
 use crate::{
+    components::Position,
     game_loop,
+    gamelog::LogCategory,
+    history::RunOrder,
     main_menu::{self, MainMenuSelection},
-    menu::pause_menu::PauseMenuSelection,
+    menu::{
+        hall_of_fame,
+        help_menu::HelpPage,
+        pause_menu::PauseMenuSelection,
+        replay_viewer,
+        slot_menu::{SlotMode, SlotPrompt},
+        trade_menu::TradeMode,
+    },
     systems::Systems,
 };
 
@@ -14,20 +24,73 @@ pub enum RunState {
     PreRun,
     PlayerTurn,
     MonsterTurn,
+    AutoMove,
     ShowInventory,
     ShowDropItem,
-    ShowTargeting { range: u16, item: Entity },
+    ShowTargeting {
+        range: u16,
+        item: Entity,
+        cursor: Position,
+    },
     ShowUnequipItem,
-    PauseMenu { selected_menu: PauseMenuSelection },
-    Dead,
+    ShowTrade {
+        vendor: Entity,
+        mode: TradeMode,
+    },
+    ShowCharacter,
+    ShowOverview {
+        cursor: Position,
+        editing: bool,
+    },
+    ShowHelp {
+        page: HelpPage,
+    },
+    ShowLog {
+        scroll: u16,
+        filter: Option<LogCategory>,
+    },
+    Look {
+        x: u16,
+        y: u16,
+        index: usize,
+    },
+    PauseMenu {
+        selected_menu: PauseMenuSelection,
+    },
+    ShowSaveSlots {
+        mode: SlotMode,
+        selected: usize,
+        prompt: SlotPrompt,
+    },
+    Dead {
+        scroll: u16,
+    },
     NextLevel,
     NewGame,
 }
 
 #[derive(Clone, Copy)]
 pub enum GlobalState {
-    MainMenu { selected_menu: MainMenuSelection },
-    Gameplay { phase: RunState, systems: Systems },
+    MainMenu {
+        selected_menu: MainMenuSelection,
+    },
+    LoadGame {
+        selected: usize,
+        prompt: SlotPrompt,
+    },
+    HallOfFame {
+        order: RunOrder,
+        selected: usize,
+    },
+    Gameplay {
+        phase: RunState,
+        systems: Systems,
+    },
+    Replay {
+        speed: u16,
+        paused: bool,
+        waited_ms: f32,
+    },
 }
 
 pub struct State {
@@ -42,9 +105,20 @@ impl GameState for State {
             GlobalState::MainMenu { selected_menu } => {
                 main_menu::main_menu(&mut self.ecs, ctx, selected_menu)
             }
+            GlobalState::LoadGame { selected, prompt } => {
+                main_menu::load_menu(&mut self.ecs, ctx, selected, prompt)
+            }
+            GlobalState::HallOfFame { order, selected } => {
+                hall_of_fame::hall_of_fame(&mut self.ecs, ctx, order, selected)
+            }
             GlobalState::Gameplay { phase, systems } => {
                 game_loop::next_iteration(&mut self.ecs, ctx, phase, systems)
             }
+            GlobalState::Replay {
+                speed,
+                paused,
+                waited_ms,
+            } => replay_viewer::replay_viewer(&mut self.ecs, ctx, speed, paused, waited_ms),
         };
 
         {
@@ -0,0 +1,141 @@
+use std::fs;
+use std::thread::{self, JoinHandle};
+
+use serde_json::Value;
+use specs::World;
+
+use crate::components::GameMode;
+use crate::gamelog::{GameLog, LogCategory};
+use crate::menu::slot_menu::SlotBrowser;
+use crate::settings::Settings;
+
+use super::save_slots::{self, SlotHeader};
+use super::saveload_system::{self, SaveError};
+
+// Slot the game saves itself to, and the slot the autosave before that one is kept in
+pub const AUTOSAVE_SLOT: &str = "autosave";
+pub const BACKUP_SLOT: &str = "autosave-backup";
+
+// Left in the save directory while a run is played, a lock still there on startup means a crash
+const SESSION_LOCK: &str = "session.lock";
+
+// Tests that write the autosave slot take turns, they all share the test save folder
+#[cfg(test)]
+pub static SLOT_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// The autosave being written in the background, games without this resource never autosave
+#[derive(Default)]
+pub struct Autosave {
+    writer: Option<JoinHandle<Result<(), SaveError>>>,
+}
+
+/// Saves the run to the autosave slot, the autosave it replaces becomes the backup in casual runs
+///
+/// Only taking the snapshot happens here, encoding and writing it are left to a thread of their own
+pub fn autosave(ecs: &mut World) {
+    if !ecs.has_value::<Autosave>() {
+        return;
+    }
+    // One autosave at a time, so the backup is always the one before the latest
+    wait_for_writer(ecs);
+
+    let format = ecs.fetch::<Settings>().save_format();
+    let header = SlotHeader::from_ecs(ecs);
+    let components = match saveload_system::snapshot(ecs) {
+        Ok(components) => components,
+        Err(error) => {
+            report(ecs, error);
+            return;
+        }
+    };
+    let writer = thread::spawn(move || {
+        let data = format.encode(&header, &Value::Object(components))?;
+        // A strict run keeps no backup, its latest save is the only one
+        if header.mode == GameMode::Strict {
+            saveload_system::write_slot(AUTOSAVE_SLOT, &data)?;
+            return save_slots::delete_strict_run(header.seed, Some(AUTOSAVE_SLOT));
+        }
+        if let Some(previous) = save_slots::existing_slot_path(AUTOSAVE_SLOT) {
+            save_slots::delete_slot(BACKUP_SLOT)?;
+            fs::rename(previous, save_slots::slot_path(BACKUP_SLOT)).map_err(|error| {
+                SaveError::Io {
+                    action: "keep the previous autosave",
+                    error,
+                }
+            })?;
+        }
+        saveload_system::write_slot(AUTOSAVE_SLOT, &data)
+    });
+    ecs.fetch_mut::<Autosave>().writer = Some(writer);
+}
+
+/// Autosaves when the turn is a multiple of the interval in the settings
+///
+/// Strict runs only autosave on level changes, anything more often would be a save to go back to
+pub fn autosave_if_due(ecs: &mut World) {
+    if saveload_system::run_stats(ecs).mode == GameMode::Strict {
+        return;
+    }
+    let interval = ecs.fetch::<Settings>().autosave_turns;
+    if interval > 0 && ecs.fetch::<GameLog>().turn.is_multiple_of(interval) {
+        autosave(ecs);
+    }
+}
+
+/// Reports a finished autosave that failed, an autosave still being written is left alone
+pub fn poll(ecs: &mut World) {
+    let finished = ecs.try_fetch::<Autosave>().is_some_and(|autosave| {
+        autosave
+            .writer
+            .as_ref()
+            .is_some_and(|writer| writer.is_finished())
+    });
+    if finished {
+        wait_for_writer(ecs);
+    }
+}
+
+/// Blocks until the autosave being written, if any, is on disk
+pub fn wait_for_writer(ecs: &mut World) {
+    let writer = ecs
+        .try_fetch_mut::<Autosave>()
+        .and_then(|mut autosave| autosave.writer.take());
+    if let Some(Ok(Err(error))) = writer.map(|writer| writer.join()) {
+        report(ecs, error);
+    }
+}
+
+fn report(ecs: &mut World, error: SaveError) {
+    if let Some(mut gamelog) = ecs.try_fetch_mut::<GameLog>() {
+        gamelog.log(LogCategory::System, format!("Autosave failed: {}", error));
+    }
+}
+
+/// Marks a run as being played, until `end_session` says the game was left properly
+pub fn begin_session() {
+    let _ = fs::create_dir_all(save_slots::save_dir());
+    let _ = fs::write(save_slots::save_dir().join(SESSION_LOCK), "");
+}
+
+/// Finishes the autosave being written and clears the session lock, for quitting or ending a run
+pub fn end_session(ecs: &mut World) {
+    wait_for_writer(ecs);
+    let _ = fs::remove_file(save_slots::save_dir().join(SESSION_LOCK));
+}
+
+/// Whether the last session ended without quitting and left an autosave to resume
+pub fn interrupted_session() -> bool {
+    save_slots::save_dir().join(SESSION_LOCK).exists()
+        && (save_slots::existing_slot_path(AUTOSAVE_SLOT).is_some()
+            || save_slots::existing_slot_path(BACKUP_SLOT).is_some())
+}
+
+/// Loads the latest autosave, or the backup when the latest can't be loaded
+pub fn resume(ecs: &mut World) -> Result<(), SaveError> {
+    let result = saveload_system::load_game(ecs, AUTOSAVE_SLOT)
+        .or_else(|error| saveload_system::load_game(ecs, BACKUP_SLOT).map_err(|_| error));
+    if result.is_ok() {
+        ecs.fetch_mut::<SlotBrowser>().active = None;
+    }
+    result
+}
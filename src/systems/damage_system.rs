@@ -1,10 +1,31 @@
 use specs::prelude::*;
 
 use crate::{
-    components::{CombatStats, Player, Position, SufferDamage},
+    components::{
+        CombatStats, Equipped, InBackpack, LeavesCorpse, Name, Player, Position, Resistance,
+        RunStats, SufferDamage, Wallet,
+    },
     map::map::Map,
+    spawn::spawner,
 };
 
+// Resistance from equipment never reduces damage by more than this percentage
+pub const MAX_RESISTANCE: u32 = 75;
+
+/// Damage reduction from everything the entity has equipped, capped at MAX_RESISTANCE
+pub fn total_resistance(
+    entity: Entity,
+    equipped: &ReadStorage<Equipped>,
+    resistances: &ReadStorage<Resistance>,
+) -> u32 {
+    let resistance = (equipped, resistances)
+        .join()
+        .filter(|(equipped_by, _)| equipped_by.owner == entity)
+        .map(|(_, resistance)| resistance.percent)
+        .sum();
+    u32::min(MAX_RESISTANCE, resistance)
+}
+
 #[derive(Clone, Copy)]
 pub struct DamageSystem;
 
@@ -15,14 +36,55 @@ impl<'a> System<'a> for DamageSystem {
         ReadStorage<'a, Position>,
         WriteExpect<'a, Map>,
         Entities<'a>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Resistance>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, RunStats>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut stats, mut damage, positions, mut map, entities) = data;
+        let (
+            mut stats,
+            mut damage,
+            positions,
+            mut map,
+            entities,
+            equipped,
+            resistances,
+            player_entity,
+            names,
+            mut run_stats,
+        ) = data;
 
         for (entity, mut stats, damage) in (&entities, &mut stats, &damage).join() {
+            let resistance = total_resistance(entity, &equipped, &resistances);
+
+            let was_alive = stats.hp > 0;
             let total_damage = damage.amount.iter().sum::<u32>();
-            stats.damage(total_damage);
+            stats.damage(total_damage - total_damage * resistance / 100);
+
+            // Remember what killed the player, the biggest hit when several land at once
+            if was_alive && stats.hp == 0 && entity == *player_entity {
+                let killer = damage
+                    .amount
+                    .iter()
+                    .zip(&damage.causes)
+                    .max_by_key(|(amount, _)| **amount)
+                    .map(|(_, cause)| cause.clone());
+                if let Some(run_stats) = run_stats.get_mut(entity) {
+                    run_stats.killed_by = killer;
+                }
+            }
+
+            // Remember what the player killed for the character sheet
+            if was_alive && stats.hp == 0 && damage.by_player && entity != *player_entity {
+                if let (Some(run_stats), Some(name)) =
+                    (run_stats.get_mut(*player_entity), names.get(entity))
+                {
+                    *run_stats.kills.entry(name.name.clone()).or_insert(0) += 1;
+                }
+            }
             if let Some(pos) = positions.get(entity) {
                 let idx = map.index_from_xy(pos.x, pos.y);
                 map.bloodstains.insert(idx);
@@ -35,20 +97,83 @@ impl<'a> System<'a> for DamageSystem {
 
 pub fn delete_the_dead(ecs: &mut World) {
     let mut dead: Vec<Entity> = Vec::new();
+    let mut dropped_gold: Vec<(Position, u32)> = Vec::new();
+    let mut corpses: Vec<(Position, String)> = Vec::new();
     {
         let combat_stats = ecs.read_storage::<CombatStats>();
         let players = ecs.read_storage::<Player>();
+        let positions = ecs.read_storage::<Position>();
+        let wallets = ecs.read_storage::<Wallet>();
+        let leaves_corpse = ecs.read_storage::<LeavesCorpse>();
+        let names = ecs.read_storage::<Name>();
         let entities = ecs.entities();
         for (entity, stats) in (&entities, &combat_stats).join() {
             if stats.hp == 0 {
                 let player = players.get(entity);
                 if player.is_none() {
                     dead.push(entity);
+                    if let (Some(pos), Some(wallet)) = (positions.get(entity), wallets.get(entity))
+                    {
+                        if wallet.gold > 0 {
+                            dropped_gold.push((*pos, wallet.gold));
+                        }
+                    }
+                    if let (Some(pos), Some(_), Some(name)) = (
+                        positions.get(entity),
+                        leaves_corpse.get(entity),
+                        names.get(entity),
+                    ) {
+                        corpses.push((*pos, name.name.to_string()));
+                    }
                 }
             }
         }
     }
+    drop_carried_items(ecs, &dead);
+    for (pos, amount) in dropped_gold {
+        spawner::gold_pile(ecs, pos.x, pos.y, amount);
+    }
+    for (pos, name) in corpses {
+        spawner::corpse(ecs, pos.x, pos.y, &name);
+    }
     for victim in dead {
         ecs.delete_entity(victim).expect("Unable to delete");
     }
 }
+
+// The dead drop everything they carried or wielded at the place of death. The items are
+// detached right away, a snapshot must never see them owned by a deleted entity
+fn drop_carried_items(ecs: &mut World, dead: &[Entity]) {
+    let entities = ecs.entities();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut backpack = ecs.write_storage::<InBackpack>();
+    let mut equipped = ecs.write_storage::<Equipped>();
+
+    let mut dropped: Vec<(Entity, Option<Position>)> = Vec::new();
+    for (item, carried, wielded) in (&entities, backpack.maybe(), equipped.maybe()).join() {
+        let owner = match (carried, wielded) {
+            (Some(carried), _) => carried.owner,
+            (None, Some(wielded)) => wielded.owner,
+            (None, None) => continue,
+        };
+        if dead.contains(&owner) {
+            dropped.push((item, positions.get(owner).copied()));
+        }
+    }
+
+    for (item, position) in dropped {
+        backpack.remove(item);
+        equipped.remove(item);
+        match position {
+            Some(position) => {
+                positions
+                    .insert(item, position)
+                    .expect("Unable to insert position");
+            }
+            // Nowhere to drop it, the item goes with its owner
+            None => {
+                entities.delete(item).expect("Unable to delete");
+            }
+        }
+    }
+}
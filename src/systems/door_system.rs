@@ -0,0 +1,124 @@
+use specs::prelude::*;
+
+use crate::{
+    components::{
+        BlocksTile, BlocksVisibility, Door, InBackpack, Key, Locked, Name, Renderable, Viewshed,
+        WantsToCloseDoor, WantsToOpenDoor,
+    },
+    gamelog::{GameLog, LogCategory},
+};
+
+#[derive(Clone, Copy)]
+pub struct DoorSystem;
+
+impl<'a> System<'a> for DoorSystem {
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        Entities<'a>,
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, WantsToOpenDoor>,
+        WriteStorage<'a, WantsToCloseDoor>,
+        WriteStorage<'a, Door>,
+        WriteStorage<'a, Locked>,
+        WriteStorage<'a, BlocksTile>,
+        WriteStorage<'a, BlocksVisibility>,
+        WriteStorage<'a, Renderable>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, InBackpack>,
+        ReadStorage<'a, Key>,
+        ReadStorage<'a, Name>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            player_entity,
+            entities,
+            mut gamelog,
+            mut wants_open,
+            mut wants_close,
+            mut doors,
+            mut locked,
+            mut blocks_tile,
+            mut blocks_visibility,
+            mut renderables,
+            mut viewsheds,
+            backpack,
+            keys,
+            names,
+        ) = data;
+
+        let mut doors_changed = false;
+
+        for (opener, intent) in (&entities, &wants_open).join() {
+            let door = match doors.get_mut(intent.door) {
+                None => continue,
+                Some(door) => door,
+            };
+
+            if locked.contains(intent.door) {
+                let key = (&entities, &backpack, &keys)
+                    .join()
+                    .find(|(_, item, _)| item.owner == opener)
+                    .map(|(key, _, _)| key);
+                match key {
+                    None => {
+                        if opener == *player_entity {
+                            gamelog.log(LogCategory::Status, "The door is locked. You need a key.");
+                        }
+                        continue;
+                    }
+                    Some(key) => {
+                        entities.delete(key).expect("Unable to delete key");
+                        locked.remove(intent.door);
+                        blocks_tile.remove(intent.door);
+                        if opener == *player_entity {
+                            gamelog.log(LogCategory::Status, "You unlock the door with your key.");
+                        }
+                    }
+                }
+            }
+
+            door.open = true;
+            blocks_visibility.remove(intent.door);
+            if let Some(renderable) = renderables.get_mut(intent.door) {
+                renderable.glyph = rltk::to_cp437('\'');
+                renderable.fg = rltk::RGB::named(rltk::CHOCOLATE);
+            }
+            if opener == *player_entity {
+                gamelog.log(LogCategory::Status, "You open the door.");
+            } else if let Some(name) = names.get(opener) {
+                gamelog.log(LogCategory::Status, format!("{} opens a door.", &name.name));
+            }
+            doors_changed = true;
+        }
+
+        for (closer, intent) in (&entities, &wants_close).join() {
+            let door = match doors.get_mut(intent.door) {
+                None => continue,
+                Some(door) => door,
+            };
+
+            door.open = false;
+            blocks_visibility
+                .insert(intent.door, BlocksVisibility {})
+                .expect("Unable to insert visibility blocker");
+            if let Some(renderable) = renderables.get_mut(intent.door) {
+                renderable.glyph = rltk::to_cp437('+');
+            }
+            if closer == *player_entity {
+                gamelog.log(LogCategory::Status, "You close the door.");
+            }
+            doors_changed = true;
+        }
+
+        // Everyone near the door may now see more or less than before
+        if doors_changed {
+            for viewshed in (&mut viewsheds).join() {
+                viewshed.dirty = true;
+            }
+        }
+
+        wants_open.clear();
+        wants_close.clear();
+    }
+}
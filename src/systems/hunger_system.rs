@@ -2,7 +2,7 @@ use specs::{Entities, Entity, Join, ReadExpect, System, WriteExpect, WriteStorag
 
 use crate::{
     components::{HungerClock, HungerState, SufferDamage},
-    gamelog::GameLog,
+    gamelog::{GameLog, LogCategory, LogEntry},
     state::RunState,
 };
 
@@ -44,33 +44,33 @@ impl<'a> System<'a> for HungerSystem {
                     clock.state = HungerState::Normal;
                     clock.duration = 200;
                     if entity == *player {
-                        gamelog
-                            .entries
-                            .push("You are no longer well fed.".to_string());
+                        gamelog.log(LogCategory::Status, "You are no longer well fed.");
                     }
                 }
                 HungerState::Normal => {
                     clock.state = HungerState::Hungry;
                     clock.duration = 200;
                     if entity == *player {
-                        gamelog.entries.push("You are hungry.".to_string());
+                        gamelog.log(LogCategory::Status, "You are hungry.");
                     }
                 }
                 HungerState::Hungry => {
                     clock.state = HungerState::Starving;
                     clock.duration = 200;
                     if entity == *player {
-                        gamelog.entries.push("You are starving!".to_string());
+                        gamelog.log(LogCategory::Status, "You are starving!");
                     }
                 }
                 HungerState::Starving => {
                     if entity == *player {
-                        gamelog.entries.push(
-                            "Your hunger pangs are getting painful! You suffer 1 hp damage."
-                                .to_string(),
+                        gamelog.push(
+                            LogEntry::new(LogCategory::Status)
+                                .text("Your hunger pangs are getting painful! You suffer ")
+                                .damage(1)
+                                .text(" damage."),
                         );
                     }
-                    SufferDamage::new_damage(&mut inflict_damage, entity, 1);
+                    SufferDamage::new_damage(&mut inflict_damage, entity, 1, "starvation", false);
                 }
             }
         }
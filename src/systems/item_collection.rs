@@ -1,8 +1,8 @@
 use specs::prelude::*;
 
 use crate::{
-    components::{InBackpack, Name, Position, WantsToPickupItem},
-    gamelog::GameLog,
+    components::{Gold, InBackpack, Name, Position, Wallet, WantsToPickupItem},
+    gamelog::{GameLog, LogCategory},
 };
 
 #[derive(Clone, Copy)]
@@ -16,13 +16,40 @@ impl<'a> System<'a> for ItemCollectionSystem {
         WriteStorage<'a, Position>,
         ReadStorage<'a, Name>,
         WriteStorage<'a, InBackpack>,
+        Entities<'a>,
+        ReadStorage<'a, Gold>,
+        WriteStorage<'a, Wallet>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (player_entity, mut gamelog, mut wants_pickup, mut positions, names, mut backpack) =
-            data;
+        let (
+            player_entity,
+            mut gamelog,
+            mut wants_pickup,
+            mut positions,
+            names,
+            mut backpack,
+            entities,
+            gold,
+            mut wallets,
+        ) = data;
 
         for pickup in wants_pickup.join() {
+            // Gold goes straight into the wallet instead of the backpack
+            if let Some(gold) = gold.get(pickup.item) {
+                if let Some(wallet) = wallets.get_mut(pickup.collected_by) {
+                    wallet.gold += gold.amount;
+                    entities.delete(pickup.item).expect("Unable to delete gold");
+                    if pickup.collected_by == *player_entity {
+                        gamelog.log(
+                            LogCategory::Loot,
+                            format!("You pick up {} gold.", gold.amount),
+                        );
+                    }
+                    continue;
+                }
+            }
+
             positions.remove(pickup.item);
             backpack
                 .insert(
@@ -34,10 +61,10 @@ impl<'a> System<'a> for ItemCollectionSystem {
                 .expect("Unable to insert backpack entry");
 
             if pickup.collected_by == *player_entity {
-                gamelog.entries.push(format!(
-                    "You pick up the {}.",
-                    names.get(pickup.item).unwrap().name
-                ));
+                gamelog.log(
+                    LogCategory::Loot,
+                    format!("You pick up the {}.", names.get(pickup.item).unwrap().name),
+                );
             }
         }
 
@@ -1,8 +1,8 @@
 use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
-    components::{InBackpack, Name, Position, WantsToDropItem},
-    gamelog::GameLog,
+    components::{Equipped, InBackpack, Name, Position, WantsToDropItem},
+    gamelog::{GameLog, LogCategory},
 };
 
 #[derive(Clone, Copy)]
@@ -17,6 +17,7 @@ impl<'a> System<'a> for ItemDropSystem {
         ReadStorage<'a, Name>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, InBackpack>,
+        WriteStorage<'a, Equipped>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -28,6 +29,7 @@ impl<'a> System<'a> for ItemDropSystem {
             names,
             mut positions,
             mut backpack,
+            mut equipped,
         ) = data;
 
         for (entity, to_drop) in (&entities, &wants_drop).join() {
@@ -36,12 +38,13 @@ impl<'a> System<'a> for ItemDropSystem {
                 .insert(to_drop.item, drop_position)
                 .expect("Unable to insert position");
             backpack.remove(to_drop.item);
+            equipped.remove(to_drop.item);
 
             if entity == *player_entity {
-                gamelog.entries.push(format!(
-                    "You drop the {}",
-                    names.get(to_drop.item).unwrap().name
-                ));
+                gamelog.log(
+                    LogCategory::Loot,
+                    format!("You drop the {}", names.get(to_drop.item).unwrap().name),
+                );
             }
         }
 
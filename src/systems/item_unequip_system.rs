@@ -1,6 +1,6 @@
 use specs::{Entities, Join, System, WriteStorage};
 
-use crate::components::{Equipped, InBackpack, WantsToUnequipItem};
+use crate::components::{Equipped, InBackpack, Viewshed, WantsToUnequipItem};
 
 #[derive(Clone, Copy)]
 pub struct ItemUnequipSystem;
@@ -11,12 +11,17 @@ impl<'a> System<'a> for ItemUnequipSystem {
         WriteStorage<'a, WantsToUnequipItem>,
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
+        WriteStorage<'a, Viewshed>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut wants_unequip, mut equipped, mut backpack) = data;
+        let (entities, mut wants_unequip, mut equipped, mut backpack, mut viewsheds) = data;
 
         for (entity, to_remove) in (&entities, &wants_unequip).join() {
+            // Equipment may change the vision range
+            if let Some(viewshed) = viewsheds.get_mut(entity) {
+                viewshed.dirty = true;
+            }
             equipped.remove(to_remove.item);
             backpack
                 .insert(to_remove.item, InBackpack { owner: entity })
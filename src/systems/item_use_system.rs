@@ -5,9 +5,9 @@ use crate::{
     components::{
         AreaOfEffect, CombatStats, Confusion, Consumable, Equippable, Equipped, HungerClock,
         HungerState, InBackpack, InflictsDamage, Name, Position, ProvidesFood, ProvidesHealing,
-        SufferDamage, WantsToUseItem,
+        SufferDamage, Viewshed, WantsToUseItem,
     },
-    gamelog::GameLog,
+    gamelog::{GameLog, LogCategory, LogEntry},
     map::map::Map,
 };
 
@@ -38,6 +38,7 @@ impl<'a> System<'a> for ItemUseSystem {
         ReadStorage<'a, Position>,
         ReadStorage<'a, ProvidesFood>,
         WriteStorage<'a, HungerClock>,
+        WriteStorage<'a, Viewshed>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -62,6 +63,7 @@ impl<'a> System<'a> for ItemUseSystem {
             positions,
             food,
             mut hunger,
+            mut viewsheds,
         ) = data;
 
         for (entity, use_item) in (&entities, &wants_use).join() {
@@ -76,6 +78,10 @@ impl<'a> System<'a> for ItemUseSystem {
                 &mut gamelog,
                 &names,
             ) {
+                // Equipment may change the vision range
+                if let Some(viewshed) = viewsheds.get_mut(entity) {
+                    viewshed.dirty = true;
+                }
                 // Equipping items shouldn't trigger any other immediate effects
                 continue;
             }
@@ -245,11 +251,15 @@ fn try_to_heal(
             );
         }
         if entity == player_entity {
-            gamelog.entries.push(format!(
-                "You drink the {}, healing {} hp.",
-                names.get(use_item.item).unwrap().name,
-                hp_diff
-            ));
+            gamelog.push(
+                LogEntry::new(LogCategory::Status)
+                    .text(format!(
+                        "You drink the {}, healing ",
+                        names.get(use_item.item).unwrap().name
+                    ))
+                    .healing(hp_diff)
+                    .text("."),
+            );
         }
     }
     used_item
@@ -279,7 +289,15 @@ fn try_to_damage(
             continue;
         }
 
-        SufferDamage::new_damage(suffer_damage, *target, damage_item.damage);
+        SufferDamage::new_damage(
+            suffer_damage,
+            *target,
+            damage_item.damage,
+            names
+                .get(use_item.item)
+                .map_or("an item", |name| &name.name),
+            entity == player_entity,
+        );
         used_item = true;
         if let Some(pos) = positions.get(*target) {
             particle_builder.request(
@@ -294,16 +312,26 @@ fn try_to_damage(
         if entity == player_entity {
             let item_name = names.get(use_item.item).unwrap();
             if *target == player_entity {
-                gamelog.entries.push(format!(
-                    "You use {} on yourself, inflicting {} hp.",
-                    item_name.name, damage_item.damage
-                ));
+                gamelog.push(
+                    LogEntry::new(LogCategory::Combat)
+                        .text(format!(
+                            "You use {} on yourself, inflicting ",
+                            item_name.name
+                        ))
+                        .damage(damage_item.damage)
+                        .text("."),
+                );
             } else {
                 let mob_name = names.get(*target).unwrap();
-                gamelog.entries.push(format!(
-                    "You use {} on {}, inflicting {} hp.",
-                    item_name.name, mob_name.name, damage_item.damage
-                ));
+                gamelog.push(
+                    LogEntry::new(LogCategory::Combat)
+                        .text(format!(
+                            "You use {} on {}, inflicting ",
+                            item_name.name, mob_name.name
+                        ))
+                        .damage(damage_item.damage)
+                        .text("."),
+                );
             }
         }
     }
@@ -347,16 +375,22 @@ fn try_to_confuse(
         if entity == player_entity {
             let item_name = names.get(use_item.item).unwrap();
             if *target == player_entity {
-                gamelog.entries.push(format!(
-                    "You use {} on yourself, confusing yourself for {} turns",
-                    item_name.name, confusion_item.turns
-                ));
+                gamelog.log(
+                    LogCategory::Combat,
+                    format!(
+                        "You use {} on yourself, confusing yourself for {} turns",
+                        item_name.name, confusion_item.turns
+                    ),
+                );
             } else {
                 let mob_name = names.get(*target).unwrap();
-                gamelog.entries.push(format!(
-                    "You use {} on {}, confusing them for {} turns.",
-                    item_name.name, mob_name.name, confusion_item.turns
-                ));
+                gamelog.log(
+                    LogCategory::Combat,
+                    format!(
+                        "You use {} on {}, confusing them for {} turns.",
+                        item_name.name, mob_name.name, confusion_item.turns
+                    ),
+                );
             }
         }
     }
@@ -385,7 +419,7 @@ fn try_to_equip(
         if already_equipped.owner == entity && already_equipped.slot == target_slot {
             to_unequip.push(item_entity);
             if entity == player_entity {
-                gamelog.entries.push(format!("You unequip {}.", name.name));
+                gamelog.log(LogCategory::Loot, format!("You unequip {}.", name.name));
             }
         }
     }
@@ -407,10 +441,10 @@ fn try_to_equip(
         .expect("Unable to insert equipped component");
     backpack.remove(use_item.item);
     if entity == player_entity {
-        gamelog.entries.push(format!(
-            "You equip {}.",
-            names.get(use_item.item).unwrap().name
-        ));
+        gamelog.log(
+            LogCategory::Loot,
+            format!("You equip {}.", names.get(use_item.item).unwrap().name),
+        );
     }
 
     true
@@ -450,10 +484,10 @@ fn try_to_eat(
     hunger_clock.state = new_hunger_state;
     if entity == player_entity {
         let ration_name = names.get(use_item.item).unwrap();
-        gamelog.entries.push(format!(
-            "You eat the {}, satisfying your hunger.",
-            ration_name.name
-        ));
+        gamelog.log(
+            LogCategory::Status,
+            format!("You eat the {}, satisfying your hunger.", ration_name.name),
+        );
     }
     true
 }
@@ -2,14 +2,41 @@ use specs::{Entity, Join, World, WorldExt};
 
 use crate::components::Lifetime;
 
-pub fn remove_expired_entities(ecs: &mut World, ctx: &rltk::Rltk) {
+/// Time spent waiting for input since lifetimes were last aged
+///
+/// Lifetimes only age when a turn runs, by the whole milliseconds since the turn before,
+/// so a replay that knows those milliseconds removes the same entities on the same turn
+#[derive(Default)]
+pub struct LifetimeClock {
+    waited_ms: f32,
+}
+
+impl LifetimeClock {
+    pub fn wait(&mut self, frame_time_ms: f32) {
+        self.waited_ms += frame_time_ms;
+    }
+
+    /// Whole milliseconds since the last turn, this frame included, and the clock starts over
+    pub fn elapsed(&mut self, frame_time_ms: f32) -> u32 {
+        let elapsed = (self.waited_ms + frame_time_ms).round() as u32;
+        self.waited_ms = 0.0;
+        elapsed
+    }
+
+    /// Whether the lifetime ran out while waiting, the entity is only removed on the next turn
+    pub fn has_expired(&self, lifetime: &Lifetime) -> bool {
+        lifetime.lifetime_ms - self.waited_ms < 0.0
+    }
+}
+
+pub fn remove_expired_entities(ecs: &mut World, elapsed_ms: u32) {
     let mut expired_entities: Vec<Entity> = Vec::new();
     {
         // Age out entities
         let mut lifetimes = ecs.write_storage::<Lifetime>();
         let entities = ecs.entities();
         for (entity, mut lifetime) in (&entities, &mut lifetimes).join() {
-            lifetime.lifetime_ms -= ctx.frame_time_ms;
+            lifetime.lifetime_ms -= elapsed_ms as f32;
             if lifetime.lifetime_ms < 0.0 {
                 expired_entities.push(entity);
             }
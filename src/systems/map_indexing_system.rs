@@ -1,7 +1,7 @@
 use specs::prelude::*;
 
 use crate::{
-    components::{BlocksTile, Position},
+    components::{BlocksTile, BlocksVisibility, Position},
     map::map::Map,
 };
 
@@ -14,13 +14,15 @@ impl<'a> System<'a> for MapIndexingSystem {
         ReadStorage<'a, Position>,
         ReadStorage<'a, BlocksTile>,
         Entities<'a>,
+        ReadStorage<'a, BlocksVisibility>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, position, blockers, entities) = data;
+        let (mut map, position, blockers, entities, view_blockers) = data;
 
         map.populate_blocked();
         map.clear_content_index();
+        map.view_blocked.clear();
         for (entity, position) in (&entities, &position).join() {
             let idx = map.index_from_xy(position.x, position.y);
 
@@ -30,6 +32,10 @@ impl<'a> System<'a> for MapIndexingSystem {
                 map.blocked[idx] = true;
             }
 
+            if view_blockers.contains(entity) {
+                map.view_blocked.insert(idx);
+            }
+
             // Push the entity to the appropriate index slot. It's a Copy type,
             // so we don't need to clone it (we want to avoid moving it out of the ECS!)
             map.tile_content[idx].push(entity);
@@ -6,11 +6,87 @@ use crate::{
         CombatStats, DefenseBonus, Equipped, HungerClock, HungerState, MeleePowerBonus, Name,
         Position, SufferDamage, WantsToMelee,
     },
-    gamelog::GameLog,
+    gamelog::{GameLog, LogCategory, LogEntry},
 };
 
 use super::particle_system::ParticleBuilder;
 
+// Well fed creatures hit a bit harder
+pub const WELL_FED_POWER_BONUS: i32 = 1;
+
+/// Storages with everything that can modify the base combat stats
+pub struct Bonuses<'s, 'a> {
+    pub names: &'s ReadStorage<'a, Name>,
+    pub equipped: &'s ReadStorage<'a, Equipped>,
+    pub melee_bonus: &'s ReadStorage<'a, MeleePowerBonus>,
+    pub defense_bonus: &'s ReadStorage<'a, DefenseBonus>,
+    pub hunger_clocks: &'s ReadStorage<'a, HungerClock>,
+}
+
+/// Base combat stats together with every bonus that applies to them
+pub struct EffectiveStats {
+    pub base_power: i32,
+    pub base_defense: i32,
+    pub power_bonuses: Vec<(String, i32)>,
+    pub defense_bonuses: Vec<(String, i32)>,
+}
+
+impl EffectiveStats {
+    pub fn power(&self) -> i32 {
+        self.base_power
+            + self
+                .power_bonuses
+                .iter()
+                .map(|(_, bonus)| bonus)
+                .sum::<i32>()
+    }
+
+    pub fn defense(&self) -> i32 {
+        self.base_defense
+            + self
+                .defense_bonuses
+                .iter()
+                .map(|(_, bonus)| bonus)
+                .sum::<i32>()
+    }
+}
+
+pub fn effective_stats(entity: Entity, stats: &CombatStats, bonuses: &Bonuses) -> EffectiveStats {
+    let mut power_bonuses = Vec::new();
+    let mut defense_bonuses = Vec::new();
+    for (equipped_by, name, melee_bonus, defense_bonus) in (
+        bonuses.equipped,
+        bonuses.names,
+        bonuses.melee_bonus.maybe(),
+        bonuses.defense_bonus.maybe(),
+    )
+        .join()
+    {
+        if equipped_by.owner != entity {
+            continue;
+        }
+        if let Some(melee_bonus) = melee_bonus {
+            power_bonuses.push((name.name.clone(), melee_bonus.power));
+        }
+        if let Some(defense_bonus) = defense_bonus {
+            defense_bonuses.push((name.name.clone(), defense_bonus.defense));
+        }
+    }
+
+    if let Some(hunger) = bonuses.hunger_clocks.get(entity) {
+        if hunger.state == HungerState::WellFed {
+            power_bonuses.push(("Well fed".to_string(), WELL_FED_POWER_BONUS));
+        }
+    }
+
+    EffectiveStats {
+        base_power: stats.power,
+        base_defense: stats.defense,
+        power_bonuses,
+        defense_bonuses,
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct MeleeCombatSystem;
 
@@ -28,6 +104,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
         WriteExpect<'a, ParticleBuilder>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, HungerClock>,
+        ReadExpect<'a, Entity>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -44,6 +121,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
             mut particle_builder,
             positions,
             hunger_clocks,
+            player_entity,
         ) = data;
 
         for (entity, wants_melee, name, stats) in
@@ -58,37 +136,22 @@ impl<'a> System<'a> for MeleeCombatSystem {
                 continue;
             }
 
-            let mut offensive_bonus = 0;
-            for (_item_entity, power_bonus, equipped_by) in
-                (&entities, &melee_bonus, &equipped).join()
-            {
-                if equipped_by.owner == entity {
-                    offensive_bonus += power_bonus.power;
-                }
-            }
-
-            if let Some(hunger) = hunger_clocks.get(entity) {
-                if hunger.state == HungerState::WellFed {
-                    offensive_bonus += 1;
-                }
-            }
+            let bonuses = Bonuses {
+                names: &names,
+                equipped: &equipped,
+                melee_bonus: &melee_bonus,
+                defense_bonus: &defense_bonus,
+                hunger_clocks: &hunger_clocks,
+            };
+            let entity_power = effective_stats(entity, stats, &bonuses).power();
+            let target_defense =
+                effective_stats(wants_melee.target, target_stats, &bonuses).defense();
 
             let target_name = names.get(wants_melee.target).unwrap();
-            let mut target_defense_bonus = 0;
-            for (_item_entity, defense_bonus, equipped_by) in
-                (&entities, &defense_bonus, &equipped).join()
-            {
-                if equipped_by.owner == wants_melee.target {
-                    target_defense_bonus += defense_bonus.defense;
-                }
-            }
-
-            let entity_power = stats.power + offensive_bonus;
-            let target_defense = target_stats.defense + target_defense_bonus;
             let damage = i32::max(0, entity_power - target_defense) as u32;
             if damage == 0 {
                 let message = format!("{} is unable to hurt {}", &name.name, &target_name.name);
-                gamelog.entries.push(message);
+                gamelog.log(LogCategory::Combat, message);
                 if let Some(position) = positions.get(wants_melee.target) {
                     particle_builder.request(
                         position.x,
@@ -100,12 +163,18 @@ impl<'a> System<'a> for MeleeCombatSystem {
                     );
                 }
             } else {
-                let message = format!(
-                    "{} hits {} for {} hp",
-                    &name.name, &target_name.name, damage
+                gamelog.push(
+                    LogEntry::new(LogCategory::Combat)
+                        .text(format!("{} hits {} for ", &name.name, &target_name.name))
+                        .damage(damage),
+                );
+                SufferDamage::new_damage(
+                    &mut inflict_damage,
+                    wants_melee.target,
+                    damage,
+                    &name.name,
+                    entity == *player_entity,
                 );
-                gamelog.entries.push(message);
-                SufferDamage::new_damage(&mut inflict_damage, wants_melee.target, damage);
 
                 if let Some(position) = positions.get(wants_melee.target) {
                     particle_builder.request(
@@ -2,6 +2,7 @@ use specs::{RunNow, World, WorldExt};
 
 use self::{
     damage_system::{delete_the_dead, DamageSystem},
+    door_system::DoorSystem,
     hunger_system::HungerSystem,
     item_collection::ItemCollectionSystem,
     item_drop_system::ItemDropSystem,
@@ -12,11 +13,16 @@ use self::{
     melee_combat_system::MeleeCombatSystem,
     monster_ai_system::MonsterAI,
     particle_system::ParticleSpawnSystem,
+    perception_system::PerceptionSystem,
+    regeneration_system::RegenerationSystem,
+    spoilage_system::SpoilageSystem,
     trigger_system::TriggerSystem,
     visibility_system::VisibilitySystem,
 };
 
+pub mod autosave;
 pub mod damage_system;
+pub mod door_system;
 pub mod hunger_system;
 pub mod item_collection;
 pub mod item_drop_system;
@@ -27,7 +33,12 @@ pub mod map_indexing_system;
 pub mod melee_combat_system;
 pub mod monster_ai_system;
 pub mod particle_system;
+pub mod perception_system;
+pub mod regeneration_system;
+pub mod save_format;
+pub mod save_slots;
 pub mod saveload_system;
+pub mod spoilage_system;
 pub mod trigger_system;
 pub mod visibility_system;
 
@@ -44,7 +55,11 @@ pub struct Systems {
     item_unequip: ItemUnequipSystem,
     particle_spawn: ParticleSpawnSystem,
     hunger: HungerSystem,
+    regeneration: RegenerationSystem,
+    spoilage: SpoilageSystem,
     trigger: TriggerSystem,
+    perception: PerceptionSystem,
+    door: DoorSystem,
 }
 
 impl Systems {
@@ -61,35 +76,45 @@ impl Systems {
             item_unequip: ItemUnequipSystem {},
             particle_spawn: ParticleSpawnSystem {},
             hunger: HungerSystem {},
+            regeneration: RegenerationSystem {},
+            spoilage: SpoilageSystem {},
             trigger: TriggerSystem {},
+            perception: PerceptionSystem {},
+            door: DoorSystem {},
         }
     }
 
-    pub fn run(&mut self, ecs: &mut World, ctx: &rltk::Rltk) {
+    /// One turn of every system, entities with a lifetime age by the milliseconds since the last turn
+    pub fn run(&mut self, ecs: &mut World, elapsed_ms: u32) {
+        // Doors and other view blockers have to be indexed before the first field of view
+        self.map_indexing.run_now(ecs);
         self.item_use.run_now(ecs);
         self.item_unequip.run_now(ecs);
         self.item_drop.run_now(ecs);
         self.visibility.run_now(ecs);
         self.monster_ai.run_now(ecs);
+        self.door.run_now(ecs);
         self.trigger.run_now(ecs);
         self.map_indexing.run_now(ecs);
+        self.perception.run_now(ecs);
         self.melee_combat.run_now(ecs);
         self.damage_system.run_now(ecs);
         self.item_collection.run_now(ecs);
         self.hunger.run_now(ecs);
+        self.regeneration.run_now(ecs);
+        self.spoilage.run_now(ecs);
         self.particle_spawn.run_now(ecs);
         delete_the_dead(ecs);
-        remove_expired_entities(ecs, ctx);
+        remove_expired_entities(ecs, elapsed_ms);
         ecs.maintain();
     }
 
-    pub fn run_during_pause(&mut self, ecs: &mut World, ctx: &rltk::Rltk) {
+    pub fn run_during_pause(&mut self, ecs: &mut World) {
         self.item_use.run_now(ecs);
         self.item_unequip.run_now(ecs);
         self.item_drop.run_now(ecs);
         self.item_collection.run_now(ecs);
         self.particle_spawn.run_now(ecs);
-        remove_expired_entities(ecs, ctx);
         ecs.maintain();
     }
 }
@@ -1,9 +1,14 @@
+use std::collections::HashSet;
+
 use rltk::{Point, RGB};
 use specs::prelude::*;
 
 use crate::{
-    components::{Confusion, EntityMoved, Monster, Position, Viewshed, WantsToMelee},
-    map::map::Map,
+    components::{
+        Alerted, Confusion, Door, EntityMoved, EntryTrigger, Hidden, Monster, Position, Viewshed,
+        WantsToMelee, WantsToOpenDoor,
+    },
+    map::map::{AvoidingMap, Map},
     state::RunState,
 };
 
@@ -26,6 +31,11 @@ impl<'a> System<'a> for MonsterAI {
         WriteStorage<'a, Confusion>,
         WriteExpect<'a, ParticleBuilder>,
         WriteStorage<'a, EntityMoved>,
+        ReadStorage<'a, EntryTrigger>,
+        ReadStorage<'a, Hidden>,
+        WriteStorage<'a, Alerted>,
+        ReadStorage<'a, Door>,
+        WriteStorage<'a, WantsToOpenDoor>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -42,12 +52,23 @@ impl<'a> System<'a> for MonsterAI {
             mut confused,
             mut particle_builder,
             mut entity_moved,
+            entry_triggers,
+            hidden,
+            mut alerted,
+            doors,
+            mut wants_to_open,
         ) = data;
 
         if *runstate != RunState::MonsterTurn {
             return;
         }
 
+        // Monsters know about the traps that have been revealed, hidden ones catch them out too
+        let known_traps: HashSet<usize> = (&entry_triggers, &position, !&hidden)
+            .join()
+            .map(|(_, pos, _)| map.index_from_xy(pos.x, pos.y))
+            .collect();
+
         for (entity, mut viewshed, _monster, mut pos) in
             (&entities, &mut viewshed, &monster, &mut position).join()
         {
@@ -78,13 +99,35 @@ impl<'a> System<'a> for MonsterAI {
                         },
                     )
                     .expect("Unable to insert attack");
-            } else if viewshed.visible_tiles.contains(&player_point) {
+            } else if viewshed.visible_tiles.contains(&player_point)
+                || is_alerted(&mut alerted, entity)
+            {
+                let start = map.index_from_xy(pos.x, pos.y);
+                let goal = map.index_from_xy(player_pos.x, player_pos.y);
+                // A trap under the monster or under the player can't keep them apart
+                let mut avoid = known_traps.clone();
+                avoid.remove(&start);
+                avoid.remove(&goal);
                 let path = rltk::a_star_search(
-                    map.index_from_xy(pos.x, pos.y),
-                    map.index_from_xy(player_pos.x, player_pos.y),
-                    &mut *map,
+                    start,
+                    goal,
+                    &AvoidingMap {
+                        map: &map,
+                        avoid: &avoid,
+                    },
                 );
                 if path.success && path.steps.len() > 1 {
+                    // Monsters spend their turn opening a closed door that's in the way
+                    let closed_door = map.tile_content[path.steps[1]]
+                        .iter()
+                        .find(|content| doors.get(**content).is_some_and(|door| !door.open));
+                    if let Some(door) = closed_door {
+                        wants_to_open
+                            .insert(entity, WantsToOpenDoor { door: *door })
+                            .expect("Unable to insert door intent");
+                        continue;
+                    }
+
                     pos.x = (path.steps[1] % map.width as usize) as u16;
                     pos.y = (path.steps[1] / map.width as usize) as u16;
                     viewshed.dirty = true;
@@ -98,3 +141,17 @@ impl<'a> System<'a> for MonsterAI {
         }
     }
 }
+
+// Alerted monsters hunt the player even when they can't see them
+fn is_alerted(alerted: &mut WriteStorage<Alerted>, entity: Entity) -> bool {
+    let alert = match alerted.get_mut(entity) {
+        None => return false,
+        Some(alert) => alert,
+    };
+    if alert.turns > 1 {
+        alert.turns -= 1;
+    } else {
+        alerted.remove(entity);
+    }
+    true
+}
@@ -0,0 +1,74 @@
+use specs::{Entities, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
+
+use crate::{
+    components::{Hidden, Name, Position},
+    gamelog::{GameLog, LogCategory},
+    map::map::Map,
+    state::RunState,
+};
+
+// Hidden things this close to the player can be noticed every turn
+const PERCEPTION_RANGE: f32 = 2.5;
+// One in PERCEPTION_CHANCE to notice each hidden thing per turn
+const PERCEPTION_CHANCE: i32 = 4;
+
+#[derive(Clone, Copy)]
+pub struct PerceptionSystem;
+
+impl<'a> System<'a> for PerceptionSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, Position>,
+        ReadExpect<'a, RunState>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, Hidden>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+        WriteExpect<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            map,
+            player_pos,
+            run_state,
+            positions,
+            mut hidden,
+            names,
+            mut rng,
+            mut gamelog,
+        ) = data;
+
+        if *run_state != RunState::PlayerTurn {
+            return;
+        }
+
+        let mut spotted = Vec::new();
+        for (entity, _hidden, pos) in (&entities, &hidden, &positions).join() {
+            let idx = map.index_from_xy(pos.x, pos.y);
+            if !map.visible_tiles[idx] {
+                continue;
+            }
+            let distance =
+                rltk::DistanceAlg::Pythagoras.distance2d((*player_pos).into(), pos.into());
+            if distance > PERCEPTION_RANGE {
+                continue;
+            }
+            if rng.roll_dice(1, PERCEPTION_CHANCE) == 1 {
+                spotted.push(entity);
+            }
+        }
+
+        for entity in spotted {
+            if let Some(name) = names.get(entity) {
+                gamelog.log(
+                    LogCategory::Status,
+                    format!("You notice a {} nearby.", name.name),
+                );
+            }
+            hidden.remove(entity);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteStorage};
+
+use crate::{
+    components::{CombatStats, Equipped, LifeRegen},
+    state::RunState,
+};
+
+#[derive(Clone, Copy)]
+pub struct RegenerationSystem;
+
+impl<'a> System<'a> for RegenerationSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        ReadStorage<'a, Equipped>,
+        WriteStorage<'a, LifeRegen>,
+        WriteStorage<'a, CombatStats>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, player, run_state, equipped, mut regens, mut combat_stats) = data;
+
+        for (_item, equipped_by, regen) in (&entities, &equipped, &mut regens).join() {
+            let proceed = match *run_state {
+                RunState::PlayerTurn => equipped_by.owner == *player,
+                RunState::MonsterTurn => equipped_by.owner != *player,
+                _ => false,
+            };
+            if !proceed {
+                continue;
+            }
+
+            if regen.countdown > 1 {
+                regen.countdown -= 1;
+                continue;
+            }
+            regen.countdown = regen.interval;
+            if let Some(stats) = combat_stats.get_mut(equipped_by.owner) {
+                stats.heal(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use std::io::{Read, Write};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+
+use super::save_slots::SlotHeader;
+use super::saveload_system::SaveError;
+
+// Binary saves start with these bytes, gzip streams with the two after them
+const BINARY_MAGIC: &[u8] = b"RLSAVE\x01";
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+// FNV-1a parameters, and a salt so the checksum isn't simply the hash of the file
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+const CHECKSUM_SALT: &[u8] = b"rougelike save";
+
+// Type tags of the binary encoding, one byte in front of every value
+const NULL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const UNSIGNED: u8 = 3;
+const NEGATIVE: u8 = 4;
+const FLOAT: u8 = 5;
+const STRING: u8 = 6;
+const ARRAY: u8 = 7;
+const OBJECT: u8 = 8;
+
+/// How a save is laid out on disk, loading tells them apart by their first bytes
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SaveEncoding {
+    /// The header on the first line and the components on the second, as JSON
+    #[default]
+    Json,
+    /// The same data as JSON, in a compact binary form with every object key stored once
+    Binary,
+}
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct SaveFormat {
+    pub encoding: SaveEncoding,
+    pub compressed: bool,
+}
+
+impl SaveFormat {
+    pub fn encode(&self, header: &SlotHeader, body: &Value) -> Result<Vec<u8>, SaveError> {
+        let mut header = header.clone();
+        header.checksum = 0;
+        let data = match self.encoding {
+            SaveEncoding::Json => {
+                let body = serde_json::to_vec(body).map_err(SaveError::Body)?;
+                header.checksum = checksum(&header, &body);
+                let mut data = serde_json::to_vec(&header).map_err(SaveError::Header)?;
+                data.push(b'\n');
+                data.extend_from_slice(&body);
+                data
+            }
+            SaveEncoding::Binary => {
+                // The header has the same keys whatever its checksum, so the body can be encoded first
+                let mut writer = BinaryWriter::default();
+                writer.value(&serde_json::to_value(&header).map_err(SaveError::Header)?);
+                let mut body_writer = BinaryWriter {
+                    data: Vec::new(),
+                    keys: writer.keys,
+                };
+                body_writer.value(body);
+                header.checksum = checksum(&header, &body_writer.data);
+
+                let mut writer = BinaryWriter::default();
+                writer.data.extend_from_slice(BINARY_MAGIC);
+                writer.value(&serde_json::to_value(&header).map_err(SaveError::Header)?);
+                writer.data.extend_from_slice(&body_writer.data);
+                writer.data
+            }
+        };
+        if !self.compressed {
+            return Ok(data);
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&data)
+            .and_then(|_| encoder.finish())
+            .map_err(|error| SaveError::Io {
+                action: "compress the save",
+                error,
+            })
+    }
+}
+
+/// The header and the components of a save in any format
+pub fn decode(data: &[u8]) -> Result<(SlotHeader, Value), SaveError> {
+    if data.starts_with(GZIP_MAGIC) {
+        return decode(&decompress(data)?);
+    }
+    if let Some(data) = data.strip_prefix(BINARY_MAGIC) {
+        let mut reader = BinaryReader::new(data);
+        let header = reader.value().map_err(SaveError::Header)?;
+        let header = serde_json::from_value(header).map_err(SaveError::Header)?;
+        verify(&header, reader.data)?;
+        let body = reader.value().map_err(SaveError::Body)?;
+        return Ok((header, body));
+    }
+
+    let text = std::str::from_utf8(data).map_err(|_| SaveError::Unrecognized)?;
+    let (header, body) = match text.split_once('\n') {
+        Some((header, body)) => {
+            let header: SlotHeader = serde_json::from_str(header).map_err(SaveError::Header)?;
+            verify(&header, body.as_bytes())?;
+            (header, body)
+        }
+        // The original game's save has no header, only its components on a single line
+        None if text.starts_with('[') => (SlotHeader::original(), text),
+        None => return Err(SaveError::Empty),
+    };
+    // Version 1 saves hold their components one after another rather than in a single object
+    let body = if header.version == 1 {
+        Value::Array(
+            serde_json::Deserializer::from_str(body)
+                .into_iter::<Value>()
+                .collect::<Result<Vec<Value>, _>>()
+                .map_err(SaveError::Body)?,
+        )
+    } else {
+        serde_json::from_str(body).map_err(SaveError::Body)?
+    };
+    Ok((header, body))
+}
+
+/// Just the header, which is all the slot browser needs
+pub fn decode_header(data: &[u8]) -> Result<SlotHeader, SaveError> {
+    if data.starts_with(GZIP_MAGIC) {
+        return decode_header(&decompress(data)?);
+    }
+    if let Some(data) = data.strip_prefix(BINARY_MAGIC) {
+        let header = BinaryReader::new(data).value().map_err(SaveError::Header)?;
+        return serde_json::from_value(header).map_err(SaveError::Header);
+    }
+    let line = data.split(|byte| *byte == b'\n').next().unwrap_or_default();
+    serde_json::from_slice(line).map_err(SaveError::Header)
+}
+
+/// A hash of the header and the encoded body, never zero as zero stands for no checksum
+fn checksum(header: &SlotHeader, body: &[u8]) -> u64 {
+    let fields = format!(
+        "{}|{}|{}|{}|{}|{}|{}",
+        header.version,
+        header.mode.name(),
+        header.character,
+        header.depth,
+        header.turns,
+        header.timestamp,
+        header.seed
+    );
+    hash(CHECKSUM_SALT.iter().chain(fields.as_bytes()).chain(body)).max(1)
+}
+
+/// FNV-1a of the bytes, what save checksums and replay checkpoints are made of
+pub fn hash<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u64 {
+    let mut hash = FNV_OFFSET;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+// Saves from before checksums have none to check, whether that's allowed is up to the loader
+fn verify(header: &SlotHeader, body: &[u8]) -> Result<(), SaveError> {
+    if header.checksum != 0 && header.checksum != checksum(header, body) {
+        return Err(SaveError::Tampered);
+    }
+    Ok(())
+}
+
+fn decompress(data: &[u8]) -> Result<Vec<u8>, SaveError> {
+    let mut decompressed = Vec::new();
+    GzDecoder::new(data)
+        .read_to_end(&mut decompressed)
+        .map_err(|error| SaveError::Io {
+            action: "decompress the save",
+            error,
+        })?;
+    Ok(decompressed)
+}
+
+#[derive(Default)]
+struct BinaryWriter {
+    data: Vec<u8>,
+    keys: Vec<String>,
+}
+
+impl BinaryWriter {
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.data.push(NULL),
+            Value::Bool(false) => self.data.push(FALSE),
+            Value::Bool(true) => self.data.push(TRUE),
+            Value::Number(number) => {
+                if let Some(unsigned) = number.as_u64() {
+                    self.data.push(UNSIGNED);
+                    self.varint(unsigned);
+                } else if let Some(negative) = number.as_i64() {
+                    self.data.push(NEGATIVE);
+                    self.varint(!negative as u64);
+                } else {
+                    self.data.push(FLOAT);
+                    let float = number.as_f64().unwrap_or_default();
+                    self.data.extend_from_slice(&float.to_le_bytes());
+                }
+            }
+            Value::String(text) => {
+                self.data.push(STRING);
+                self.string(text);
+            }
+            Value::Array(items) => {
+                self.data.push(ARRAY);
+                self.varint(items.len() as u64);
+                for item in items {
+                    self.value(item);
+                }
+            }
+            Value::Object(fields) => {
+                self.data.push(OBJECT);
+                self.varint(fields.len() as u64);
+                for (key, field) in fields {
+                    self.key(key);
+                    self.value(field);
+                }
+            }
+        }
+    }
+
+    // A key seen before is written as its index plus one, a new one as a zero and the key itself
+    fn key(&mut self, key: &str) {
+        match self.keys.iter().position(|known| known == key) {
+            Some(index) => self.varint(index as u64 + 1),
+            None => {
+                self.varint(0);
+                self.string(key);
+                self.keys.push(key.to_string());
+            }
+        }
+    }
+
+    fn string(&mut self, text: &str) {
+        self.varint(text.len() as u64);
+        self.data.extend_from_slice(text.as_bytes());
+    }
+
+    // Seven bits per byte, the high bit set on every byte but the last
+    fn varint(&mut self, mut number: u64) {
+        while number >= 0x80 {
+            self.data.push(number as u8 | 0x80);
+            number >>= 7;
+        }
+        self.data.push(number as u8);
+    }
+}
+
+struct BinaryReader<'a> {
+    data: &'a [u8],
+    keys: Vec<String>,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            keys: Vec::new(),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, serde_json::Error> {
+        let value = match self.byte()? {
+            NULL => Value::Null,
+            FALSE => Value::Bool(false),
+            TRUE => Value::Bool(true),
+            UNSIGNED => Value::from(self.varint()?),
+            NEGATIVE => Value::from(!self.varint()? as i64),
+            FLOAT => {
+                let bytes = self.take(8)?;
+                let float = f64::from_le_bytes(bytes.try_into().unwrap());
+                Number::from_f64(float).map_or(Value::Null, Value::Number)
+            }
+            STRING => Value::String(self.string()?),
+            ARRAY => {
+                let len = self.varint()? as usize;
+                let mut items = Vec::with_capacity(len.min(self.data.len()));
+                for _ in 0..len {
+                    items.push(self.value()?);
+                }
+                Value::Array(items)
+            }
+            OBJECT => {
+                let len = self.varint()?;
+                let mut fields = Map::new();
+                for _ in 0..len {
+                    let key = self.key()?;
+                    fields.insert(key, self.value()?);
+                }
+                Value::Object(fields)
+            }
+            tag => return Err(invalid(&format!("unknown type tag {}", tag))),
+        };
+        Ok(value)
+    }
+
+    fn key(&mut self) -> Result<String, serde_json::Error> {
+        match self.varint()? as usize {
+            0 => {
+                let key = self.string()?;
+                self.keys.push(key.clone());
+                Ok(key)
+            }
+            index => self
+                .keys
+                .get(index - 1)
+                .cloned()
+                .ok_or_else(|| invalid("a key refers to nothing")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, serde_json::Error> {
+        let len = self.varint()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid("a string isn't UTF-8"))
+    }
+
+    fn varint(&mut self) -> Result<u64, serde_json::Error> {
+        let mut number = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            number |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(number);
+            }
+        }
+        Err(invalid("a number is too long"))
+    }
+
+    fn byte(&mut self) -> Result<u8, serde_json::Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], serde_json::Error> {
+        if self.data.len() < len {
+            return Err(invalid("the save ends too soon"));
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+}
+
+// Binary decoding errors share the error type of JSON ones, so both read the same in SaveError
+fn invalid(reason: &str) -> serde_json::Error {
+    serde::de::Error::custom(reason)
+}
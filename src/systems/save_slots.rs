@@ -0,0 +1,258 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use specs::{World, WorldExt};
+
+use crate::components::{GameMode, Name, RunStats};
+use crate::gamelog::GameLog;
+use crate::map::map::Map;
+
+use super::save_format;
+use super::saveload_system::{SaveError, SAVE_VERSION};
+
+// Folder inside the platform's data directory that holds the save slots
+const GAME_FOLDER: &str = "rougelike";
+
+// Extensions of slot files, saves use the first one and the others are from older versions
+const SLOT_EXTENSIONS: &[&str] = &["sav", "json"];
+
+// Longest name the player can give a slot
+pub const MAX_SLOT_NAME: usize = 24;
+
+/// What the browser shows about a slot, written as the first line of the save file
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SlotHeader {
+    // Slots written before the format was versioned have no version in their header
+    #[serde(default = "first_version")]
+    pub version: u32,
+    pub character: String,
+    pub depth: u32,
+    pub turns: u32,
+    pub timestamp: u64,
+    pub seed: u64,
+    #[serde(default)]
+    pub mode: GameMode,
+    /// Ties the header to the rest of the save so edits can be caught, zero in saves from before it
+    #[serde(default)]
+    pub checksum: u64,
+}
+
+impl SlotHeader {
+    pub fn from_ecs(ecs: &World) -> Self {
+        let player = *ecs.fetch::<specs::Entity>();
+        let character = ecs
+            .read_storage::<Name>()
+            .get(player)
+            .map_or_else(String::new, |name| name.name.clone());
+        let (seed, mode) = ecs
+            .read_storage::<RunStats>()
+            .get(player)
+            .map_or((0, GameMode::default()), |run_stats| {
+                (run_stats.seed, run_stats.mode)
+            });
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self {
+            version: SAVE_VERSION,
+            character,
+            depth: ecs.fetch::<Map>().depth,
+            turns: ecs.fetch::<GameLog>().turn,
+            timestamp,
+            seed,
+            mode,
+            checksum: 0,
+        }
+    }
+
+    /// Stands in for the header of a save from the original game, which had none
+    pub fn original() -> Self {
+        Self {
+            version: first_version(),
+            character: String::new(),
+            depth: 0,
+            turns: 0,
+            timestamp: 0,
+            seed: 0,
+            mode: GameMode::Casual,
+            checksum: 0,
+        }
+    }
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+pub struct SaveSlot {
+    pub name: String,
+    /// None when the file is there but its header can't be read
+    pub header: Option<SlotHeader>,
+}
+
+/// The game's folder in the user's data directory, or in the working directory when there is none
+pub fn game_dir() -> PathBuf {
+    // Tests get a folder of their own, so they never touch the player's saves
+    if cfg!(test) {
+        return env::temp_dir().join(format!("{}-test", GAME_FOLDER));
+    }
+    let data_dir = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    data_dir
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(GAME_FOLDER)
+}
+
+/// Where the slots live
+pub fn save_dir() -> PathBuf {
+    game_dir().join("saves")
+}
+
+/// The file a slot is saved to
+pub fn slot_path(name: &str) -> PathBuf {
+    save_dir().join(format!("{}.{}", name, SLOT_EXTENSIONS[0]))
+}
+
+/// The file a slot is loaded from, which may still have an older extension
+pub fn existing_slot_path(name: &str) -> Option<PathBuf> {
+    SLOT_EXTENSIONS
+        .iter()
+        .map(|extension| save_dir().join(format!("{}.{}", name, extension)))
+        .find(|path| path.exists())
+}
+
+fn is_slot_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| SLOT_EXTENSIONS.contains(&extension))
+}
+
+/// Slot names double as file names, so only a safe set of characters is allowed
+pub fn is_slot_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_'
+}
+
+pub fn read_header(name: &str) -> Option<SlotHeader> {
+    let data = fs::read(existing_slot_path(name)?).ok()?;
+    save_format::decode_header(&data).ok()
+}
+
+/// Every slot in the save directory, the most recently saved first
+pub fn list_slots() -> Vec<SaveSlot> {
+    let entries = match fs::read_dir(save_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut slots: Vec<SaveSlot> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| is_slot_file(path))
+        .filter_map(|path| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .map(|stem| stem.to_string())
+        })
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .map(|name| SaveSlot {
+            header: read_header(&name),
+            name,
+        })
+        .collect();
+    slots.sort_by_key(|slot| {
+        std::cmp::Reverse(slot.header.as_ref().map_or(0, |header| header.timestamp))
+    });
+    slots
+}
+
+/// Whether there is anything to load, without reading the slots themselves
+pub fn any_slots() -> bool {
+    fs::read_dir(save_dir()).is_ok_and(|mut entries| {
+        entries.any(|entry| entry.is_ok_and(|entry| is_slot_file(&entry.path())))
+    })
+}
+
+/// Removes the slot, along with any copy of it under an older extension
+pub fn delete_slot(name: &str) -> Result<(), SaveError> {
+    while let Some(path) = existing_slot_path(name) {
+        fs::remove_file(path).map_err(|error| SaveError::Io {
+            action: "delete the slot",
+            error,
+        })?;
+    }
+    Ok(())
+}
+
+/// Removes every slot holding the strict run with this seed, apart from the one to keep
+pub fn delete_strict_run(seed: u64, keep: Option<&str>) -> Result<(), SaveError> {
+    for slot in list_slots() {
+        let of_run = slot
+            .header
+            .as_ref()
+            .is_some_and(|header| header.mode == GameMode::Strict && header.seed == seed);
+        if of_run && keep != Some(slot.name.as_str()) {
+            delete_slot(&slot.name)?;
+        }
+    }
+    Ok(())
+}
+
+/// Seconds since the epoch as a UTC date and time
+pub fn format_timestamp(timestamp: u64) -> String {
+    let (year, month, day, hours, minutes, _) = calendar(timestamp);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year, month, day, hours, minutes
+    )
+}
+
+/// Seconds since the epoch as a UTC date and time that can go in a file name
+pub fn file_timestamp(timestamp: u64) -> String {
+    let (year, month, day, hours, minutes, seconds) = calendar(timestamp);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year, month, day, hours, minutes, seconds
+    )
+}
+
+// Year, month, day, hours, minutes and seconds in UTC
+fn calendar(timestamp: u64) -> (i64, i64, i64, u64, u64, u64) {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+
+    // Days since 1970-01-01 to a calendar date, after Howard Hinnant's civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60,
+    )
+}
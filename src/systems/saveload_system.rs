@@ -1,6 +1,9 @@
+use std::fmt;
 use std::fs::{self, File};
+use std::io::{self, Write};
 use std::path::Path;
 
+use serde_json::Value;
 use specs::{
     error::NoError,
     saveload::{
@@ -11,103 +14,98 @@ use specs::{
 };
 
 use crate::components::{
-    AreaOfEffect, BlocksTile, CombatStats, Confusion, Consumable, DefenseBonus, EntityMoved,
-    EntryTrigger, Equippable, Equipped, GameLogSerializationHelper, Hidden, HungerClock,
-    InBackpack, InflictsDamage, Item, Lifetime, MapSerializationHelper, MeleePowerBonus, Monster,
-    Name, Particle, Player, Position, ProvidesFood, ProvidesHealing, Ranged, Renderable,
-    SerializeMe, SingleActivation, SufferDamage, Viewshed, WantsToDropItem, WantsToMelee,
-    WantsToPickupItem, WantsToUnequipItem, WantsToUseItem,
+    self, register_components, Alarm, Alerted, AreaOfEffect, BaseValue, BlocksTile,
+    BlocksVisibility, CombatStats, Confusion, Consumable, DefenseBonus, Description, Door,
+    EntityMoved, EntryTrigger, Equippable, Equipped, Freshness, GameLogSerializationHelper,
+    GameMode, Gold, Hidden, HungerClock, InBackpack, InflictsDamage, Item, Key, LeavesCorpse,
+    LifeRegen, Lifetime, Locked, MapSerializationHelper, MeleePowerBonus, Monster, Name, Particle,
+    Pit, Player, Position, ProvidesFood, ProvidesHealing, Ranged, Rarity, Renderable, Resistance,
+    RunStats, SerializeMe, SingleActivation, SufferDamage, Teleports, Vendor, Viewshed,
+    VisionBonus, Wallet, WantsToCloseDoor, WantsToDescend, WantsToDropItem, WantsToMelee,
+    WantsToOpenDoor, WantsToPickupItem, WantsToUnequipItem, WantsToUseItem,
 };
 
-use crate::gamelog::GameLog;
+use crate::automove::AutoMove;
+use crate::gamelog::{GameLog, LogCategory};
 use crate::map::map::Map;
+use crate::menu::target_menu::LastTarget;
+use crate::replay::Recorder;
+use crate::settings::Settings;
 
+use super::autosave;
+use super::lifetime_system::LifetimeClock;
 use super::particle_system::ParticleBuilder;
+use super::save_format::{self, SaveFormat};
+use super::save_slots::{self, SlotHeader};
+use super::trigger_system::SprungTraps;
 
-const SAVE_FILE: &str = "./savegame.json";
+// Format written into new saves, bump it and add a migration whenever saved data changes shape
+pub const SAVE_VERSION: u32 = 3;
 
-macro_rules! serialize_individually {
-    ($ecs:expr, $ser:expr, $data:expr, $( $type:ty),*) => {
-        $(
-        SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize(
-            &( $ecs.read_storage::<$type>(), ),
-            &$data.0,
-            &$data.1,
-            &mut $ser,
-        )
-        .unwrap();
-        )*
-    };
-}
+// Version 1 is the original save from before slots, its components one after another in this
+// order without their names. Every component added since is simply missing from it
+const V1_COMPONENTS: &[&str] = &[
+    "Position",
+    "Renderable",
+    "Player",
+    "Viewshed",
+    "Monster",
+    "Name",
+    "BlocksTile",
+    "CombatStats",
+    "SufferDamage",
+    "WantsToMelee",
+    "Item",
+    "Consumable",
+    "Ranged",
+    "InflictsDamage",
+    "AreaOfEffect",
+    "Confusion",
+    "ProvidesHealing",
+    "InBackpack",
+    "WantsToPickupItem",
+    "WantsToUseItem",
+    "WantsToDropItem",
+    "MapSerializationHelper",
+    "GameLogSerializationHelper",
+    "Equippable",
+    "Equipped",
+    "MeleePowerBonus",
+    "DefenseBonus",
+    "WantsToUnequipItem",
+    "Lifetime",
+    "Particle",
+    "HungerClock",
+    "ProvidesFood",
+    "Hidden",
+    "EntryTrigger",
+    "EntityMoved",
+    "SingleActivation",
+];
 
-macro_rules! deserialize_individually {
-    ($ecs:expr, $de:expr, $data:expr, $( $type:ty),*) => {
-        $(
-        DeserializeComponents::<NoError, _>::deserialize(
-            &mut ( &mut $ecs.write_storage::<$type>(), ),
-            &mut $data.0, // entities
-            &mut $data.1, // marker
-            &mut $data.2, // allocater
-            &mut $de,
-        )
-        .unwrap();
-        )*
-    };
-}
+// Where the original game kept its only save, and the slot it moves to when the game finds it
+pub const ORIGINAL_SAVE_FILE: &str = "./savegame.json";
+pub const IMPORTED_SLOT: &str = "imported";
 
-pub fn does_save_exist() -> bool {
-    Path::new(SAVE_FILE).exists()
-}
-
-pub fn delete_save() {
-    let _ = std::fs::remove_file(SAVE_FILE);
-}
+/// Saved components keyed by their type name
+pub type TaggedComponents = serde_json::Map<String, Value>;
 
-pub fn save_game(ecs: &mut World) {
-    // Create helper
-    let mut save_helpers = Vec::new();
-    {
-        let map_copy = ecs.get_mut::<Map>().unwrap().clone();
-        let save_helper = ecs
-            .create_entity()
-            .with(MapSerializationHelper { map: map_copy })
-            .marked::<SimpleMarker<SerializeMe>>()
-            .build();
-        save_helpers.push(save_helper);
-    }
-    {
-        let gamelog_copy = ecs.get_mut::<GameLog>().unwrap().clone();
-        let save_helper = ecs
-            .create_entity()
-            .with(GameLogSerializationHelper {
-                gamelog: gamelog_copy,
-            })
-            .marked::<SimpleMarker<SerializeMe>>()
-            .build();
-        save_helpers.push(save_helper);
-    }
-    // Actually serialize
-    {
-        let data = (
-            ecs.entities(),
-            ecs.read_storage::<SimpleMarker<SerializeMe>>(),
-        );
-
-        let writer = File::create(SAVE_FILE).unwrap();
-        let mut serializer = serde_json::Serializer::new(writer);
-        serialize_individually!(
-            ecs,
-            serializer,
-            data,
+/// Calls the macro with every component that goes into a save
+macro_rules! with_saved_components {
+    ($action:ident!($($args:tt)*)) => {
+        $action!(
+            $($args)*;
             Position,
             Renderable,
             Player,
             Viewshed,
             Monster,
             Name,
+            Description,
             BlocksTile,
             CombatStats,
             SufferDamage,
+            RunStats,
             WantsToMelee,
             Item,
             Consumable,
@@ -134,73 +132,356 @@ pub fn save_game(ecs: &mut World) {
             Hidden,
             EntryTrigger,
             EntityMoved,
-            SingleActivation
+            SingleActivation,
+            Wallet,
+            Gold,
+            BaseValue,
+            Vendor,
+            Rarity,
+            Resistance,
+            LifeRegen,
+            VisionBonus,
+            LeavesCorpse,
+            Freshness,
+            Teleports,
+            Alarm,
+            Pit,
+            Alerted,
+            WantsToDescend,
+            Door,
+            Locked,
+            BlocksVisibility,
+            Key,
+            WantsToOpenDoor,
+            WantsToCloseDoor
+        )
+    };
+}
+
+macro_rules! serialize_individually {
+    ($ecs:expr, $components:expr, $data:expr; $( $type:ty),*) => {
+        $(
+        let value = SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize(
+            &( $ecs.read_storage::<$type>(), ),
+            &$data.0,
+            &$data.1,
+            serde_json::value::Serializer,
+        )
+        .map_err(|error| SaveError::Component { name: stringify!($type), error });
+        $components.push((stringify!($type), value));
+        )*
+    };
+}
+
+macro_rules! deserialize_individually {
+    ($ecs:expr, $components:expr, $data:expr; $( $type:ty),*) => {
+        $(
+        // A component missing from the save just means no entity had it
+        if let Some(value) = $components.remove(stringify!($type)) {
+            DeserializeComponents::<NoError, _>::deserialize(
+                &mut ( &mut $ecs.write_storage::<$type>(), ),
+                &mut $data.0, // entities
+                &mut $data.1, // marker
+                &mut $data.2, // allocater
+                value,
+            )
+            .map_err(|error| SaveError::Component { name: stringify!($type), error })?;
+        }
+        )*
+    };
+}
+
+macro_rules! component_names {
+    (; $( $type:ty),*) => {
+        &[$( stringify!($type) ),*]
+    };
+}
+
+// Every tag a save of the current version may contain
+const SAVED_COMPONENTS: &[&str] = with_saved_components!(component_names!());
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io {
+        action: &'static str,
+        error: io::Error,
+    },
+    Empty,
+    Unrecognized,
+    Header(serde_json::Error),
+    Body(serde_json::Error),
+    NewerVersion(u32),
+    UnknownVersion(u32),
+    ComponentCount {
+        found: usize,
+        expected: usize,
+    },
+    UnknownComponent(String),
+    Component {
+        name: &'static str,
+        error: serde_json::Error,
+    },
+    Missing(&'static str),
+    PlayerCount(usize),
+    MapSize {
+        width: u16,
+        height: u16,
+        tiles: usize,
+    },
+    MissingOwner(&'static str),
+    Tampered,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io { action, error } => write!(f, "Couldn't {}: {}", action, error),
+            SaveError::Empty => write!(f, "The save is empty."),
+            SaveError::Unrecognized => write!(f, "The file isn't a save."),
+            SaveError::Header(error) => write!(f, "The save's header is broken: {}", error),
+            SaveError::Body(error) => write!(f, "The save is broken: {}", error),
+            SaveError::NewerVersion(version) => write!(
+                f,
+                "The save was made by a newer version of the game (format {}).",
+                version
+            ),
+            SaveError::UnknownVersion(version) => {
+                write!(f, "The save has an unknown format {}.", version)
+            }
+            SaveError::ComponentCount { found, expected } => write!(
+                f,
+                "The save has {} components where {} were expected.",
+                found, expected
+            ),
+            SaveError::UnknownComponent(name) => {
+                write!(f, "The save has an unknown component {}.", name)
+            }
+            SaveError::Component { name, error } => {
+                write!(f, "The save's {} data is broken: {}", name, error)
+            }
+            SaveError::Missing(what) => write!(f, "The save has no {}.", what),
+            SaveError::PlayerCount(count) => {
+                write!(f, "The save has {} players instead of one.", count)
+            }
+            SaveError::MapSize {
+                width,
+                height,
+                tiles,
+            } => write!(
+                f,
+                "The save's {}x{} map has {} tiles.",
+                width, height, tiles
+            ),
+            SaveError::MissingOwner(component) => {
+                write!(f, "The save has a {} that belongs to no one.", component)
+            }
+            SaveError::Tampered => write!(f, "The save was changed after the game wrote it."),
+        }
+    }
+}
+
+/// Write the game to the named slot, replacing whatever was saved there
+pub fn save_game(ecs: &mut World, slot: &str) -> Result<(), SaveError> {
+    let format = ecs.fetch::<Settings>().save_format();
+    let data = save_to_bytes(ecs, format)?;
+    write_slot(slot, &data)?;
+
+    // A strict run lives in a single slot, so saving it elsewhere moves it
+    let run = run_stats(ecs);
+    if run.mode == GameMode::Strict {
+        save_slots::delete_strict_run(run.seed, Some(slot))?;
+    }
+    Ok(())
+}
+
+/// Write an encoded save to the named slot, this needs no world so it can run on any thread
+pub fn write_slot(slot: &str, data: &[u8]) -> Result<(), SaveError> {
+    fs::create_dir_all(save_slots::save_dir()).map_err(|error| SaveError::Io {
+        action: "create the save directory",
+        error,
+    })?;
+
+    // The save goes to a file of its own first, so a failed write never leaves half a slot behind
+    let path = save_slots::slot_path(slot);
+    let temp_path = path.with_extension("tmp");
+    let written = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, &path));
+    if let Err(error) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(SaveError::Io {
+            action: "write the save",
+            error,
+        });
+    }
+
+    // A copy of the slot under an older extension would otherwise shadow nothing but disk space
+    if let Some(older) = save_slots::existing_slot_path(slot).filter(|older| *older != path) {
+        let _ = fs::remove_file(older);
+    }
+    Ok(())
+}
+
+pub fn save_to_bytes(ecs: &mut World, format: SaveFormat) -> Result<Vec<u8>, SaveError> {
+    let header = SlotHeader::from_ecs(ecs);
+    let components = snapshot(ecs)?;
+    format.encode(&header, &Value::Object(components))
+}
+
+/// Every saved component under its name
+pub fn snapshot(ecs: &mut World) -> Result<TaggedComponents, SaveError> {
+    let map_copy = ecs
+        .get_mut::<Map>()
+        .ok_or(SaveError::Missing("map"))?
+        .clone();
+    let gamelog_copy = ecs
+        .get_mut::<GameLog>()
+        .ok_or(SaveError::Missing("message log"))?
+        .clone();
+    // Taking a snapshot leaves no trace, so a run plays out the same whether it saves or not
+    let markers: SimpleMarkerAllocator<SerializeMe> =
+        (*ecs.fetch::<SimpleMarkerAllocator<SerializeMe>>()).clone();
+
+    // Create helper
+    let save_helpers = vec![
+        ecs.create_entity()
+            .with(MapSerializationHelper { map: map_copy })
+            .marked::<SimpleMarker<SerializeMe>>()
+            .build(),
+        ecs.create_entity()
+            .with(GameLogSerializationHelper {
+                gamelog: gamelog_copy,
+            })
+            .marked::<SimpleMarker<SerializeMe>>()
+            .build(),
+    ];
+    // Actually serialize
+    let mut serialized = Vec::new();
+    {
+        let data = (
+            ecs.entities(),
+            ecs.read_storage::<SimpleMarker<SerializeMe>>(),
         );
+        with_saved_components!(serialize_individually!(ecs, serialized, data));
     }
 
-    // Clean up
-    for save_helper in save_helpers {
+    // Clean up, last one first so the next entities get the same ids as without the helpers
+    for save_helper in save_helpers.into_iter().rev() {
         ecs.delete_entity(save_helper).expect("Crash on cleanup");
     }
+    ecs.insert(markers);
+
+    serialized
+        .into_iter()
+        .map(|(tag, value)| value.map(|value| (tag.to_string(), value)))
+        .collect()
 }
 
-pub fn load_game(ecs: &mut World) {
-    ecs.delete_all();
-    let data = fs::read_to_string(SAVE_FILE).unwrap();
-    let mut de = serde_json::Deserializer::from_str(&data);
+/// Replace the world with the one saved in the named slot, the slot itself is left alone
+pub fn load_game(ecs: &mut World, slot: &str) -> Result<(), SaveError> {
+    let path = save_slots::existing_slot_path(slot).unwrap_or_else(|| save_slots::slot_path(slot));
+    let data = fs::read(path).map_err(|error| SaveError::Io {
+        action: "read the save",
+        error,
+    })?;
+    load_from_bytes(ecs, &data)?;
+
+    // Loading a strict run uses its save up, it only comes back by saving again
+    let run = run_stats(ecs);
+    if run.mode == GameMode::Strict {
+        save_slots::delete_strict_run(run.seed, None)?;
+    }
+    Ok(())
+}
+
+/// Replace the world with a saved one in any format, a save that fails to load leaves the world untouched
+pub fn load_from_bytes(ecs: &mut World, data: &[u8]) -> Result<(), SaveError> {
+    let (header, body) = save_format::decode(data)?;
+    let components = migrate(header.version, body)?;
+    if let Some(unknown) = components
+        .keys()
+        .find(|tag| !SAVED_COMPONENTS.contains(&tag.as_str()))
+    {
+        return Err(SaveError::UnknownComponent(unknown.clone()));
+    }
+
+    // Try the save on a world of its own before the real one is cleared
+    let mut scratch = World::new();
+    register_components(&mut scratch);
+    scratch.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    restore(&mut scratch, components.clone())?;
+    validate(&scratch)?;
+    // Only saves from before checksums lack one, and no strict run is that old
+    if header.checksum == 0 && run_stats(&scratch).mode == GameMode::Strict {
+        return Err(SaveError::Tampered);
+    }
+
+    restore(ecs, components)
+}
+
+/// Moves a save of the original game into a slot of its own, so the slot browser lists it
+///
+/// The old file stays where it was under another name, which also keeps it from being imported twice.
+/// Returns whether there was anything to import.
+pub fn import_original_save(path: &Path, format: SaveFormat) -> Result<bool, SaveError> {
+    if !path.exists() || save_slots::existing_slot_path(IMPORTED_SLOT).is_some() {
+        return Ok(false);
+    }
+    let data = fs::read(path).map_err(|error| SaveError::Io {
+        action: "read the old save",
+        error,
+    })?;
+    let mut ecs = components::new_world();
+    load_from_bytes(&mut ecs, &data)?;
+    write_slot(IMPORTED_SLOT, &save_to_bytes(&mut ecs, format)?)?;
+    fs::rename(path, path.with_extension("json.imported")).map_err(|error| SaveError::Io {
+        action: "put the old save aside",
+        error,
+    })?;
+    Ok(true)
+}
+
+/// The player's run, or a blank casual one when there is no player
+pub fn run_stats(ecs: &World) -> RunStats {
+    let player = ecs.try_fetch::<Entity>().map(|player| *player);
+    player
+        .and_then(|player| ecs.read_storage::<RunStats>().get(player).cloned())
+        .unwrap_or_default()
+}
+
+/// A strict run that ends, by dying or by starting over, takes its saves with it
+pub fn forfeit_run(ecs: &mut World) {
+    autosave::wait_for_writer(ecs);
+    let run = run_stats(ecs);
+    if run.mode != GameMode::Strict {
+        return;
+    }
+    if let Err(error) = save_slots::delete_strict_run(run.seed, None) {
+        ecs.fetch_mut::<GameLog>().log(
+            LogCategory::System,
+            format!("The run's saves couldn't be removed: {}", error),
+        );
+    }
+}
 
+fn restore(ecs: &mut World, mut components: TaggedComponents) -> Result<(), SaveError> {
+    ecs.delete_all();
     {
         let mut d = (
             &mut ecs.entities(),
             &mut ecs.write_storage::<SimpleMarker<SerializeMe>>(),
             &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>(),
         );
-        deserialize_individually!(
-            ecs,
-            de,
-            d,
-            Position,
-            Renderable,
-            Player,
-            Viewshed,
-            Monster,
-            Name,
-            BlocksTile,
-            CombatStats,
-            SufferDamage,
-            WantsToMelee,
-            Item,
-            Consumable,
-            Ranged,
-            InflictsDamage,
-            AreaOfEffect,
-            Confusion,
-            ProvidesHealing,
-            InBackpack,
-            WantsToPickupItem,
-            WantsToUseItem,
-            WantsToDropItem,
-            MapSerializationHelper,
-            GameLogSerializationHelper,
-            Equippable,
-            Equipped,
-            MeleePowerBonus,
-            DefenseBonus,
-            WantsToUnequipItem,
-            Lifetime,
-            Particle,
-            HungerClock,
-            ProvidesFood,
-            Hidden,
-            EntryTrigger,
-            EntityMoved,
-            SingleActivation
-        );
+        with_saved_components!(deserialize_individually!(ecs, components, d));
     }
 
     let mut helpers_to_delete = Vec::new();
     let mut loaded_map = None;
     let mut loaded_gamelog = None;
+    let mut loaded_player = None;
     {
         let entities = ecs.entities();
         let map_helper = ecs.read_storage::<MapSerializationHelper>();
@@ -221,10 +502,7 @@ pub fn load_game(ecs: &mut World) {
         let player = ecs.read_storage::<Player>();
         let position = ecs.read_storage::<Position>();
         for (e, _p, pos) in (&entities, &player, &position).join() {
-            let mut player_pos = ecs.write_resource::<Position>();
-            *player_pos = *pos;
-            let mut player_resource = ecs.write_resource::<Entity>();
-            *player_resource = e;
+            loaded_player = Some((e, *pos));
         }
     }
 
@@ -232,7 +510,778 @@ pub fn load_game(ecs: &mut World) {
         ecs.delete_entity(delete_entity)
             .expect("Unable to delete helper");
     }
-    ecs.insert(loaded_map.unwrap());
-    ecs.insert(loaded_gamelog.unwrap());
+    let (player_entity, player_pos) = loaded_player.ok_or(SaveError::Missing("player"))?;
+    ecs.insert(player_entity);
+    ecs.insert(player_pos);
+    ecs.insert(loaded_map.ok_or(SaveError::Missing("map"))?);
+    ecs.insert(loaded_gamelog.ok_or(SaveError::Missing("message log"))?);
     ecs.insert(ParticleBuilder::new());
+    ecs.insert(SprungTraps::default());
+    ecs.insert(LifetimeClock::default());
+    ecs.insert(LastTarget::default());
+    ecs.insert(AutoMove::default());
+    // A loaded run didn't start from its seed here, so its recording can't go on
+    ecs.remove::<Recorder>();
+    Ok(())
+}
+
+// Things a save must hold true to be playable, beyond being well formed
+fn validate(ecs: &World) -> Result<(), SaveError> {
+    let players = ecs.read_storage::<Player>().join().count();
+    if players != 1 {
+        return Err(SaveError::PlayerCount(players));
+    }
+
+    let map = ecs.fetch::<Map>();
+    let size = map.width as usize * map.height as usize;
+    let layers = [
+        map.tiles.len(),
+        map.revealed_tiles.len(),
+        map.visible_tiles.len(),
+        map.blocked.len(),
+    ];
+    if size == 0 || layers.iter().any(|len| *len != size) {
+        return Err(SaveError::MapSize {
+            width: map.width,
+            height: map.height,
+            tiles: map.tiles.len(),
+        });
+    }
+
+    // An owner that wasn't saved comes back as a blank entity without even a name
+    let names = ecs.read_storage::<Name>();
+    if ecs
+        .read_storage::<InBackpack>()
+        .join()
+        .any(|pack| !names.contains(pack.owner))
+    {
+        return Err(SaveError::MissingOwner("backpack item"));
+    }
+    if ecs
+        .read_storage::<Equipped>()
+        .join()
+        .any(|gear| !names.contains(gear.owner))
+    {
+        return Err(SaveError::MissingOwner("piece of equipment"));
+    }
+    Ok(())
+}
+
+/// Brings the components of a save written by an older version up to the current format.
+/// A new version adds an arm here that upgrades the previous version's components.
+fn migrate(version: u32, body: Value) -> Result<TaggedComponents, SaveError> {
+    let mut components = match version {
+        1 => tag_by_position(body, V1_COMPONENTS)?,
+        2..=SAVE_VERSION => serde_json::from_value(body).map_err(SaveError::Body)?,
+        newer if newer > SAVE_VERSION => return Err(SaveError::NewerVersion(newer)),
+        unknown => return Err(SaveError::UnknownVersion(unknown)),
+    };
+    if version < 2 {
+        upgrade_original_save(&mut components)?;
+    }
+    if version < 3 {
+        add_damage_causes(&mut components);
+    }
+    Ok(components)
+}
+
+// The original game logged plain lines, didn't know who dealt damage and gave the player no
+// wallet or run stats, version 2 has all of them
+fn upgrade_original_save(components: &mut TaggedComponents) -> Result<(), SaveError> {
+    for damage in saved_values(components, "SufferDamage") {
+        damage.insert("by_player".to_string(), Value::Bool(false));
+    }
+
+    for helper in saved_values(components, "GameLogSerializationHelper") {
+        let lines = helper
+            .get_mut("gamelog")
+            .and_then(|gamelog| gamelog.get_mut("entries"))
+            .map(Value::take)
+            .unwrap_or_default();
+        let lines: Vec<String> = serde_json::from_value(lines).map_err(SaveError::Body)?;
+        let mut gamelog = GameLog::new();
+        for line in lines {
+            gamelog.log(LogCategory::System, line);
+        }
+        let gamelog = serde_json::to_value(gamelog).map_err(SaveError::Body)?;
+        helper.insert("gamelog".to_string(), gamelog);
+    }
+
+    let depth = saved_values(components, "MapSerializationHelper")
+        .find_map(|helper| helper.get("map")?.get("depth")?.as_u64())
+        .unwrap_or(1) as u32;
+    let player = components
+        .get("Player")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .find(|entry| !entry["components"][0].is_null())
+        .map(|entry| entry["marker"].clone())
+        .ok_or(SaveError::Missing("player"))?;
+    let run_stats = RunStats {
+        deepest_depth: depth,
+        ..RunStats::default()
+    };
+    let run_stats = serde_json::to_value(run_stats).map_err(SaveError::Body)?;
+    add_saved_value(components, "RunStats", &player, run_stats);
+    add_saved_value(
+        components,
+        "Wallet",
+        &player,
+        serde_json::json!({ "gold": 0 }),
+    );
+    Ok(())
+}
+
+// Version 3 saves what dealt each amount of damage, older damage was dealt by no one in particular
+fn add_damage_causes(components: &mut TaggedComponents) {
+    for damage in saved_values(components, "SufferDamage") {
+        damage.insert("causes".to_string(), Value::Array(Vec::new()));
+    }
+}
+
+// What every entity saved with the component holds, for migrations to change in place
+fn saved_values<'a>(
+    components: &'a mut TaggedComponents,
+    tag: &str,
+) -> impl Iterator<Item = &'a mut serde_json::Map<String, Value>> {
+    components
+        .get_mut(tag)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.get_mut("components")?.get_mut(0)?.as_object_mut())
+}
+
+fn add_saved_value(components: &mut TaggedComponents, tag: &str, marker: &Value, value: Value) {
+    let entry = serde_json::json!({ "marker": marker, "components": [value] });
+    match components.get_mut(tag).and_then(Value::as_array_mut) {
+        Some(entries) => entries.push(entry),
+        None => {
+            components.insert(tag.to_string(), Value::Array(vec![entry]));
+        }
+    }
+}
+
+// Names the components of a save that stored them one after another
+fn tag_by_position(body: Value, order: &[&str]) -> Result<TaggedComponents, SaveError> {
+    let values: Vec<Value> = serde_json::from_value(body).map_err(SaveError::Body)?;
+    if values.len() != order.len() {
+        return Err(SaveError::ComponentCount {
+            found: values.len(),
+            expected: order.len(),
+        });
+    }
+    Ok(order
+        .iter()
+        .map(|tag| tag.to_string())
+        .zip(values)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use rltk::RandomNumberGenerator;
+    use specs::Entity;
+
+    use super::*;
+    use crate::components::{self, Item, RarityTier};
+    use crate::gamelog::{LogCategory, LogEntry};
+    use crate::map::map::TileType;
+    use crate::spawn::spawner;
+    use crate::systems::save_format::SaveEncoding;
+
+    // Saves written by every version of the format, all of which must keep loading
+    const CORPUS: &[(u32, &str)] = &[
+        (1, include_str!("../../tests/saves/v1.json")),
+        (2, include_str!("../../tests/saves/v2.json")),
+        (3, include_str!("../../tests/saves/v3.json")),
+    ];
+
+    // Every encoding, with and without compression
+    const FORMATS: [SaveFormat; 4] = [
+        SaveFormat {
+            encoding: SaveEncoding::Json,
+            compressed: false,
+        },
+        SaveFormat {
+            encoding: SaveEncoding::Json,
+            compressed: true,
+        },
+        SaveFormat {
+            encoding: SaveEncoding::Binary,
+            compressed: false,
+        },
+        SaveFormat {
+            encoding: SaveEncoding::Binary,
+            compressed: true,
+        },
+    ];
+
+    // The default format, which is plain JSON text
+    fn save_to_string(ecs: &mut World) -> Result<String, SaveError> {
+        save_to_bytes(ecs, SaveFormat::default()).map(|data| String::from_utf8(data).unwrap())
+    }
+
+    fn load_from_str(ecs: &mut World, data: &str) -> Result<(), SaveError> {
+        load_from_bytes(ecs, data.as_bytes())
+    }
+
+    fn empty_world() -> World {
+        let mut ecs = World::new();
+        components::register_components(&mut ecs);
+        ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+        ecs
+    }
+
+    // A small level with one of everything, a full backpack and equipped gear
+    fn sample_world() -> World {
+        let mut ecs = empty_world();
+        ecs.insert(RandomNumberGenerator::seeded(7));
+
+        let mut map = Map::empty_map(3, 16, 10);
+        for y in 1..map.height - 1 {
+            for x in 1..map.width - 1 {
+                let idx = map.index_from_xy(x, y);
+                map.tiles[idx] = TileType::Floor;
+                map.revealed_tiles[idx] = x < 8;
+            }
+        }
+        let stairs = map.index_from_xy(14, 8);
+        map.tiles[stairs] = TileType::DownStairs;
+        map.bloodstains.insert(map.index_from_xy(3, 3));
+        map.notes.insert(stairs, "Way down".to_string());
+
+        let player_pos = Position { x: 2, y: 2 };
+        let player = spawner::player(&mut ecs, player_pos);
+        ecs.insert(player);
+        ecs.insert(player_pos);
+
+        // The samples bring a player of their own, which isn't wanted here
+        let samples = spawner::spawn_samples(&mut ecs);
+        ecs.delete_entity(samples[0]).unwrap();
+        {
+            let mut positions = ecs.write_storage::<Position>();
+            let mut backpacks = ecs.write_storage::<InBackpack>();
+            let mut equipped = ecs.write_storage::<Equipped>();
+            let items = ecs.read_storage::<Item>();
+            let equippables = ecs.read_storage::<Equippable>();
+            for (i, sample) in samples.iter().skip(1).enumerate() {
+                match (equippables.get(*sample), items.contains(*sample)) {
+                    (Some(equippable), _) if i % 2 == 0 => {
+                        positions.remove(*sample);
+                        equipped
+                            .insert(
+                                *sample,
+                                Equipped {
+                                    owner: player,
+                                    slot: equippable.slot,
+                                },
+                            )
+                            .unwrap();
+                    }
+                    (_, true) if i % 3 == 0 => {
+                        positions.remove(*sample);
+                        backpacks
+                            .insert(*sample, InBackpack { owner: player })
+                            .unwrap();
+                    }
+                    _ => {
+                        if let Some(pos) = positions.get_mut(*sample) {
+                            pos.x = 1 + (i % 14) as u16;
+                            pos.y = 4 + (i / 14) as u16;
+                        }
+                    }
+                }
+            }
+        }
+        ecs.insert(map);
+
+        // Intents and passing states, usually gone by the end of a turn but saved all the same
+        let first = |ecs: &World, with: fn(&World, Entity) -> bool| {
+            samples[1..]
+                .iter()
+                .copied()
+                .find(|sample| with(ecs, *sample))
+                .unwrap()
+        };
+        let monster = first(&ecs, |ecs, e| ecs.read_storage::<Monster>().contains(e));
+        let door = first(&ecs, |ecs, e| ecs.read_storage::<Door>().contains(e));
+        let packed = first(&ecs, |ecs, e| ecs.read_storage::<InBackpack>().contains(e));
+        let gear = first(&ecs, |ecs, e| ecs.read_storage::<Equipped>().contains(e));
+        let loose = first(&ecs, |ecs, e| {
+            ecs.read_storage::<Item>().contains(e) && ecs.read_storage::<Position>().contains(e)
+        });
+        ecs.write_storage::<SufferDamage>()
+            .insert(
+                player,
+                SufferDamage {
+                    amount: vec![2],
+                    causes: vec!["Orc".to_string()],
+                    by_player: false,
+                },
+            )
+            .unwrap();
+        ecs.write_storage::<WantsToMelee>()
+            .insert(player, WantsToMelee { target: monster })
+            .unwrap();
+        ecs.write_storage::<WantsToPickupItem>()
+            .insert(
+                player,
+                WantsToPickupItem {
+                    collected_by: player,
+                    item: loose,
+                },
+            )
+            .unwrap();
+        ecs.write_storage::<WantsToUseItem>()
+            .insert(
+                player,
+                WantsToUseItem {
+                    item: packed,
+                    target: Some(Position { x: 5, y: 5 }),
+                },
+            )
+            .unwrap();
+        ecs.write_storage::<WantsToDropItem>()
+            .insert(
+                player,
+                WantsToDropItem {
+                    item: packed,
+                    position: player_pos,
+                },
+            )
+            .unwrap();
+        ecs.write_storage::<WantsToUnequipItem>()
+            .insert(player, WantsToUnequipItem { item: gear })
+            .unwrap();
+        ecs.write_storage::<WantsToOpenDoor>()
+            .insert(player, WantsToOpenDoor { door })
+            .unwrap();
+        ecs.write_storage::<WantsToCloseDoor>()
+            .insert(player, WantsToCloseDoor { door })
+            .unwrap();
+        ecs.write_storage::<EntityMoved>()
+            .insert(player, EntityMoved {})
+            .unwrap();
+        ecs.write_storage::<WantsToDescend>()
+            .insert(player, WantsToDescend {})
+            .unwrap();
+        ecs.write_storage::<Alerted>()
+            .insert(monster, Alerted { turns: 3 })
+            .unwrap();
+
+        // Enchantments are rolled at random, so the gear gets them by hand
+        ecs.write_storage::<Rarity>()
+            .insert(
+                gear,
+                Rarity {
+                    tier: RarityTier::Magic,
+                },
+            )
+            .unwrap();
+        ecs.write_storage::<Resistance>()
+            .insert(gear, Resistance { percent: 10 })
+            .unwrap();
+        ecs.write_storage::<LifeRegen>()
+            .insert(
+                gear,
+                LifeRegen {
+                    interval: 5,
+                    countdown: 2,
+                },
+            )
+            .unwrap();
+        ecs.write_storage::<VisionBonus>()
+            .insert(gear, VisionBonus { range: 2 })
+            .unwrap();
+        ecs.create_entity()
+            .with(Position { x: 6, y: 6 })
+            .with(Particle {})
+            .with(Lifetime { lifetime_ms: 200.0 })
+            .marked::<SimpleMarker<SerializeMe>>()
+            .build();
+
+        let mut gamelog = GameLog::new();
+        gamelog.log(LogCategory::System, "The corpus begins.");
+        gamelog.next_turn();
+        gamelog.push(
+            LogEntry::new(LogCategory::Combat)
+                .text("Orc hits you for ")
+                .damage(3),
+        );
+        ecs.insert(gamelog);
+        ecs
+    }
+
+    #[test]
+    fn corpus_loads() {
+        for (version, data) in CORPUS {
+            let mut ecs = empty_world();
+            if let Err(e) = load_from_str(&mut ecs, data) {
+                panic!("version {} doesn't load: {}", version, e);
+            }
+
+            let player = *ecs.fetch::<Entity>();
+            assert_eq!(ecs.read_storage::<Player>().join().count(), 1);
+            assert_eq!(
+                ecs.read_storage::<Name>().get(player).unwrap().name,
+                "Player"
+            );
+            let backpacks = ecs.read_storage::<InBackpack>();
+            assert!(backpacks.join().count() > 0);
+            assert!(backpacks.join().all(|pack| pack.owner == player));
+            let equipped = ecs.read_storage::<Equipped>();
+            assert!(equipped.join().count() > 0);
+            assert!(equipped.join().all(|gear| gear.owner == player));
+            assert!(ecs.read_storage::<Monster>().join().count() > 0);
+            assert!(ecs.read_storage::<RunStats>().contains(player));
+            assert!(ecs.read_storage::<Wallet>().contains(player));
+        }
+    }
+
+    #[test]
+    fn sample_saves_load() {
+        // Every version from the second on was written from the sample world
+        for (_, data) in &CORPUS[1..] {
+            let mut ecs = empty_world();
+            load_from_str(&mut ecs, data).unwrap();
+            assert!(*ecs.fetch::<Position>() == Position { x: 2, y: 2 });
+
+            let map = ecs.fetch::<Map>();
+            assert_eq!((map.width, map.height, map.depth), (16, 10, 3));
+            assert!(map.tiles[map.index_from_xy(14, 8)] == TileType::DownStairs);
+            assert_eq!(map.notes.len(), 1);
+            assert_eq!(ecs.fetch::<GameLog>().turn, 1);
+            assert_eq!(ecs.fetch::<GameLog>().entries().count(), 2);
+        }
+    }
+
+    #[test]
+    fn original_save_loads() {
+        let (_, original) = CORPUS[0];
+        assert!(!original.contains('\n'));
+        let mut ecs = empty_world();
+        load_from_str(&mut ecs, original).unwrap();
+
+        let map = ecs.fetch::<Map>();
+        assert_eq!((map.width, map.height, map.depth), (80, 43, 1));
+        assert!(map.notes.is_empty());
+        let gamelog = ecs.fetch::<GameLog>();
+        assert_eq!(gamelog.entries().count(), 3);
+        assert!(gamelog
+            .entries()
+            .all(|entry| entry.turn == 0 && entry.category == LogCategory::System));
+
+        let player = *ecs.fetch::<Entity>();
+        let run = ecs.read_storage::<RunStats>().get(player).cloned().unwrap();
+        assert!(run.deepest_depth == 1 && run.mode == GameMode::Casual && run.kills.is_empty());
+        assert_eq!(ecs.read_storage::<Wallet>().get(player).unwrap().gold, 0);
+        let damage = ecs.read_storage::<SufferDamage>();
+        let damage = damage.join().next().unwrap();
+        assert!(damage.amount == vec![2] && damage.causes.is_empty() && !damage.by_player);
+    }
+
+    #[test]
+    fn original_save_is_imported_once() {
+        let path = save_slots::game_dir().join("savegame.json");
+        let put_aside = path.with_extension("json.imported");
+        fs::create_dir_all(save_slots::game_dir()).unwrap();
+        save_slots::delete_slot(IMPORTED_SLOT).unwrap();
+        let _ = fs::remove_file(&put_aside);
+        fs::write(&path, CORPUS[0].1).unwrap();
+
+        assert!(import_original_save(&path, SaveFormat::default()).unwrap());
+        assert!(!path.exists() && put_aside.exists());
+        let header = save_slots::read_header(IMPORTED_SLOT).unwrap();
+        assert!(header.version == SAVE_VERSION && header.depth == 1 && header.checksum != 0);
+        load_game(&mut empty_world(), IMPORTED_SLOT).unwrap();
+
+        // A second old save doesn't replace the first one
+        fs::write(&path, CORPUS[0].1).unwrap();
+        assert!(!import_original_save(&path, SaveFormat::default()).unwrap());
+        assert!(path.exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn corpus_covers_current_version() {
+        assert_eq!(CORPUS.last().unwrap().0, SAVE_VERSION);
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let (_, body) = CORPUS.last().unwrap().1.split_once('\n').unwrap();
+        let data = format!(
+            "{{\"version\":{},\"character\":\"Player\",\"depth\":1,\"turns\":0,\"timestamp\":0,\"seed\":0}}\n{}",
+            SAVE_VERSION + 1,
+            body
+        );
+        assert!(matches!(
+            load_from_str(&mut empty_world(), &data),
+            Err(SaveError::NewerVersion(_))
+        ));
+    }
+
+    // Saves the sample world after breaking it, then loads that save over an intact world
+    fn load_broken(break_world: fn(&mut World)) -> Result<(), SaveError> {
+        let mut broken = sample_world();
+        break_world(&mut broken);
+        let data = save_to_string(&mut broken).unwrap();
+
+        let mut ecs = sample_world();
+        let result = load_from_str(&mut ecs, &data);
+        if result.is_err() {
+            // The world that was there before is still whole
+            let player = *ecs.fetch::<Entity>();
+            assert!(ecs.read_storage::<Player>().contains(player));
+            assert_eq!(ecs.fetch::<Map>().tiles.len(), 16 * 10);
+        }
+        result
+    }
+
+    #[test]
+    fn a_second_player_is_refused() {
+        let result = load_broken(|ecs| {
+            spawner::player(ecs, Position { x: 3, y: 3 });
+        });
+        assert!(matches!(result, Err(SaveError::PlayerCount(2))));
+    }
+
+    #[test]
+    fn a_map_of_the_wrong_size_is_refused() {
+        let result = load_broken(|ecs| {
+            ecs.fetch_mut::<Map>().tiles.pop();
+        });
+        assert!(matches!(result, Err(SaveError::MapSize { .. })));
+    }
+
+    #[test]
+    fn items_without_an_owner_are_refused() {
+        let data = save_to_bytes(&mut sample_world(), SaveFormat::default()).unwrap();
+        let (header, body) = save_format::decode(&data).unwrap();
+        let mut components = migrate(header.version, body).unwrap();
+
+        // Hand the backpack to an entity that was never saved
+        for entry in components["InBackpack"].as_array_mut().unwrap() {
+            if let Some(pack) = entry["components"][0].as_object_mut() {
+                pack.insert("owner".to_string(), serde_json::json!([9999]));
+            }
+        }
+        // Encoded again so the checksum matches and the save gets as far as being validated
+        let data = SaveFormat::default()
+            .encode(&header, &Value::Object(components))
+            .unwrap();
+        assert!(matches!(
+            load_from_bytes(&mut empty_world(), &data),
+            Err(SaveError::MissingOwner(_))
+        ));
+    }
+
+    #[test]
+    fn version_2_damage_loads_without_causes() {
+        let data = save_to_string(&mut sample_world()).unwrap();
+        let (header, body) = data.split_once('\n').unwrap();
+        let mut header: SlotHeader = serde_json::from_str(header).unwrap();
+        header.version = 2;
+        header.checksum = 0;
+        let mut components: TaggedComponents = serde_json::from_str(body).unwrap();
+        for entry in components["SufferDamage"].as_array_mut().unwrap() {
+            if let Some(damage) = entry["components"][0].as_object_mut() {
+                damage.remove("causes");
+            }
+        }
+        let data = format!(
+            "{}\n{}",
+            serde_json::to_string(&header).unwrap(),
+            Value::Object(components)
+        );
+
+        let mut world = empty_world();
+        load_from_str(&mut world, &data).unwrap();
+        let damage = world.read_storage::<SufferDamage>();
+        let damage = damage.join().next().unwrap();
+        assert!(damage.amount == vec![2] && damage.causes.is_empty());
+    }
+
+    #[test]
+    fn edited_saves_are_refused() {
+        let data = save_to_string(&mut sample_world()).unwrap();
+        let edited = data.replacen("\"deepest_depth\":1", "\"deepest_depth\":9", 1);
+        assert!(edited != data);
+        assert!(matches!(
+            load_from_str(&mut empty_world(), &edited),
+            Err(SaveError::Tampered)
+        ));
+
+        for format in FORMATS {
+            let mut data = save_to_bytes(&mut sample_world(), format).unwrap();
+            let last = data.len() - 2;
+            data[last] ^= 1;
+            assert!(load_from_bytes(&mut empty_world(), &data).is_err());
+        }
+    }
+
+    #[test]
+    fn strict_saves_need_a_checksum() {
+        let mut world = sample_world();
+        let player = *world.fetch::<Entity>();
+        world
+            .write_storage::<RunStats>()
+            .get_mut(player)
+            .unwrap()
+            .mode = GameMode::Strict;
+        let data = save_to_string(&mut world).unwrap();
+        assert!(load_from_str(&mut empty_world(), &data).is_ok());
+
+        let (header, body) = data.split_once('\n').unwrap();
+        let mut header: SlotHeader = serde_json::from_str(header).unwrap();
+        header.checksum = 0;
+        let data = format!("{}\n{}", serde_json::to_string(&header).unwrap(), body);
+        assert!(matches!(
+            load_from_str(&mut empty_world(), &data),
+            Err(SaveError::Tampered)
+        ));
+    }
+
+    #[test]
+    fn strict_saves_load_once() {
+        let mut world = sample_world();
+        let player = *world.fetch::<Entity>();
+        {
+            let mut run_stats = world.write_storage::<RunStats>();
+            let run = run_stats.get_mut(player).unwrap();
+            run.mode = GameMode::Strict;
+            run.seed = 0x5eed;
+        }
+        world.insert(Settings {
+            autosave_turns: 1,
+            ..Settings::default()
+        });
+        world.insert(autosave::Autosave::default());
+        let _slot = autosave::SLOT_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        save_slots::delete_slot(autosave::AUTOSAVE_SLOT).unwrap();
+
+        save_game(&mut world, "strict").unwrap();
+        // Playing on leaves no autosave to go back to, only a level change writes one
+        autosave::autosave_if_due(&mut world);
+        autosave::wait_for_writer(&mut world);
+        assert!(save_slots::existing_slot_path(autosave::AUTOSAVE_SLOT).is_none());
+
+        load_game(&mut empty_world(), "strict").unwrap();
+        assert!(save_slots::existing_slot_path("strict").is_none());
+        assert!(load_game(&mut empty_world(), "strict").is_err());
+    }
+
+    #[test]
+    fn broken_saves_are_errors() {
+        for (_, data) in CORPUS {
+            let truncated = &data[..data.len() / 2];
+            assert!(load_from_str(&mut empty_world(), truncated).is_err());
+        }
+        assert!(load_from_str(&mut empty_world(), "").is_err());
+
+        let (header, _) = CORPUS.last().unwrap().1.split_once('\n').unwrap();
+        let wrong_shape = format!(
+            "{}\n{{\"Position\":[{{\"marker\":[0],\"components\":[7]}}]}}",
+            header
+        );
+        assert!(load_from_str(&mut empty_world(), &wrong_shape).is_err());
+    }
+
+    // The entities that have the component, by marker, and what it holds for each of them
+    fn instances(components: &TaggedComponents, tag: &str) -> Vec<(String, Value)> {
+        let mut instances: Vec<(String, Value)> = components[tag]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|entry| !entry["components"][0].is_null())
+            .map(|entry| {
+                // Helpers are made anew for every save, so only what they hold counts
+                let marker = if tag.ends_with("SerializationHelper") {
+                    String::new()
+                } else {
+                    entry["marker"].to_string()
+                };
+                (marker, entry["components"][0].clone())
+            })
+            .collect();
+        instances.sort_by(|a, b| a.0.cmp(&b.0));
+        instances
+    }
+
+    #[test]
+    fn sample_world_has_every_component() {
+        let components = snapshot(&mut sample_world()).unwrap();
+        for tag in SAVED_COMPONENTS {
+            assert!(!instances(&components, tag).is_empty(), "no {}", tag);
+        }
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        for format in FORMATS {
+            let mut ecs = sample_world();
+            let saved = snapshot(&mut ecs).unwrap();
+            let data = save_to_bytes(&mut ecs, format).unwrap();
+
+            let mut loaded = empty_world();
+            load_from_bytes(&mut loaded, &data).unwrap();
+            let reloaded = snapshot(&mut loaded).unwrap();
+            for tag in SAVED_COMPONENTS {
+                assert_eq!(
+                    instances(&saved, tag),
+                    instances(&reloaded, tag),
+                    "{} in {:?}",
+                    tag,
+                    format
+                );
+            }
+
+            let header = save_format::decode_header(&data).unwrap();
+            assert_eq!((header.version, header.depth), (SAVE_VERSION, 3));
+        }
+    }
+
+    #[test]
+    fn binary_and_compressed_saves_are_smaller() {
+        let sizes: Vec<usize> = FORMATS
+            .iter()
+            .map(|format| save_to_bytes(&mut sample_world(), *format).unwrap().len())
+            .collect();
+        let [json, compressed_json, binary, compressed_binary] = sizes[..] else {
+            unreachable!()
+        };
+        assert!(binary < json);
+        assert!(compressed_json < json);
+        assert!(compressed_binary < binary);
+    }
+
+    #[test]
+    fn broken_binary_saves_are_errors() {
+        for format in &FORMATS[1..] {
+            let data = save_to_bytes(&mut sample_world(), *format).unwrap();
+            let truncated = &data[..data.len() / 2];
+            assert!(load_from_bytes(&mut empty_world(), truncated).is_err());
+        }
+        assert!(matches!(
+            load_from_bytes(&mut empty_world(), &[0xff, 0xfe, 0x00]),
+            Err(SaveError::Unrecognized)
+        ));
+    }
+
+    /// Adds the corpus file for the current version: cargo test write_save_corpus -- --ignored
+    #[test]
+    #[ignore]
+    fn write_save_corpus() {
+        let mut ecs = sample_world();
+        let path = format!(
+            "{}/tests/saves/v{}.json",
+            env!("CARGO_MANIFEST_DIR"),
+            SAVE_VERSION
+        );
+        fs::write(path, save_to_string(&mut ecs).unwrap()).unwrap();
+    }
 }
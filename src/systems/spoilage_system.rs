@@ -0,0 +1,50 @@
+use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
+
+use crate::{
+    components::{Freshness, InBackpack, Name},
+    gamelog::{GameLog, LogCategory},
+    state::RunState,
+};
+
+#[derive(Clone, Copy)]
+pub struct SpoilageSystem;
+
+impl<'a> System<'a> for SpoilageSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        WriteStorage<'a, Freshness>,
+        ReadStorage<'a, InBackpack>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, player, run_state, mut freshness, backpack, names, mut gamelog) = data;
+
+        // Food spoils once per game turn
+        if *run_state != RunState::PlayerTurn {
+            return;
+        }
+
+        for (entity, freshness) in (&entities, &mut freshness).join() {
+            if freshness.turns > 0 {
+                freshness.turns -= 1;
+                continue;
+            }
+
+            if let (Some(carried), Some(name)) = (backpack.get(entity), names.get(entity)) {
+                if carried.owner == *player {
+                    gamelog.log(
+                        LogCategory::Loot,
+                        format!("Your {} has rotted away.", name.name),
+                    );
+                }
+            }
+            entities
+                .delete(entity)
+                .expect("Unable to delete spoiled food");
+        }
+    }
+}
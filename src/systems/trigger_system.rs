@@ -1,25 +1,44 @@
-use rltk::RGB;
-use specs::{Entities, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
+use rltk::{RandomNumberGenerator, RGB};
+use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
     components::{
-        EntityMoved, EntryTrigger, Hidden, InflictsDamage, Name, Position, SingleActivation,
-        SufferDamage,
+        Alarm, Alerted, AreaOfEffect, CombatStats, Confusion, EntityMoved, EntryTrigger, Hidden,
+        InflictsDamage, Monster, Name, Pit, Position, SingleActivation, SufferDamage, Teleports,
+        Viewshed, WantsToDescend,
     },
-    gamelog::GameLog,
-    map::map::Map,
+    gamelog::{GameLog, LogCategory},
+    map::map::{Map, TileType},
 };
 
 use super::particle_system::ParticleBuilder;
 
+// How many turns monsters keep hunting the player after an alarm
+const ALARM_DURATION: u32 = 20;
+
+/// Traps set off without anyone stepping on them, they go off the next time the systems run
+#[derive(Default)]
+pub struct SprungTraps {
+    requests: Vec<(Entity, Entity)>,
+}
+
+impl SprungTraps {
+    /// The trap goes off on the victim as if they had stepped onto it
+    pub fn request(&mut self, victim: Entity, trap: Entity) {
+        self.requests.push((victim, trap));
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct TriggerSystem {}
 
 impl<'a> System<'a> for TriggerSystem {
     type SystemData = (
         ReadExpect<'a, Map>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, Position>,
         WriteStorage<'a, EntityMoved>,
-        ReadStorage<'a, Position>,
+        WriteStorage<'a, Position>,
         ReadStorage<'a, EntryTrigger>,
         WriteStorage<'a, Hidden>,
         ReadStorage<'a, Name>,
@@ -29,13 +48,27 @@ impl<'a> System<'a> for TriggerSystem {
         WriteStorage<'a, SufferDamage>,
         WriteExpect<'a, ParticleBuilder>,
         ReadStorage<'a, SingleActivation>,
+        ReadStorage<'a, AreaOfEffect>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, Confusion>,
+        ReadStorage<'a, Teleports>,
+        ReadStorage<'a, Alarm>,
+        ReadStorage<'a, Pit>,
+        ReadStorage<'a, Monster>,
+        WriteStorage<'a, Alerted>,
+        WriteStorage<'a, WantsToDescend>,
+        WriteStorage<'a, Viewshed>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        WriteExpect<'a, SprungTraps>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (
             map,
+            player_entity,
+            mut player_pos,
             mut entity_moved,
-            positions,
+            mut positions,
             entry_triggers,
             mut hidden,
             names,
@@ -45,10 +78,23 @@ impl<'a> System<'a> for TriggerSystem {
             mut suffer_damage,
             mut particle_builder,
             single_activation,
+            aoe,
+            mut combat_stats,
+            mut confusion,
+            teleports,
+            alarms,
+            pits,
+            monsters,
+            mut alerted,
+            mut wants_to_descend,
+            mut viewsheds,
+            mut rng,
+            mut sprung_traps,
         ) = data;
 
-        let mut remove_entities = Vec::new();
-        for (entity, mut _entity_moved, pos) in (&entities, &mut entity_moved, &positions).join() {
+        // Find out who stepped on which trap
+        let mut activations = Vec::new();
+        for (entity, _entity_moved, pos) in (&entities, &entity_moved, &positions).join() {
             let idx = map.index_from_xy(pos.x, pos.y);
             for entity_id in map.tile_content[idx].iter() {
                 // No need to check itself
@@ -56,38 +102,181 @@ impl<'a> System<'a> for TriggerSystem {
                     continue;
                 }
 
-                if let Some(_trigger) = entry_triggers.get(*entity_id) {
-                    // Entity is no longer hidden
-                    hidden.remove(*entity_id);
+                if entry_triggers.contains(*entity_id) {
+                    activations.push((entity, *entity_id, *pos));
+                }
+            }
+        }
+        for (victim, trap) in sprung_traps.requests.drain(..) {
+            if let Some(pos) = positions.get(trap) {
+                activations.push((victim, trap, *pos));
+            }
+        }
+
+        let mut remove_entities = Vec::new();
+        for (victim, trap, pos) in activations {
+            // A sprung trap goes off next to its victim rather than under them
+            let victim_pos = positions.get(victim).copied().unwrap_or(pos);
+
+            // Entity is no longer hidden
+            hidden.remove(trap);
+
+            if let Some(name) = names.get(trap) {
+                gamelog.log(LogCategory::Combat, format!("{} triggers!", &name.name));
+            }
 
-                    if let Some(name) = names.get(*entity_id) {
-                        gamelog.entries.push(format!("{} triggers!", &name.name));
+            if let Some(damage) = inflicts_damage.get(trap) {
+                let victims = match aoe.get(trap) {
+                    None => vec![(victim, victim_pos)],
+                    Some(area) => {
+                        let mut victims =
+                            blast_victims(&map, pos, area.radius, &positions, &combat_stats);
+                        // The victim has just moved here, so the map index doesn't know about it yet
+                        if !victims.iter().any(|(target, _)| *target == victim) {
+                            victims.push((victim, victim_pos));
+                        }
+                        victims
                     }
+                };
+                for (target, target_pos) in victims {
+                    particle_builder.request(
+                        target_pos.x,
+                        target_pos.y,
+                        RGB::named(rltk::ORANGE),
+                        RGB::named(rltk::BLACK),
+                        rltk::to_cp437('‼'),
+                        200.0,
+                    );
+                    SufferDamage::new_damage(
+                        &mut suffer_damage,
+                        target,
+                        damage.damage,
+                        names.get(trap).map_or("a trap", |name| &name.name),
+                        false,
+                    );
+                }
+            }
 
-                    if let Some(damage) = inflicts_damage.get(*entity_id) {
-                        particle_builder.request(
-                            pos.x,
-                            pos.y,
-                            RGB::named(rltk::ORANGE),
-                            RGB::named(rltk::BLACK),
-                            rltk::to_cp437('‼'),
-                            200.0,
-                        );
-                        SufferDamage::new_damage(&mut suffer_damage, entity, damage.damage);
+            if let Some(gas) = confusion.get(trap).cloned() {
+                if combat_stats.contains(victim) {
+                    confusion
+                        .insert(victim, gas)
+                        .expect("Unable to insert status");
+                    particle_builder.request(
+                        victim_pos.x,
+                        victim_pos.y,
+                        RGB::named(rltk::MAGENTA),
+                        RGB::named(rltk::BLACK),
+                        rltk::to_cp437('?'),
+                        200.0,
+                    );
+                }
+            }
+
+            if alarms.contains(trap) {
+                gamelog.log(
+                    LogCategory::Status,
+                    "A loud alarm echoes through the dungeon!",
+                );
+                for (monster, _) in (&entities, &monsters).join() {
+                    alerted
+                        .insert(
+                            monster,
+                            Alerted {
+                                turns: ALARM_DURATION,
+                            },
+                        )
+                        .expect("Unable to alert monster");
+                }
+            }
+
+            if teleports.contains(trap) {
+                if let Some(destination) = random_free_tile(&map, &mut rng) {
+                    if let Some(victim_pos) = positions.get_mut(victim) {
+                        *victim_pos = destination;
+                    }
+                    if let Some(viewshed) = viewsheds.get_mut(victim) {
+                        viewshed.dirty = true;
+                    }
+                    if victim == *player_entity {
+                        *player_pos = destination;
+                        gamelog.log(LogCategory::Status, "The world twists around you!");
                     }
+                }
+            }
 
-                    if let Some(_) = single_activation.get(*entity_id) {
-                        remove_entities.push(*entity_id);
+            if pits.contains(trap) {
+                if victim == *player_entity {
+                    gamelog.log(LogCategory::Status, "The floor gives way and you fall!");
+                    wants_to_descend
+                        .insert(victim, WantsToDescend {})
+                        .expect("Unable to insert descend intent");
+                } else {
+                    if let Some(name) = names.get(victim) {
+                        gamelog.log(
+                            LogCategory::Combat,
+                            format!("{} falls into the pit!", &name.name),
+                        );
+                    }
+                    // Nothing comes back up, the monster dies and what it had lands on the pit
+                    if let Some(stats) = combat_stats.get_mut(victim) {
+                        stats.hp = 0;
                     }
                 }
             }
+
+            if single_activation.contains(trap) {
+                remove_entities.push(trap);
+            }
         }
 
-        // Remove all single activation entities
-        for trap in remove_entities {
-            entities.delete(trap).expect("Unable to delete entity");
+        // Remove all single activation traps
+        for entity in remove_entities {
+            entities.delete(entity).expect("Unable to delete entity");
         }
         // Remove all entity movement markers
         entity_moved.clear();
     }
 }
+
+fn blast_victims(
+    map: &Map,
+    center: Position,
+    radius: u16,
+    positions: &WriteStorage<Position>,
+    combat_stats: &WriteStorage<CombatStats>,
+) -> Vec<(Entity, Position)> {
+    let mut victims = Vec::new();
+    let blast_tiles = rltk::field_of_view(center.into(), radius as i32, map);
+    for tile in blast_tiles.iter() {
+        if tile.x < 0 || tile.x >= map.width as i32 || tile.y < 0 || tile.y >= map.height as i32 {
+            continue;
+        }
+        let idx = map.index_from_xy(tile.x as u16, tile.y as u16);
+        for entity in map.tile_content[idx].iter() {
+            if !combat_stats.contains(*entity) {
+                continue;
+            }
+            if let Some(pos) = positions.get(*entity) {
+                victims.push((*entity, *pos));
+            }
+        }
+    }
+    victims
+}
+
+fn random_free_tile(map: &Map, rng: &mut RandomNumberGenerator) -> Option<Position> {
+    let free_tiles: Vec<usize> = (0..map.tiles.len())
+        .filter(|idx| {
+            map.tiles[*idx] == TileType::Floor
+                && !map.blocked[*idx]
+                && !map.view_blocked.contains(idx)
+        })
+        .collect();
+    if free_tiles.is_empty() {
+        return None;
+    }
+    let idx = free_tiles[rng.roll_dice(1, free_tiles.len() as i32) as usize - 1];
+    let (x, y) = map.xy_from_index(&idx);
+    Some(Position { x, y })
+}
@@ -2,8 +2,8 @@ use rltk::field_of_view;
 use specs::prelude::*;
 use specs::{System, WriteStorage};
 
-use crate::components::{Hidden, Name, Player, Position, Viewshed};
-use crate::gamelog::GameLog;
+use crate::components::{Equipped, Hidden, Name, Player, Position, Viewshed, VisionBonus};
+use crate::gamelog::{GameLog, LogCategory};
 use crate::map::map::Map;
 
 #[derive(Clone, Copy)]
@@ -20,21 +20,43 @@ impl<'a> System<'a> for VisibilitySystem {
         WriteExpect<'a, rltk::RandomNumberGenerator>,
         WriteExpect<'a, GameLog>,
         ReadStorage<'a, Name>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, VisionBonus>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, entities, mut viewshed, pos, player, mut hidden, mut rng, mut gamelog, names) =
-            data;
+        let (
+            mut map,
+            entities,
+            mut viewshed,
+            pos,
+            player,
+            mut hidden,
+            mut rng,
+            mut gamelog,
+            names,
+            equipped,
+            vision_bonuses,
+        ) = data;
         for (ent, viewshed, pos) in (&entities, &mut viewshed, &pos).join() {
             if !viewshed.dirty {
                 continue;
             }
+            let mut range = viewshed.range;
+            for (equipped_by, bonus) in (&equipped, &vision_bonuses).join() {
+                if equipped_by.owner == ent {
+                    range += bonus.range;
+                }
+            }
+
             viewshed.dirty = false;
             viewshed.visible_tiles.clear();
-            viewshed.visible_tiles = field_of_view(pos.into(), viewshed.range as i32, &*map);
+            viewshed.visible_tiles = field_of_view(pos.into(), range as i32, &*map);
             viewshed.visible_tiles.retain(|p| {
                 p.x >= 0 && p.x < map.width as i32 && p.y >= 0 && p.y < map.height as i32
             });
+            // The field of view comes out of a hash set, sorted it reads the same in a replay
+            viewshed.visible_tiles.sort_by_key(|p| (p.y, p.x));
 
             // If this is the player, reveal what the can see
             let p: Option<&Player> = player.get(ent);
@@ -52,9 +74,10 @@ impl<'a> System<'a> for VisibilitySystem {
                         if let Some(_hidden) = hidden.get(*e) {
                             if rng.roll_dice(1, 24) == 1 {
                                 if let Some(name) = names.get(*e) {
-                                    gamelog
-                                        .entries
-                                        .push(format!("You spotted a {}.", name.name));
+                                    gamelog.log(
+                                        LogCategory::Status,
+                                        format!("You spotted a {}.", name.name),
+                                    );
                                 }
                                 hidden.remove(*e);
                             }